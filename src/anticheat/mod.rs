@@ -0,0 +1,573 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    fmt,
+    time::{Duration, Instant},
+};
+
+use valence::{
+    block::BlockKind,
+    log::warn,
+    movement::MovementEvent,
+    prelude::*,
+    text::{Color, IntoText},
+};
+
+use crate::{
+    commands::teleport::apply::TeleportedEvent,
+    server::shutdown,
+    setup::settings::{AntiCheatSettings, Settings},
+    world::BlockView,
+};
+
+/// Speed is averaged over this window
+const SPEED_WINDOW: Duration = Duration::from_secs(1);
+const EYE_HEIGHT: f64 = 1.62;
+/// Heights above the feet where a player's body is checked against blocks
+const BODY_HEIGHTS: [f64; 2] = [0.1, 1.5];
+/// Half the width of a player's hitbox, just under 0.3 so a player flush
+/// against a wall doesn't count as standing on it
+const HALF_WIDTH: f64 = 0.299;
+/// How far below a player's feet a block may end and still hold them up
+const GROUND_TOLERANCE: f64 = 0.05;
+
+/// Blocks players can move up and down through without touching the ground
+const CLIMBABLE: &[BlockKind] = &[
+    BlockKind::Ladder,
+    BlockKind::Vine,
+    BlockKind::Scaffolding,
+    BlockKind::Cobweb,
+    BlockKind::PowderSnow,
+    BlockKind::BubbleColumn,
+    BlockKind::TwistingVines,
+    BlockKind::TwistingVinesPlant,
+    BlockKind::WeepingVines,
+    BlockKind::WeepingVinesPlant,
+    BlockKind::CaveVines,
+    BlockKind::CaveVinesPlant,
+];
+
+/// A check a player can fail
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Check {
+    Speed,
+    Fly,
+    NoClip,
+    Reach,
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Check::Speed => "speed",
+            Check::Fly => "fly",
+            Check::NoClip => "noclip",
+            Check::Reach => "reach",
+        })
+    }
+}
+
+/// Sent whenever a player fails a check, after the action was undone.
+#[derive(Event, Clone, Debug)]
+pub struct ViolationEvent {
+    pub player: Entity,
+    pub check: Check,
+    pub detail: String,
+}
+
+/// What the movement checks remember about a player between packets.
+#[derive(Component, Debug)]
+pub struct MovementState {
+    /// The last position that passed every check, illegal moves are sent
+    /// back here
+    last_valid: DVec3,
+    /// The height the player last stood, swam or climbed at
+    ground_y: f64,
+    /// Ticks spent in the air without falling
+    hover_ticks: u32,
+    /// Set when bouncing off a slime block, which can throw a player higher
+    /// than a jump until they land again
+    bounced: bool,
+    /// Horizontal distance moved per packet within the speed window
+    recent: VecDeque<(Instant, f64)>,
+    game_mode: GameMode,
+}
+
+impl MovementState {
+    fn new(pos: DVec3, game_mode: GameMode) -> Self {
+        Self {
+            last_valid: pos,
+            ground_y: pos.y,
+            hover_ticks: 0,
+            bounced: false,
+            recent: VecDeque::new(),
+            game_mode,
+        }
+    }
+}
+
+/// When a player recently failed checks.
+#[derive(Component, Default, Debug)]
+pub struct Violations(VecDeque<Instant>);
+
+pub fn init_movement_state(
+    mut commands: Commands,
+    clients: Query<(Entity, &Position, &GameMode), Added<Client>>,
+) {
+    for (entity, pos, game_mode) in &clients {
+        commands
+            .entity(entity)
+            .insert((MovementState::new(pos.0, *game_mode), Violations::default()));
+    }
+}
+
+/// Teleports move players further than any check allows, so they start over
+/// from the destination.
+pub fn reset_on_teleport(
+    mut events: EventReader<TeleportedEvent>,
+    mut players: Query<(&Position, &GameMode, &mut MovementState)>,
+) {
+    for event in events.read() {
+        if let Ok((pos, game_mode, mut state)) = players.get_mut(event.entity) {
+            *state = MovementState::new(pos.0, *game_mode);
+        }
+    }
+}
+
+/// Whether a point relative to a block's corner is inside its collision box
+fn in_collision_box(state: BlockState, local: DVec3) -> bool {
+    state.collision_shapes().any(|shape| {
+        let (min, max) = (shape.min(), shape.max());
+        local.x > min.x
+            && local.x < max.x
+            && local.y > min.y
+            && local.y < max.y
+            && local.z > min.z
+            && local.z < max.z
+    })
+}
+
+/// Whether a point in the player's body is inside the collision box of a
+/// block
+fn inside_block(layer: &impl BlockView, point: DVec3) -> bool {
+    let block_pos = BlockPos::new(
+        point.x.floor() as i32,
+        point.y.floor() as i32,
+        point.z.floor() as i32,
+    );
+    let Some(state) = layer.state_at(block_pos) else {
+        return false;
+    };
+
+    in_collision_box(state, point - point.floor())
+}
+
+fn collides(layer: &impl BlockView, pos: DVec3) -> bool {
+    BODY_HEIGHTS
+        .iter()
+        .any(|height| inside_block(layer, pos + DVec3::Y * *height))
+}
+
+/// Whether a block holds up a player standing at `pos`, worked out from
+/// the blocks under their hitbox rather than what their client says.
+pub fn on_ground(layer: &impl BlockView, pos: DVec3) -> bool {
+    let feet = pos.y - GROUND_TOLERANCE;
+    let points = [(0.0, 0.0), (-1.0, -1.0), (-1.0, 1.0), (1.0, -1.0), (1.0, 1.0)];
+
+    points.iter().any(|(dx, dz)| {
+        let x = pos.x + dx * HALF_WIDTH;
+        let z = pos.z + dz * HALF_WIDTH;
+
+        // Fences and walls reach up into the block above them
+        (0..2).any(|below| {
+            let block_pos = BlockPos::new(
+                x.floor() as i32,
+                feet.floor() as i32 - below,
+                z.floor() as i32,
+            );
+            let Some(state) = layer.state_at(block_pos) else {
+                return false;
+            };
+
+            let local = DVec3::new(x - x.floor(), feet - f64::from(block_pos.y), z - z.floor());
+            in_collision_box(state, local)
+        })
+    })
+}
+
+/// Whether the player is somewhere they can rise or hover without flying
+pub fn can_climb(layer: &impl BlockView, pos: DVec3) -> bool {
+    BODY_HEIGHTS.iter().any(|height| {
+        let point = pos + DVec3::Y * *height;
+        let block_pos = BlockPos::new(
+            point.x.floor() as i32,
+            point.y.floor() as i32,
+            point.z.floor() as i32,
+        );
+
+        layer
+            .state_at(block_pos)
+            .is_some_and(|state| state.is_liquid() || CLIMBABLE.contains(&state.to_kind()))
+    })
+}
+
+fn standing_on(layer: &impl BlockView, pos: DVec3, kind: BlockKind) -> bool {
+    let below = BlockPos::new(
+        pos.x.floor() as i32,
+        (pos.y - 0.5).floor() as i32,
+        pos.z.floor() as i32,
+    );
+
+    layer
+        .state_at(below)
+        .is_some_and(|state| state.to_kind() == kind)
+}
+
+/// Checks a move to `to` against the thresholds, returning the failed check
+fn check_move(
+    settings: &AntiCheatSettings,
+    state: &mut MovementState,
+    layer: &impl BlockView,
+    to: DVec3,
+    game_mode: GameMode,
+) -> Option<(Check, String)> {
+    let delta = to - state.last_valid;
+
+    if game_mode != GameMode::Spectator && collides(layer, to) && !collides(layer, state.last_valid)
+    {
+        return Some((Check::NoClip, "moved into a block".to_owned()));
+    }
+
+    // Creative and spectator players can fly, and fly fast
+    if !matches!(game_mode, GameMode::Survival | GameMode::Adventure) {
+        return None;
+    }
+
+    let distance = delta.length();
+    if distance > settings.max_move {
+        return Some((Check::Speed, format!("moved {distance:.1} blocks at once")));
+    }
+
+    let now = Instant::now();
+    state
+        .recent
+        .retain(|(time, _)| now.duration_since(*time) < SPEED_WINDOW);
+    state
+        .recent
+        .push_back((now, (delta.x * delta.x + delta.z * delta.z).sqrt()));
+
+    let speed = state.recent.iter().map(|(_, distance)| distance).sum::<f64>();
+    if speed > settings.max_speed {
+        return Some((Check::Speed, format!("{speed:.1} blocks per second")));
+    }
+
+    // The client's own on_ground flag is never trusted
+    let grounded = on_ground(layer, to);
+    if grounded || can_climb(layer, to) {
+        state.ground_y = to.y;
+        state.hover_ticks = 0;
+        state.bounced = grounded && standing_on(layer, to, BlockKind::SlimeBlock);
+        return None;
+    }
+
+    let rise = to.y - state.ground_y;
+    if !state.bounced && rise > settings.max_jump_height {
+        return Some((Check::Fly, format!("rose {rise:.1} blocks off the ground")));
+    }
+
+    if delta.y >= 0.0 {
+        state.hover_ticks += 1;
+        if state.hover_ticks > settings.max_hover_ticks {
+            return Some((
+                Check::Fly,
+                format!("hovered for {} ticks", state.hover_ticks),
+            ));
+        }
+    } else {
+        state.hover_ticks = 0;
+    }
+
+    None
+}
+
+/// Sends players back to where they last moved legally when a move fails a
+/// check.
+///
+/// Once a player is sent back, the rest of their moves from the same tick
+/// are ignored, as they were made from the rejected position.
+pub fn validate_movement(
+    mut events: EventReader<MovementEvent>,
+    mut players: Query<(&mut Position, &GameMode, &EntityLayerId, &mut MovementState)>,
+    layers: Query<&ChunkLayer>,
+    settings: Res<Settings>,
+    mut violations: EventWriter<ViolationEvent>,
+) {
+    let Some(anticheat) = &settings.anticheat else {
+        return;
+    };
+
+    let mut rejected = HashSet::new();
+
+    for event in events.read() {
+        if rejected.contains(&event.client) {
+            continue;
+        }
+
+        let Ok((mut pos, game_mode, layer_id, mut state)) = players.get_mut(event.client) else {
+            continue;
+        };
+        let Ok(layer) = layers.get(layer_id.0) else {
+            continue;
+        };
+
+        // Switching game mode mid-air shouldn't count as flying
+        if state.game_mode != *game_mode {
+            *state = MovementState::new(state.last_valid, *game_mode);
+        }
+
+        match check_move(anticheat, &mut state, layer, event.position, *game_mode) {
+            None => state.last_valid = event.position,
+            Some((check, detail)) => {
+                pos.set(state.last_valid);
+                state.hover_ticks = 0;
+                state.recent.clear();
+                rejected.insert(event.client);
+
+                violations.send(ViolationEvent {
+                    player: event.client,
+                    check,
+                    detail,
+                });
+            }
+        }
+    }
+}
+
+/// How far `block` is from a player's eyes, if it's further than they may
+/// reach
+pub fn beyond_reach(settings: &Settings, pos: DVec3, block: BlockPos) -> Option<f64> {
+    let max_reach = settings.anticheat.as_ref()?.max_reach;
+
+    let eyes = pos + DVec3::Y * EYE_HEIGHT;
+    let centre = DVec3::new(
+        f64::from(block.x) + 0.5,
+        f64::from(block.y) + 0.5,
+        f64::from(block.z) + 0.5,
+    );
+    let distance = eyes.distance(centre);
+
+    (distance > max_reach).then_some(distance)
+}
+
+/// Logs violations and kicks players who fail too many checks within the
+/// configured window.
+pub fn handle_violations(
+    mut commands: Commands,
+    mut events: EventReader<ViolationEvent>,
+    mut players: Query<(&mut Client, &Username, &mut Violations)>,
+    settings: Res<Settings>,
+) {
+    let Some(anticheat) = &settings.anticheat else {
+        return;
+    };
+
+    for event in events.read() {
+        let Ok((mut client, username, mut violations)) = players.get_mut(event.player) else {
+            continue;
+        };
+
+        warn!(
+            "{} failed the {} check: {}",
+            username.0, event.check, event.detail
+        );
+
+        let now = Instant::now();
+        violations
+            .0
+            .retain(|time| now.duration_since(*time) < anticheat.violation_window);
+        violations.0.push_back(now);
+
+        let Some(kick_after) = anticheat.kick_after else {
+            continue;
+        };
+
+        if violations.0.len() >= kick_after {
+            warn!("Kicking {} after {} violations", username.0, violations.0.len());
+            violations.0.clear();
+
+            shutdown::disconnect(
+                &mut commands,
+                event.player,
+                &mut client,
+                format!("Kicked for failing the {} check", event.check).color(Color::RED),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::test_blocks::TestBlocks;
+
+    const START: DVec3 = DVec3::new(0.5, 64.0, 0.5);
+
+    fn settings() -> AntiCheatSettings {
+        AntiCheatSettings {
+            max_speed: 12.0,
+            max_move: 10.0,
+            max_jump_height: 1.35,
+            max_hover_ticks: 20,
+            max_reach: 6.0,
+            kick_after: None,
+            violation_window: Duration::from_secs(30),
+        }
+    }
+
+    /// A stone floor with its top at y = 64
+    fn floor() -> TestBlocks {
+        let mut blocks = TestBlocks::default();
+        for x in -20..=20 {
+            for z in -20..=20 {
+                blocks.set([x, 63, z], BlockState::STONE);
+            }
+        }
+        blocks
+    }
+
+    /// Checks a move like `validate_movement`, which only moves the player
+    /// on when it passes
+    fn step(state: &mut MovementState, layer: &TestBlocks, to: DVec3) -> Option<Check> {
+        let game_mode = state.game_mode;
+        let failed = check_move(&settings(), state, layer, to, game_mode);
+        if failed.is_none() {
+            state.last_valid = to;
+        }
+        failed.map(|(check, _)| check)
+    }
+
+    #[test]
+    fn the_ground_comes_from_the_blocks() {
+        let layer = floor();
+
+        assert!(on_ground(&layer, START));
+        // Hanging over the edge of the floor
+        assert!(on_ground(&layer, DVec3::new(21.2, 64.0, 0.5)));
+        assert!(!on_ground(&layer, START + DVec3::Y * 0.5));
+        assert!(!on_ground(&TestBlocks::default(), START));
+    }
+
+    #[test]
+    fn liquids_and_ladders_can_be_climbed() {
+        let mut layer = floor();
+        layer
+            .set([0, 64, 0], BlockState::WATER)
+            .set([2, 64, 0], BlockState::LADDER);
+
+        assert!(can_climb(&layer, START));
+        assert!(can_climb(&layer, DVec3::new(2.5, 64.0, 0.5)));
+        assert!(!can_climb(&layer, DVec3::new(4.5, 64.0, 0.5)));
+    }
+
+    #[test]
+    fn jumping_is_allowed_but_rising_higher_is_not() {
+        let layer = floor();
+        let mut state = MovementState::new(START, GameMode::Survival);
+
+        assert_eq!(step(&mut state, &layer, START + DVec3::Y * 1.2), None);
+        assert_eq!(
+            step(&mut state, &layer, START + DVec3::Y * 1.5),
+            Some(Check::Fly)
+        );
+    }
+
+    #[test]
+    fn hovering_is_limited() {
+        let layer = floor();
+        let mut state = MovementState::new(START, GameMode::Survival);
+        let hover = START + DVec3::Y;
+
+        for _ in 0..settings().max_hover_ticks {
+            assert_eq!(step(&mut state, &layer, hover), None);
+        }
+        assert_eq!(step(&mut state, &layer, hover), Some(Check::Fly));
+    }
+
+    #[test]
+    fn landing_resets_the_hover_count() {
+        let layer = floor();
+        let mut state = MovementState::new(START, GameMode::Survival);
+
+        for _ in 0..settings().max_hover_ticks {
+            assert_eq!(step(&mut state, &layer, START + DVec3::Y), None);
+        }
+        assert_eq!(step(&mut state, &layer, START), None);
+        assert_eq!(step(&mut state, &layer, START + DVec3::Y), None);
+    }
+
+    #[test]
+    fn swimming_up_is_not_flying() {
+        let mut layer = floor();
+        for y in 64..80 {
+            layer.set([0, y, 0], BlockState::WATER);
+        }
+        let mut state = MovementState::new(START, GameMode::Survival);
+
+        for y in 1..15 {
+            let to = START + DVec3::Y * f64::from(y);
+            assert_eq!(step(&mut state, &layer, to), None);
+        }
+    }
+
+    #[test]
+    fn moving_too_far_at_once_fails_speed() {
+        let layer = floor();
+        let mut state = MovementState::new(START, GameMode::Survival);
+
+        assert_eq!(
+            step(&mut state, &layer, START + DVec3::Z * 11.0),
+            Some(Check::Speed)
+        );
+    }
+
+    #[test]
+    fn speed_is_summed_over_the_window() {
+        let layer = floor();
+        let mut state = MovementState::new(START, GameMode::Survival);
+
+        for z in 1..=12 {
+            let to = START + DVec3::Z * f64::from(z);
+            assert_eq!(step(&mut state, &layer, to), None);
+        }
+        assert_eq!(
+            step(&mut state, &layer, START + DVec3::Z * 13.0),
+            Some(Check::Speed)
+        );
+    }
+
+    #[test]
+    fn walking_into_a_block_is_noclip() {
+        let mut layer = floor();
+        layer.set([1, 64, 0], BlockState::STONE);
+        let to = START + DVec3::X;
+
+        let mut state = MovementState::new(START, GameMode::Survival);
+        assert_eq!(step(&mut state, &layer, to), Some(Check::NoClip));
+
+        let mut state = MovementState::new(START, GameMode::Creative);
+        assert_eq!(step(&mut state, &layer, to), Some(Check::NoClip));
+
+        let mut state = MovementState::new(START, GameMode::Spectator);
+        assert_eq!(step(&mut state, &layer, to), None);
+    }
+
+    #[test]
+    fn creative_players_may_fly() {
+        let layer = floor();
+        let mut state = MovementState::new(START, GameMode::Creative);
+
+        for y in 1..=30 {
+            let to = START + DVec3::Y * f64::from(y) + DVec3::Z * 8.0 * f64::from(y);
+            assert_eq!(step(&mut state, &layer, to), None);
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use std::{collections::BTreeSet, fs, path::PathBuf};
+
+use valence::{
+    log::warn,
+    prelude::*,
+    text::{Color, IntoText},
+};
+
+use crate::{chat, setup::settings::Settings, world::storage};
+
+/// The UUIDs of everyone who has joined before, kept in `players.json` in the
+/// world's data directory so first joins can be told apart.
+#[derive(Resource)]
+pub struct KnownPlayers {
+    path: PathBuf,
+    uuids: BTreeSet<String>,
+}
+
+pub fn load_known_players(mut commands: Commands, settings: Res<Settings>) {
+    let path = storage::data_dir(&settings).join("players.json");
+
+    commands.insert_resource(KnownPlayers {
+        uuids: storage::load(&path),
+        path,
+    });
+}
+
+/// Fills in `{name}`, `{online}` and `{max}`
+fn fill_placeholders(format: &str, name: &str, online: usize, settings: &Settings) -> String {
+    format
+        .replace("{name}", name)
+        .replace("{online}", &online.to_string())
+        .replace("{max}", &settings.max_players.to_string())
+}
+
+/// Announces players joining and shows them the MOTD file.
+pub fn announce_joins(
+    joined: Query<(Entity, &Username, &UniqueId), Added<Client>>,
+    mut clients: Query<&mut Client>,
+    mut known: ResMut<KnownPlayers>,
+    settings: Res<Settings>,
+) {
+    for (entity, username, uuid) in &joined {
+        let online = clients.iter().len();
+        let first_join = known.uuids.insert(uuid.0.to_string());
+
+        if first_join {
+            storage::save(&known.path, &known.uuids);
+        }
+
+        let message = match (&settings.first_join_message, &settings.join_message) {
+            (Some(message), _) if first_join => Some(message),
+            (_, message) => message.as_ref(),
+        };
+
+        if let Some(message) = message {
+            let message = fill_placeholders(message, &username.0, online, &settings);
+            chat::broadcast(&mut clients, message.color(Color::YELLOW));
+        }
+
+        let Some(motd_path) = &settings.motd_path else {
+            continue;
+        };
+
+        // Read on every join so the file can be edited while running
+        let motd = match fs::read_to_string(motd_path) {
+            Ok(motd) => motd,
+            Err(e) => {
+                warn!("failed to read MOTD from {}: {e}", motd_path.display());
+                continue;
+            }
+        };
+
+        if let Ok(mut client) = clients.get_mut(entity) {
+            for line in motd.lines() {
+                client.send_chat_message(fill_placeholders(line, &username.0, online, &settings));
+            }
+        }
+    }
+}
+
+/// Announces players leaving, while their username is still around.
+pub fn announce_leaves(
+    mut removed: RemovedComponents<Client>,
+    usernames: Query<&Username>,
+    mut clients: Query<&mut Client>,
+    settings: Res<Settings>,
+) {
+    let Some(leave_message) = &settings.leave_message else {
+        removed.clear();
+        return;
+    };
+
+    for entity in removed.read() {
+        let Ok(username) = usernames.get(entity) else {
+            continue;
+        };
+
+        let online = clients.iter().len();
+        let message = fill_placeholders(leave_message, &username.0, online, &settings);
+        chat::broadcast(&mut clients, message.color(Color::YELLOW));
+    }
+}
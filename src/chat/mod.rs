@@ -0,0 +1,347 @@
+pub mod join;
+
+use std::{
+    collections::{HashMap, VecDeque},
+    time::{Duration, Instant},
+};
+
+use valence::{
+    command::{
+        parsers::{CommandArg, CommandArgParseError, ParseInput},
+        scopes::CommandScopes,
+    },
+    log::info,
+    message::ChatMessageEvent,
+    prelude::*,
+    protocol::packets::play::command_tree_s2c::{Parser, StringArg},
+    sound::{Sound, SoundCategory},
+    text::{Color, IntoText},
+};
+
+use crate::{commands::feedback::plain_text, setup::settings::Settings};
+
+/// The name used for messages sent from outside the world (console, RCON)
+pub const SERVER_NAME: &str = "Server";
+
+/// Mutes, reply targets and recent message times, shared by chat and the
+/// chat commands.
+#[derive(Resource, Default)]
+pub struct ChatState {
+    /// Muted usernames, lowercased, with when the mute ends. None never ends
+    mutes: HashMap<String, Option<Instant>>,
+    /// Who `/r` answers for each player
+    reply_to: HashMap<Entity, Entity>,
+    recent: HashMap<Entity, VecDeque<Instant>>,
+}
+
+impl ChatState {
+    /// Mutes a player for `duration`, or for good when None or when the end
+    /// is too far away to represent
+    pub fn mute(&mut self, username: &str, duration: Option<Duration>) {
+        self.mutes.insert(
+            username.to_lowercase(),
+            duration.and_then(|duration| Instant::now().checked_add(duration)),
+        );
+    }
+
+    /// Returns false if the player wasn't muted
+    pub fn unmute(&mut self, username: &str) -> bool {
+        self.mutes.remove(&username.to_lowercase()).is_some()
+    }
+
+    pub fn set_reply(&mut self, from: Entity, to: Entity) {
+        self.reply_to.insert(from, to);
+        self.reply_to.insert(to, from);
+    }
+
+    pub fn reply_target(&self, entity: Entity) -> Option<Entity> {
+        self.reply_to.get(&entity).copied()
+    }
+
+    /// Checks whether a player may send a message right now, counting it
+    /// against their rate limit if so.
+    ///
+    /// Returns the reason as an error message otherwise.
+    pub fn check_sender(
+        &mut self,
+        sender: Entity,
+        username: &str,
+        settings: &Settings,
+    ) -> Result<(), Text> {
+        let key = username.to_lowercase();
+
+        if let Some(until) = self.mutes.get(&key) {
+            match until {
+                Some(until) if *until <= Instant::now() => {
+                    self.mutes.remove(&key);
+                }
+                Some(until) => {
+                    let left = until.duration_since(Instant::now());
+                    return Err(format!("You are muted for {}", format_duration(left))
+                        .color(Color::RED));
+                }
+                None => return Err("You are muted".color(Color::RED)),
+            }
+        }
+
+        if let Some((limit, window)) = settings.chat_rate_limit {
+            let recent = self.recent.entry(sender).or_default();
+
+            while recent.front().is_some_and(|sent| sent.elapsed() >= window) {
+                recent.pop_front();
+            }
+
+            if recent.len() >= limit {
+                return Err("You are sending messages too quickly".color(Color::RED));
+            }
+
+            recent.push_back(Instant::now());
+        }
+
+        Ok(())
+    }
+}
+
+/// A duration argument such as `30s`, `10m`, `2h` or `1d`. A bare number is
+/// read as seconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DurationArg(pub Duration);
+
+impl CommandArg for DurationArg {
+    fn parse_arg(input: &mut ParseInput) -> Result<Self, CommandArgParseError> {
+        input.skip_whitespace();
+        let word = input.pop_word();
+
+        let invalid = || CommandArgParseError::InvalidArgument {
+            expected: "duration (30s, 10m, 2h, 1d)".to_owned(),
+            got: word.to_owned(),
+        };
+
+        let split = word
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(word.len());
+        let (amount, unit) = word.split_at(split);
+        let amount: u64 = amount.parse().map_err(|_| invalid())?;
+
+        let scale: u64 = match unit {
+            "" | "s" => 1,
+            "m" => 60,
+            "h" => 60 * 60,
+            "d" => 60 * 60 * 24,
+            _ => return Err(invalid()),
+        };
+        let duration = amount
+            .checked_mul(scale)
+            .map(Duration::from_secs)
+            .ok_or_else(invalid)?;
+
+        // Durations that would end past what an Instant can hold are too long
+        // to be meant
+        if Instant::now().checked_add(duration).is_none() {
+            return Err(invalid());
+        }
+
+        Ok(DurationArg(duration))
+    }
+
+    fn display() -> Parser {
+        Parser::String(StringArg::SingleWord)
+    }
+}
+
+/// Formats a duration in its largest whole unit, rounding up
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs() + u64::from(duration.subsec_nanos() > 0);
+
+    match seconds {
+        0..=59 => format!("{seconds}s"),
+        60..=3599 => format!("{}m", seconds.div_ceil(60)),
+        3600..=86399 => format!("{}h", seconds.div_ceil(3600)),
+        _ => format!("{}d", seconds.div_ceil(86400)),
+    }
+}
+
+/// Replaces filtered words with asterisks.
+///
+/// Only whole words are matched, so words containing a filtered word are
+/// left alone.
+pub fn filter(message: &str, settings: &Settings) -> String {
+    if settings.chat_filter.is_empty() {
+        return message.to_owned();
+    }
+
+    let mut out = String::with_capacity(message.len());
+    let mut word = String::new();
+
+    let flush = |word: &mut String, out: &mut String| {
+        let lower = word.to_lowercase();
+        if settings
+            .chat_filter
+            .iter()
+            .any(|filtered| filtered.to_lowercase() == lower)
+        {
+            out.extend(word.chars().map(|_| '*'));
+        } else {
+            out.push_str(word);
+        }
+        word.clear();
+    };
+
+    for c in message.chars() {
+        if c.is_alphanumeric() {
+            word.push(c);
+        } else {
+            flush(&mut word, &mut out);
+            out.push(c);
+        }
+    }
+    flush(&mut word, &mut out);
+
+    out
+}
+
+/// The prefix of the first group in `chat_prefixes` the player is in
+pub fn prefix(settings: &Settings, scopes: &CommandScopes) -> Text {
+    settings
+        .chat_prefixes
+        .iter()
+        .find(|(group, ..)| scopes.0.contains(group.as_str()))
+        .map(|(_, prefix, color)| prefix.clone().color(*color))
+        .unwrap_or_default()
+}
+
+/// Splits a message around mentions of `name`, highlighting them
+fn highlight_mentions(message: &str, name: &str) -> (Text, bool) {
+    // ASCII lowercasing keeps byte offsets the same in both strings
+    let lower = message.to_ascii_lowercase();
+    let name_lower = name.to_ascii_lowercase();
+
+    let mut text = Text::default();
+    let mut mentioned = false;
+    let mut last = 0;
+
+    for (start, _) in lower.match_indices(&name_lower) {
+        let end = start + name_lower.len();
+
+        // Only whole names count, with or without a leading @
+        let before = lower[..start].chars().next_back();
+        let after = lower[end..].chars().next();
+        let is_boundary = |c: Option<char>| c.map_or(true, |c| !(c.is_alphanumeric() || c == '_'));
+        if start < last || !is_boundary(before.filter(|c| *c != '@')) || !is_boundary(after) {
+            continue;
+        }
+
+        let start = if before == Some('@') { start - 1 } else { start };
+
+        text = text
+            .add_child(message[last..start].to_owned())
+            .add_child(message[start..end].to_owned().color(Color::YELLOW));
+        last = end;
+        mentioned = true;
+    }
+
+    (text.add_child(message[last..].to_owned()), mentioned)
+}
+
+/// Lays out a chat message using `chat_format`
+fn format_chat(format: &str, prefix: &Text, name: &str, message: Text) -> Text {
+    let mut text = Text::default();
+    let mut rest = format;
+
+    while let Some(start) = rest.find('{') {
+        let Some(end) = rest[start..].find('}').map(|end| start + end) else {
+            break;
+        };
+
+        text = text.add_child(rest[..start].to_owned());
+        text = match &rest[start + 1..end] {
+            "prefix" => text.add_child(prefix.clone()),
+            "name" => text.add_child(name.to_owned()),
+            "message" => text.add_child(message.clone()),
+            _ => text.add_child(rest[start..=end].to_owned()),
+        };
+        rest = &rest[end + 1..];
+    }
+
+    text.add_child(rest.to_owned())
+}
+
+/// Formats, filters and broadcasts chat messages.
+///
+/// Every recipient gets their own copy so their name can be highlighted, with
+/// a sound when they are mentioned.
+pub fn handle_chat(
+    mut events: EventReader<ChatMessageEvent>,
+    senders: Query<(&Username, &CommandScopes)>,
+    mut clients: Query<(&mut Client, &Username, &Position)>,
+    mut chat: ResMut<ChatState>,
+    settings: Res<Settings>,
+) {
+    for event in events.read() {
+        let Ok((username, scopes)) = senders.get(event.client) else {
+            continue;
+        };
+
+        if let Err(e) = chat.check_sender(event.client, &username.0, &settings) {
+            if let Ok((mut client, ..)) = clients.get_mut(event.client) {
+                client.send_chat_message(e);
+            }
+            continue;
+        }
+
+        let message = filter(&event.message, &settings);
+        let prefix = prefix(&settings, scopes);
+
+        info!(
+            "{}",
+            plain_text(&format_chat(
+                &settings.chat_format,
+                &prefix,
+                &username.0,
+                message.clone().into_text()
+            ))
+        );
+
+        for (mut client, recipient, pos) in &mut clients {
+            let (body, mentioned) = if recipient.0 == username.0 {
+                (message.clone().into_text(), false)
+            } else {
+                highlight_mentions(&message, &recipient.0)
+            };
+
+            client.send_chat_message(format_chat(
+                &settings.chat_format,
+                &prefix,
+                &username.0,
+                body,
+            ));
+
+            if mentioned {
+                client.play_sound(
+                    Sound::EntityExperienceOrbPickup,
+                    SoundCategory::Player,
+                    pos.0,
+                    1.0,
+                    1.0,
+                );
+            }
+        }
+    }
+}
+
+/// Sends a message to every player and logs it to the console
+pub fn broadcast(clients: &mut Query<&mut Client>, message: Text) {
+    info!("{}", plain_text(&message));
+
+    for mut client in clients.iter_mut() {
+        client.send_chat_message(message.clone());
+    }
+}
+
+/// Forgets reply targets and rate limits of players who left
+pub fn cleanup_chat_state(mut chat: ResMut<ChatState>, mut removed: RemovedComponents<Client>) {
+    for entity in removed.read() {
+        chat.recent.remove(&entity);
+        chat.reply_to.retain(|from, to| *from != entity && *to != entity);
+    }
+}
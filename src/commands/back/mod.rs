@@ -0,0 +1,123 @@
+use valence::{
+    command::handler::CommandResultEvent,
+    command_macros::Command,
+    entity::Look,
+    prelude::*,
+    text::{Color, IntoText},
+};
+
+use crate::{
+    commands::{
+        feedback::CommandFeedback,
+        teleport::apply::{TeleportCause, TeleportEvent, TeleportedEvent},
+    },
+    setup::settings::Settings,
+};
+
+#[derive(Command, Debug, Clone)]
+#[paths("back")]
+#[scopes("command.back")]
+pub struct Command {}
+
+/// A place a player can return to with `/back`
+#[derive(Clone, Copy, Debug)]
+pub struct BackLocation {
+    pub position: DVec3,
+    pub look: Look,
+    pub layer: Entity,
+}
+
+/// Previous locations of a player, most recent last.
+///
+/// A location is pushed whenever the player is teleported or dies, and
+/// `/back` pops it again.
+#[derive(Component, Default, Debug)]
+pub struct BackHistory(Vec<BackLocation>);
+
+impl BackHistory {
+    /// Records a location, dropping the oldest ones past `limit`
+    pub fn push(&mut self, location: BackLocation, limit: usize) {
+        self.0.push(location);
+
+        if self.0.len() > limit {
+            let excess = self.0.len() - limit;
+            self.0.drain(..excess);
+        }
+    }
+
+    pub fn pop(&mut self) -> Option<BackLocation> {
+        self.0.pop()
+    }
+}
+
+pub fn init_back_history(mut commands: Commands, clients: Query<Entity, Added<Client>>) {
+    for entity in &clients {
+        commands.entity(entity).insert(BackHistory::default());
+    }
+}
+
+/// Remembers where players were before every teleport except `/back` itself,
+/// joining and respawning.
+pub fn record_teleports(
+    mut events: EventReader<TeleportedEvent>,
+    mut histories: Query<&mut BackHistory>,
+    settings: Res<Settings>,
+) {
+    for event in events.read() {
+        // Deaths record the death location instead of the respawn
+        if matches!(
+            event.cause,
+            TeleportCause::Back | TeleportCause::Join | TeleportCause::Respawn
+        ) {
+            continue;
+        }
+
+        if let Ok(mut history) = histories.get_mut(event.entity) {
+            history.push(
+                BackLocation {
+                    position: event.from,
+                    look: event.from_look,
+                    layer: event.from_layer,
+                },
+                settings.back_history,
+            );
+        }
+    }
+}
+
+pub fn handle(
+    mut events: EventReader<CommandResultEvent<Command>>,
+    mut histories: Query<&mut BackHistory>,
+    mut teleports: EventWriter<TeleportEvent>,
+    mut feedback: EventWriter<CommandFeedback>,
+) {
+    for event in events.read() {
+        let Ok(mut history) = histories.get_mut(event.executor) else {
+            feedback.send(CommandFeedback::new(
+                event.executor,
+                Text::translate("permissions.requires.player", []).color(Color::RED),
+            ));
+            continue;
+        };
+
+        let Some(location) = history.pop() else {
+            feedback.send(CommandFeedback::new(
+                event.executor,
+                "You have nowhere to go back to".color(Color::RED),
+            ));
+            continue;
+        };
+
+        teleports.send(TeleportEvent {
+            look: Some(location.look),
+            layer: Some(location.layer),
+            cause: TeleportCause::Back,
+            ..TeleportEvent::new(event.executor, location.position)
+        });
+
+        feedback.send(CommandFeedback::new(
+            event.executor,
+            "Returning to your previous location".color(Color::GREEN),
+        ));
+    }
+}
@@ -0,0 +1,223 @@
+use valence::{
+    boss_bar::{BossBarStyle, BossBarTitle},
+    command::{
+        handler::CommandResultEvent,
+        parsers::{EntitySelector, GreedyString},
+    },
+    command_macros::Command,
+    prelude::*,
+    text::{Color, IntoText},
+};
+
+use crate::{
+    commands::{feedback::CommandFeedback, selector::SelectorResolver},
+    hud::{
+        self,
+        boss_bar::{self, BossBars, ColorArg, CustomBossBar, StyleArg},
+    },
+};
+
+#[derive(Command, Debug, Clone)]
+#[paths("bossbar")]
+#[scopes("command.bossbar")]
+pub enum Command {
+    #[paths("add {id} {name}")]
+    Add { id: String, name: GreedyString },
+    #[paths("remove {id}")]
+    Remove { id: String },
+    #[paths("list")]
+    List {},
+    #[paths("set {id} name {name}")]
+    SetName { id: String, name: GreedyString },
+    #[paths("set {id} color {color}")]
+    SetColor { id: String, color: ColorArg },
+    #[paths("set {id} style {style}")]
+    SetStyle { id: String, style: StyleArg },
+    #[paths("set {id} value {value}")]
+    SetValue { id: String, value: i32 },
+    #[paths("set {id} max {max}")]
+    SetMax { id: String, max: i32 },
+    #[paths("set {id} visible {visible}")]
+    SetVisible { id: String, visible: bool },
+    /// Shows the bar to exactly these players, or nobody when omitted
+    #[paths("set {id} players {targets?}")]
+    SetPlayers {
+        id: String,
+        targets: Option<EntitySelector>,
+    },
+}
+
+type BarQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static mut CustomBossBar,
+        &'static mut BossBarTitle,
+        &'static mut BossBarStyle,
+    ),
+>;
+
+/// Looks up a bar by id, for the `set` subcommands
+fn find_bar<'a>(
+    boss_bars: &BossBars,
+    bars: &'a mut BarQuery,
+    id: &str,
+) -> Option<(Mut<'a, CustomBossBar>, Mut<'a, BossBarTitle>, Mut<'a, BossBarStyle>)> {
+    boss_bars
+        .get(id)
+        .and_then(|entity| bars.get_mut(entity).ok())
+}
+
+fn unknown_bar(id: &str) -> Text {
+    Text::translate("commands.bossbar.unknown", [id.to_owned().into_text()]).color(Color::RED)
+}
+
+fn id_text(id: &str) -> Text {
+    format!("[{id}]").into_text()
+}
+
+pub fn handle(
+    mut events: EventReader<CommandResultEvent<Command>>,
+    mut commands: Commands,
+    server: Res<Server>,
+    resolver: SelectorResolver,
+    mut boss_bars: ResMut<BossBars>,
+    mut bars: BarQuery,
+    mut feedback: EventWriter<CommandFeedback>,
+) {
+    for event in events.read() {
+        let executor = event.executor;
+
+        let message = match &event.result {
+            Command::Add { id, name } => {
+                let title = hud::parse_text(&name.0);
+                let spawned =
+                    boss_bar::spawn_boss_bar(&mut commands, &mut boss_bars, &server, id, title);
+
+                match spawned {
+                    Some(_) => {
+                        Text::translate("commands.bossbar.create.success", [id.clone().into_text()])
+                    }
+                    None => {
+                        Text::translate("commands.bossbar.create.failed", [id.clone().into_text()])
+                            .color(Color::RED)
+                    }
+                }
+            }
+            Command::Remove { id } => {
+                if boss_bar::despawn_boss_bar(&mut commands, &mut boss_bars, id) {
+                    Text::translate("commands.bossbar.remove.success", [id.clone().into_text()])
+                } else {
+                    unknown_bar(id)
+                }
+            }
+            Command::List {} => {
+                let ids: Vec<_> = boss_bars.ids().collect();
+
+                if ids.is_empty() {
+                    Text::translate("commands.bossbar.list.bars.none", [])
+                } else {
+                    Text::translate(
+                        "commands.bossbar.list.bars.some",
+                        [ids.len().to_string().into_text(), ids.join(", ").into_text()],
+                    )
+                }
+            }
+            Command::SetName { id, name } => match find_bar(&boss_bars, &mut bars, id) {
+                Some((_, mut title, _)) => {
+                    title.0 = hud::parse_text(&name.0);
+                    Text::translate("commands.bossbar.set.name.success", [id_text(id)])
+                }
+                None => unknown_bar(id),
+            },
+            Command::SetColor { id, color } => match find_bar(&boss_bars, &mut bars, id) {
+                Some((_, _, mut style)) => {
+                    style.color = color.0;
+                    Text::translate("commands.bossbar.set.color.success", [id_text(id)])
+                }
+                None => unknown_bar(id),
+            },
+            Command::SetStyle {
+                id,
+                style: division,
+            } => match find_bar(&boss_bars, &mut bars, id) {
+                Some((_, _, mut style)) => {
+                    style.division = division.0;
+                    Text::translate("commands.bossbar.set.style.success", [id_text(id)])
+                }
+                None => unknown_bar(id),
+            },
+            Command::SetValue { id, value } => match find_bar(&boss_bars, &mut bars, id) {
+                Some((mut bar, ..)) => {
+                    bar.value = *value;
+                    Text::translate(
+                        "commands.bossbar.set.value.success",
+                        [id_text(id), value.to_string().into_text()],
+                    )
+                }
+                None => unknown_bar(id),
+            },
+            Command::SetMax { id, max } => match find_bar(&boss_bars, &mut bars, id) {
+                Some((mut bar, ..)) => {
+                    bar.max = (*max).max(1);
+                    Text::translate(
+                        "commands.bossbar.set.max.success",
+                        [id_text(id), bar.max.to_string().into_text()],
+                    )
+                }
+                None => unknown_bar(id),
+            },
+            Command::SetVisible { id, visible } => match find_bar(&boss_bars, &mut bars, id) {
+                Some((mut bar, ..)) => {
+                    bar.visible = *visible;
+                    let key = if *visible {
+                        "commands.bossbar.set.visible.success.visible"
+                    } else {
+                        "commands.bossbar.set.visible.success.hidden"
+                    };
+                    Text::translate(key, [id_text(id)])
+                }
+                None => unknown_bar(id),
+            },
+            Command::SetPlayers { id, targets } => match find_bar(&boss_bars, &mut bars, id) {
+                Some((mut bar, ..)) => {
+                    let players: Vec<Entity> = match targets {
+                        Some(targets) => match resolver.resolve(executor, targets) {
+                            Ok(targets) => targets
+                                .into_iter()
+                                .filter(|target| resolver.is_player(*target))
+                                .collect(),
+                            Err(e) => {
+                                feedback.send(CommandFeedback::new(executor, e.to_text()));
+                                continue;
+                            }
+                        },
+                        None => Vec::new(),
+                    };
+
+                    let names: Vec<_> = players
+                        .iter()
+                        .map(|player| resolver.display_name(*player))
+                        .collect();
+                    bar.players = players;
+
+                    if names.is_empty() {
+                        Text::translate("commands.bossbar.set.players.success.none", [id_text(id)])
+                    } else {
+                        Text::translate(
+                            "commands.bossbar.set.players.success.some",
+                            [
+                                id_text(id),
+                                names.len().to_string().into_text(),
+                                names.join(", ").into_text(),
+                            ],
+                        )
+                    }
+                }
+                None => unknown_bar(id),
+            },
+        };
+
+        feedback.send(CommandFeedback::new(executor, message));
+    }
+}
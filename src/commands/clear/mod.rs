@@ -0,0 +1,145 @@
+use valence::{
+    command::{handler::CommandResultEvent, parsers::EntitySelector},
+    command_macros::Command,
+    prelude::*,
+    text::{Color, IntoText},
+    ItemStack,
+};
+
+use crate::commands::{
+    feedback::CommandFeedback,
+    item::arguments::ItemInput,
+    selector::{SelectorError, SelectorResolver},
+};
+
+#[derive(Command, Debug, Clone)]
+#[paths("clear {targets?} {item?} {max_count?}")]
+#[scopes("command.clear")]
+pub struct Command {
+    targets: Option<EntitySelector>,
+    item: Option<ItemInput>,
+    max_count: Option<i32>,
+}
+
+/// Removes up to `max_count` matching items from an inventory, or only counts
+/// them when `max_count` is 0. Returns how many were found.
+fn clear_inventory(
+    inventory: &mut Inventory,
+    item: Option<&ItemInput>,
+    max_count: Option<i32>,
+) -> i32 {
+    let mut found = 0;
+
+    // Slot 0 is the crafting result, which isn't really in the inventory
+    for slot in 1..inventory.slot_count() {
+        let stack = inventory.slot(slot);
+        if stack.is_empty() || item.is_some_and(|item| !item.matches(stack)) {
+            continue;
+        }
+
+        let count = i32::from(stack.count);
+        let take = match max_count {
+            Some(0) => {
+                found += count;
+                continue;
+            }
+            Some(max_count) => count.min(max_count - found),
+            None => count,
+        };
+        if take <= 0 {
+            break;
+        }
+
+        found += take;
+        if take == count {
+            inventory.set_slot(slot, ItemStack::EMPTY);
+        } else {
+            inventory.set_slot_amount(slot, (count - take) as i8);
+        }
+    }
+
+    found
+}
+
+/// Clears items from the targets' inventories, or the executor's when no
+/// targets are given.
+pub fn handle(
+    mut events: EventReader<CommandResultEvent<Command>>,
+    resolver: SelectorResolver,
+    mut inventories: Query<&mut Inventory, With<Client>>,
+    mut feedback: EventWriter<CommandFeedback>,
+) {
+    for event in events.read() {
+        if let Some(max_count) = event.result.max_count.filter(|count| *count < 0) {
+            feedback.send(CommandFeedback::new(
+                event.executor,
+                Text::translate(
+                    "argument.integer.low",
+                    ["0".into_text(), max_count.to_string().into_text()],
+                )
+                .color(Color::RED),
+            ));
+            continue;
+        }
+
+        let targets = match &event.result.targets {
+            Some(selector) => resolver.resolve(event.executor, selector),
+            None => Ok(vec![event.executor]),
+        };
+
+        let targets = match targets {
+            Ok(targets) => targets,
+            Err(e) => {
+                feedback.send(CommandFeedback::new(event.executor, e.to_text()));
+                continue;
+            }
+        };
+        let targets: Vec<Entity> = targets
+            .into_iter()
+            .filter(|target| inventories.contains(*target))
+            .collect();
+
+        if targets.is_empty() {
+            let error = match event.result.targets {
+                Some(_) => SelectorError::NoPlayerFound.to_text(),
+                None => Text::translate("permissions.requires.player", []).color(Color::RED),
+            };
+            feedback.send(CommandFeedback::new(event.executor, error));
+            continue;
+        }
+
+        let mut found = 0;
+        for target in &targets {
+            if let Ok(mut inventory) = inventories.get_mut(*target) {
+                found += clear_inventory(
+                    &mut inventory,
+                    event.result.item.as_ref(),
+                    event.result.max_count,
+                );
+            }
+        }
+
+        let (suffix, target_name) = match targets.as_slice() {
+            [target] => ("single", resolver.display_name(*target)),
+            _ => ("multiple", targets.len().to_string()),
+        };
+
+        let message = if found == 0 {
+            Text::translate(
+                format!("commands.clear.failed.{suffix}"),
+                [target_name.into_text()],
+            )
+            .color(Color::RED)
+        } else {
+            let key = match event.result.max_count {
+                Some(0) => "commands.clear.test",
+                _ => "commands.clear.success",
+            };
+            Text::translate(
+                format!("{key}.{suffix}"),
+                [found.to_string().into_text(), target_name.into_text()],
+            )
+        };
+        feedback.send(CommandFeedback::new(event.executor, message));
+    }
+}
@@ -0,0 +1,245 @@
+use valence::{
+    prelude::*,
+    text::{IntoText, TextContent},
+};
+
+/// A message produced by a command for whoever executed it.
+///
+/// Commands can be executed by players, the console or RCON, so handlers send
+/// their output through this event instead of assuming the executor is a
+/// `Client`.
+#[derive(Event, Clone, Debug)]
+pub struct CommandFeedback {
+    pub executor: Entity,
+    pub message: Text,
+}
+
+impl CommandFeedback {
+    pub fn new<'a>(executor: Entity, message: impl IntoText<'a>) -> Self {
+        Self {
+            executor,
+            message: message.into_text(),
+        }
+    }
+}
+
+/// English text for the vanilla translation keys our commands use, so
+/// outputs without a client to translate them (console, RCON) stay readable.
+fn english(key: &str) -> Option<&'static str> {
+    Some(match key {
+        "argument.entity.notfound.entity" => "No entity was found",
+        "argument.entity.notfound.player" => "No player was found",
+        "argument.entity.options.unknown" => "Unknown option '%s'",
+        "argument.entity.options.mode.invalid" => "Invalid or unknown game mode '%s'",
+        "argument.entity.options.sort.irreversible" => "Invalid or unknown sort type '%s'",
+        "argument.entity.options.limit.toosmall" => "Limit must be at least 1",
+        "argument.entity.options.valueless" => "Expected value for option '%s'",
+        "argument.entity.options.type.invalid" => "Invalid or unknown entity type '%s'",
+        "argument.entity.options.distance.negative" => "Distance cannot be negative",
+        "argument.range.empty" => "Expected value or range of values",
+        "parsing.int.invalid" => "Invalid integer '%s'",
+        "parsing.double.invalid" => "Invalid double '%s'",
+        "permissions.requires.entity" => "An entity is required to run this command here",
+        "permissions.requires.player" => "A player is required to run this command here",
+        "commands.gamemode.success.self" => "Set own game mode to %s",
+        "commands.gamemode.success.other" => "Set %s's game mode to %s",
+        "commands.message.display.incoming" => "%s whispers to you: %s",
+        "commands.message.display.outgoing" => "You whisper to %s: %s",
+        "chat.type.announcement" => "[%s] %s",
+        "chat.type.emote" => "* %s %s",
+        "arguments.objective.notFound" => "Unknown scoreboard objective '%s'",
+        "argument.criteria.invalid" => "Unknown criterion '%s'",
+        "commands.scoreboard.objectives.add.success" => "Created new objective %s",
+        "commands.scoreboard.objectives.add.duplicate" => {
+            "An objective already exists by that name"
+        }
+        "commands.scoreboard.objectives.remove.success" => "Removed objective %s",
+        "commands.scoreboard.objectives.display.set" => {
+            "Set display slot %s to show objective %s"
+        }
+        "commands.scoreboard.objectives.display.cleared" => {
+            "Cleared any objectives in display slot %s"
+        }
+        "commands.scoreboard.objectives.list.empty" => "There are no objectives",
+        "commands.scoreboard.objectives.list.success" => "There are %s objective(s): %s",
+        "commands.scoreboard.players.set.success.single" => "Set %s for %s to %s",
+        "commands.scoreboard.players.set.success.multiple" => "Set %s for %s entities to %s",
+        "commands.scoreboard.players.add.success.single" => "Added %s to %s for %s (now %s)",
+        "commands.scoreboard.players.add.success.multiple" => "Added %s to %s for %s entities",
+        "commands.scoreboard.players.remove.success.single" => {
+            "Removed %s from %s for %s (now %s)"
+        }
+        "commands.scoreboard.players.remove.success.multiple" => {
+            "Removed %s from %s for %s entities"
+        }
+        "commands.scoreboard.players.reset.all.single" => "Reset all scores for %s",
+        "commands.scoreboard.players.reset.all.multiple" => "Reset all scores for %s entities",
+        "commands.scoreboard.players.reset.specific.single" => "Reset %s for %s",
+        "commands.scoreboard.players.reset.specific.multiple" => "Reset %s for %s entities",
+        "team.notFound" => "Unknown team '%s'",
+        "argument.color.invalid" => "Unknown color '%s'",
+        "commands.team.add.success" => "Created team %s",
+        "commands.team.add.duplicate" => "A team already exists by that name",
+        "commands.team.remove.success" => "Removed team %s",
+        "commands.team.empty.success" => "Removed %s member(s) from team %s",
+        "commands.team.empty.unchanged" => "Nothing changed. That team is already empty",
+        "commands.team.join.success.single" => "Added %s to team %s",
+        "commands.team.join.success.multiple" => "Added %s entities to team %s",
+        "commands.team.leave.success.single" => "Removed %s from any team",
+        "commands.team.leave.success.multiple" => "Removed %s entities from any team",
+        "commands.team.list.teams.empty" => "There are no teams",
+        "commands.team.list.teams.success" => "There are %s team(s): %s",
+        "commands.team.list.members.empty" => "There are no members on team %s",
+        "commands.team.list.members.success" => "Team %s has %s member(s): %s",
+        "commands.team.option.name.success" => "Updated the name of team %s",
+        "commands.team.option.color.success" => "Updated the color for team %s to %s",
+        "commands.team.option.prefix.success" => "Team prefix set to %s",
+        "commands.team.option.suffix.success" => "Team suffix set to %s",
+        "commands.bossbar.create.success" => "Created custom bossbar %s",
+        "commands.bossbar.create.failed" => "A bossbar already exists with the ID '%s'",
+        "commands.bossbar.remove.success" => "Removed custom bossbar %s",
+        "commands.bossbar.unknown" => "No bossbar exists with the ID '%s'",
+        "commands.bossbar.list.bars.none" => "There are no custom bossbars active",
+        "commands.bossbar.list.bars.some" => "There are %s custom bossbar(s) active: %s",
+        "commands.bossbar.set.name.success" => "Custom bossbar %s has been renamed",
+        "commands.bossbar.set.color.success" => "Custom bossbar %s has changed color",
+        "commands.bossbar.set.style.success" => "Custom bossbar %s has changed style",
+        "commands.bossbar.set.value.success" => "Custom bossbar %s has changed value to %s",
+        "commands.bossbar.set.max.success" => "Custom bossbar %s has changed maximum to %s",
+        "commands.bossbar.set.visible.success.visible" => "Custom bossbar %s is now visible",
+        "commands.bossbar.set.visible.success.hidden" => "Custom bossbar %s is now hidden",
+        "commands.bossbar.set.players.success.none" => {
+            "Custom bossbar %s no longer has any players"
+        }
+        "commands.bossbar.set.players.success.some" => {
+            "Custom bossbar %s now has %s player(s): %s"
+        }
+        "commands.title.cleared.single" => "Cleared titles for %s",
+        "commands.title.cleared.multiple" => "Cleared titles for %s players",
+        "commands.title.reset.single" => "Reset title options for %s",
+        "commands.title.reset.multiple" => "Reset title options for %s players",
+        "commands.title.show.title.single" => "Showing new title for %s",
+        "commands.title.show.title.multiple" => "Showing new title for %s players",
+        "commands.title.show.subtitle.single" => "Showing new subtitle for %s",
+        "commands.title.show.subtitle.multiple" => "Showing new subtitle for %s players",
+        "commands.title.show.actionbar.single" => "Showing new actionbar title for %s",
+        "commands.title.show.actionbar.multiple" => {
+            "Showing new actionbar title for %s players"
+        }
+        "commands.title.times.single" => "Changed title display times for %s",
+        "commands.title.times.multiple" => "Changed title display times for %s players",
+        "death.fell.accident.generic" => "%s fell from a high place",
+        "death.attack.drown" => "%s drowned",
+        "death.attack.outOfWorld" => "%s fell out of the world",
+        "death.attack.starve" => "%s starved to death",
+        "death.attack.mob" => "%s was slain by %s",
+        "death.attack.arrow" => "%s was shot by %s",
+        "death.attack.explosion" => "%s blew up",
+        "death.attack.explosion.player" => "%s was blown up by %s",
+        "death.attack.generic" => "%s died",
+        "death.attack.genericKill" => "%s was killed",
+        "commands.setworldspawn.success" => "Set the world spawn point to %s, %s, %s [%s]",
+        "commands.teleport.success.entity.single" => "Teleported %s to %s",
+        "commands.teleport.success.entity.multiple" => "Teleported %s entities to %s",
+        "commands.teleport.success.location.single" => "Teleported %s to %s, %s, %s",
+        "commands.teleport.success.location.multiple" => "Teleported %s entities to %s, %s, %s",
+        "commands.summon.success" => "Summoned new %s",
+        "commands.summon.failed" => "Unable to summon entity",
+        "commands.summon.invalidPosition" => "Invalid position for summon",
+        "argument.nbt.expected.compound" => "Expected compound tag",
+        "argument.nbt.expected.key" => "Expected key",
+        "argument.nbt.expected.value" => "Expected value",
+        "argument.nbt.list.mixed" => "Can't insert %s into list of %s",
+        "argument.nbt.trailing" => "Unexpected trailing data",
+        "parsing.quote.expected.end" => "Unclosed quoted string",
+        "commands.kill.success.single" => "Killed %s",
+        "commands.kill.success.multiple" => "Killed %s entities",
+        "commands.give.success.single" => "Gave %s %s to %s",
+        "commands.give.success.multiple" => "Gave %s %s to %s players",
+        "commands.give.failed.toomanyitems" => "Can't give more than %s of %s",
+        "argument.integer.low" => "Integer must not be less than %s, found %s",
+        "commands.clear.success.single" => "Removed %s item(s) from player %s",
+        "commands.clear.success.multiple" => "Removed %s item(s) from %s players",
+        "commands.clear.test.single" => "Found %s matching item(s) on player %s",
+        "commands.clear.test.multiple" => "Found %s matching item(s) on %s players",
+        "commands.clear.failed.single" => "No items were found on player %s",
+        "commands.clear.failed.multiple" => "No items were found on %s players",
+        "commands.item.entity.set.success.single" => "Replaced a slot on %s with %s",
+        "commands.item.entity.set.success.multiple" => "Replaced a slot on %s entities with %s",
+        "commands.item.source.no_such_slot" => "The source does not have slot %s",
+        "commands.item.target.no_changes" => "No targets accepted item into slot %s",
+        "argument.entity.toomany" => {
+            "Only one entity is allowed, but the provided selector allows more than one"
+        }
+        "gameMode.changed" => "Your game mode has been updated to %s",
+        "gameMode.survival" => "Survival Mode",
+        "gameMode.creative" => "Creative Mode",
+        "gameMode.adventure" => "Adventure Mode",
+        "gameMode.spectator" => "Spectator Mode",
+        _ => return None,
+    })
+}
+
+/// Fills in `%s` and `%1$s` style placeholders.
+fn format_translation(format: &str, args: &[String]) -> String {
+    let mut out = String::new();
+    let mut next = 0;
+    let mut rest = format;
+
+    while let Some(i) = rest.find('%') {
+        out.push_str(&rest[..i]);
+        rest = &rest[i + 1..];
+
+        if let Some(tail) = rest.strip_prefix('s') {
+            out.push_str(args.get(next).map_or("", String::as_str));
+            next += 1;
+            rest = tail;
+        } else if let Some(tail) = rest.strip_prefix('%') {
+            out.push('%');
+            rest = tail;
+        } else if let Some(end) = rest.find("$s") {
+            match rest[..end].parse::<usize>() {
+                Ok(n) => {
+                    out.push_str(args.get(n.wrapping_sub(1)).map_or("", String::as_str));
+                    rest = &rest[end + 2..];
+                }
+                Err(_) => out.push('%'),
+            }
+        } else {
+            out.push('%');
+        }
+    }
+
+    out.push_str(rest);
+    out
+}
+
+/// Renders text without formatting, for outputs that can't show colors.
+pub fn plain_text(text: &Text) -> String {
+    let mut plain = match &text.content {
+        TextContent::Text { text } => text.to_string(),
+        TextContent::Translate { translate, with } => {
+            let args: Vec<String> = with.iter().map(plain_text).collect();
+            format_translation(english(translate).unwrap_or(translate), &args)
+        }
+        _ => String::new(),
+    };
+
+    for extra in &text.extra {
+        plain.push_str(&plain_text(extra));
+    }
+
+    plain
+}
+
+/// Delivers feedback addressed to players as chat messages.
+pub fn deliver_feedback(
+    mut events: EventReader<CommandFeedback>,
+    mut clients: Query<&mut Client>,
+) {
+    for event in events.read() {
+        if let Ok(mut client) = clients.get_mut(event.executor) {
+            client.send_chat_message(event.message.clone());
+        }
+    }
+}
@@ -1,13 +1,13 @@
 use valence::{
-    client::{Client, Username},
-    command::{
-        handler::CommandResultEvent,
-        parsers::{entity_selector::EntitySelectors, EntitySelector},
-    },
+    command::{handler::CommandResultEvent, parsers::EntitySelector},
     command_macros::Command,
-    entity::Position,
     prelude::*,
-    rand::{self, seq::IteratorRandom},
+    text::{Color, IntoText},
+};
+
+use crate::commands::{
+    feedback::CommandFeedback,
+    selector::{SelectorError, SelectorResolver},
 };
 
 /// FROM VALENCE EXAMPLE
@@ -15,174 +15,138 @@ use valence::{
 
 #[derive(Command, Debug, Clone)]
 #[paths("gamemode", "gm")]
-#[scopes("valence.command.gamemode")]
+#[scopes("command.gamemode")]
 pub enum Command {
-    #[paths("survival {target?}", "{/} gms {target?}")]
+    #[paths(
+        "survival {target?}",
+        "s {target?}",
+        "0 {target?}",
+        "{/} gms {target?}"
+    )]
     Survival { target: Option<EntitySelector> },
-    #[paths("creative {target?}", "{/} gmc {target?}")]
+    #[paths(
+        "creative {target?}",
+        "c {target?}",
+        "1 {target?}",
+        "{/} gmc {target?}"
+    )]
     Creative { target: Option<EntitySelector> },
-    #[paths("adventure {target?}", "{/} gma {target?}")]
+    #[paths(
+        "adventure {target?}",
+        "a {target?}",
+        "2 {target?}",
+        "{/} gma {target?}"
+    )]
     Adventure { target: Option<EntitySelector> },
-    #[paths("spectator {target?}", "{/} gmspec {target?}")]
+    #[paths(
+        "spectator {target?}",
+        "sp {target?}",
+        "3 {target?}",
+        "{/} gmspec {target?}"
+    )]
     Spectator { target: Option<EntitySelector> },
 }
 
-#[derive(Command, Debug, Clone)]
-#[paths("struct {gamemode} {target?}")]
-#[scopes("valence.command.gamemode")]
-#[allow(dead_code)]
-pub(crate) struct GameModeStructCommand {
-    gamemode: GameMode,
-    target: Option<EntitySelector>,
+impl Command {
+    fn game_mode(&self) -> GameMode {
+        match self {
+            Command::Survival { .. } => GameMode::Survival,
+            Command::Creative { .. } => GameMode::Creative,
+            Command::Adventure { .. } => GameMode::Adventure,
+            Command::Spectator { .. } => GameMode::Spectator,
+        }
+    }
+
+    fn target(&self) -> Option<&EntitySelector> {
+        match self {
+            Command::Survival { target }
+            | Command::Creative { target }
+            | Command::Adventure { target }
+            | Command::Spectator { target } => target.as_ref(),
+        }
+    }
+}
+
+/// The translated name of a game mode, as shown in vanilla feedback
+pub fn game_mode_text(game_mode: GameMode) -> Text {
+    let key = match game_mode {
+        GameMode::Survival => "gameMode.survival",
+        GameMode::Creative => "gameMode.creative",
+        GameMode::Adventure => "gameMode.adventure",
+        GameMode::Spectator => "gameMode.spectator",
+    };
+
+    Text::translate(key, [])
 }
 
 pub fn handle(
     mut events: EventReader<CommandResultEvent<Command>>,
-    mut clients: Query<(&mut Client, &mut GameMode, &Username, Entity)>,
-    positions: Query<&Position>,
+    mut params: ParamSet<(SelectorResolver, Query<&mut GameMode, With<Client>>)>,
+    mut feedback: EventWriter<CommandFeedback>,
 ) {
     for event in events.read() {
-        let game_mode_to_set = match &event.result {
-            Command::Survival { .. } => GameMode::Survival,
-            Command::Creative { .. } => GameMode::Creative,
-            Command::Adventure { .. } => GameMode::Adventure,
-            Command::Spectator { .. } => GameMode::Spectator,
+        let game_mode_to_set = event.result.game_mode();
+
+        let targets = match event.result.target() {
+            Some(selector) => params.p0().resolve(event.executor, selector),
+            None => Ok(vec![event.executor]),
         };
 
-        let selector = match &event.result {
-            Command::Survival { target } => target.clone(),
-            Command::Creative { target } => target.clone(),
-            Command::Adventure { target } => target.clone(),
-            Command::Spectator { target } => target.clone(),
+        let targets = match targets {
+            Ok(targets) => targets,
+            Err(e) => {
+                feedback.send(CommandFeedback::new(event.executor, e.to_text()));
+                continue;
+            }
         };
 
-        match selector {
-            None => {
-                let (mut client, mut game_mode, ..) = clients.get_mut(event.executor).unwrap();
-                *game_mode = game_mode_to_set;
-                client.send_chat_message(format!(
-                    "Gamemode command executor -> self executed with data:\n {:#?}",
-                    &event.result
+        let resolver = params.p0();
+        let (players, names): (Vec<Entity>, Vec<String>) = targets
+            .into_iter()
+            .filter(|target| resolver.is_player(*target))
+            .map(|target| (target, resolver.display_name(target)))
+            .unzip();
+
+        if players.is_empty() {
+            let error = match event.result.target() {
+                Some(_) => SelectorError::NoPlayerFound.to_text(),
+                None => Text::translate("permissions.requires.player", []).color(Color::RED),
+            };
+            feedback.send(CommandFeedback::new(event.executor, error));
+            continue;
+        }
+
+        for (target, name) in players.into_iter().zip(names) {
+            let mut game_modes = params.p1();
+            let Ok(mut game_mode) = game_modes.get_mut(target) else {
+                continue;
+            };
+
+            if *game_mode == game_mode_to_set {
+                continue;
+            }
+            *game_mode = game_mode_to_set;
+
+            let mode = game_mode_text(game_mode_to_set);
+
+            if target == event.executor {
+                feedback.send(CommandFeedback::new(
+                    event.executor,
+                    Text::translate("commands.gamemode.success.self", [mode]),
+                ));
+            } else {
+                feedback.send(CommandFeedback::new(
+                    target,
+                    Text::translate("gameMode.changed", [mode.clone()]),
+                ));
+                feedback.send(CommandFeedback::new(
+                    event.executor,
+                    Text::translate(
+                        "commands.gamemode.success.other",
+                        [name.into_text(), mode],
+                    ),
                 ));
             }
-            Some(selector) => match selector {
-                EntitySelector::SimpleSelector(selector) => match selector {
-                    EntitySelectors::AllEntities => {
-                        for (mut client, mut game_mode, ..) in &mut clients.iter_mut() {
-                            *game_mode = game_mode_to_set;
-                            client.send_chat_message(format!(
-                                "Gamemode command executor -> all entities executed with data:\n \
-                                 {:#?}",
-                                &event.result
-                            ));
-                        }
-                    }
-                    EntitySelectors::SinglePlayer(name) => {
-                        let target = clients
-                            .iter_mut()
-                            .find(|(.., username, _)| username.0 == *name)
-                            .map(|(.., target)| target);
-
-                        match target {
-                            None => {
-                                let client = &mut clients.get_mut(event.executor).unwrap().0;
-                                client.send_chat_message(format!("Could not find target: {name}"));
-                            }
-                            Some(target) => {
-                                let mut game_mode = clients.get_mut(target).unwrap().1;
-                                *game_mode = game_mode_to_set;
-
-                                let client = &mut clients.get_mut(event.executor).unwrap().0;
-                                client.send_chat_message(format!(
-                                    "Gamemode command executor -> single player executed with \
-                                     data:\n {:#?}",
-                                    &event.result
-                                ));
-                            }
-                        }
-                    }
-                    EntitySelectors::AllPlayers => {
-                        for (mut client, mut game_mode, ..) in &mut clients.iter_mut() {
-                            *game_mode = game_mode_to_set;
-                            client.send_chat_message(format!(
-                                "Gamemode command executor -> all entities executed with data:\n \
-                                 {:#?}",
-                                &event.result
-                            ));
-                        }
-                    }
-                    EntitySelectors::SelfPlayer => {
-                        let (mut client, mut game_mode, ..) =
-                            clients.get_mut(event.executor).unwrap();
-                        *game_mode = game_mode_to_set;
-                        client.send_chat_message(format!(
-                            "Gamemode command executor -> self executed with data:\n {:#?}",
-                            &event.result
-                        ));
-                    }
-                    EntitySelectors::NearestPlayer => {
-                        let executor_pos = positions.get(event.executor).unwrap();
-                        let target = clients
-                            .iter_mut()
-                            .filter(|(.., target)| *target != event.executor)
-                            .min_by(|(.., target), (.., target2)| {
-                                let target_pos = positions.get(*target).unwrap();
-                                let target2_pos = positions.get(*target2).unwrap();
-                                let target_dist = target_pos.distance(**executor_pos);
-                                let target2_dist = target2_pos.distance(**executor_pos);
-                                target_dist.partial_cmp(&target2_dist).unwrap()
-                            })
-                            .map(|(.., target)| target);
-
-                        match target {
-                            None => {
-                                let client = &mut clients.get_mut(event.executor).unwrap().0;
-                                client.send_chat_message("Could not find target".to_owned());
-                            }
-                            Some(target) => {
-                                let mut game_mode = clients.get_mut(target).unwrap().1;
-                                *game_mode = game_mode_to_set;
-
-                                let client = &mut clients.get_mut(event.executor).unwrap().0;
-                                client.send_chat_message(format!(
-                                    "Gamemode command executor -> single player executed with \
-                                     data:\n {:#?}",
-                                    &event.result
-                                ));
-                            }
-                        }
-                    }
-                    EntitySelectors::RandomPlayer => {
-                        let target = clients
-                            .iter_mut()
-                            .choose(&mut rand::thread_rng())
-                            .map(|(.., target)| target);
-
-                        match target {
-                            None => {
-                                let client = &mut clients.get_mut(event.executor).unwrap().0;
-                                client.send_chat_message("Could not find target".to_owned());
-                            }
-                            Some(target) => {
-                                let mut game_mode = clients.get_mut(target).unwrap().1;
-                                *game_mode = game_mode_to_set;
-
-                                let client = &mut clients.get_mut(event.executor).unwrap().0;
-                                client.send_chat_message(format!(
-                                    "Gamemode command executor -> single player executed with \
-                                     data:\n {:#?}",
-                                    &event.result
-                                ));
-                            }
-                        }
-                    }
-                },
-                EntitySelector::ComplexSelector(_, _) => {
-                    let client = &mut clients.get_mut(event.executor).unwrap().0;
-                    client
-                        .send_chat_message("Complex selectors are not implemented yet".to_owned());
-                }
-            },
         }
     }
 }
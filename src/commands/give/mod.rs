@@ -0,0 +1,119 @@
+use valence::{
+    command::{handler::CommandResultEvent, parsers::EntitySelector},
+    command_macros::Command,
+    prelude::*,
+    text::{Color, IntoText},
+};
+
+use crate::{
+    commands::{
+        feedback::CommandFeedback,
+        item::arguments::ItemInput,
+        selector::{SelectorError, SelectorResolver},
+    },
+    interacting::items::{drop_item, give_item},
+};
+
+/// At most this many stacks can be given at once
+const MAX_STACKS: i32 = 100;
+
+#[derive(Command, Debug, Clone)]
+#[paths("give {targets} {item} {count?}")]
+#[scopes("command.give")]
+pub struct Command {
+    targets: EntitySelector,
+    item: ItemInput,
+    count: Option<i32>,
+}
+
+/// Gives items to players, dropping whatever doesn't fit at their feet.
+pub fn handle(
+    mut commands: Commands,
+    mut events: EventReader<CommandResultEvent<Command>>,
+    resolver: SelectorResolver,
+    mut players: Query<(&mut Inventory, &Position, &EntityLayerId), With<Client>>,
+    mut feedback: EventWriter<CommandFeedback>,
+) {
+    for event in events.read() {
+        let item = &event.result.item;
+        let max_stack = i32::from(item.item.max_stack());
+        let count = event.result.count.unwrap_or(1);
+
+        if count < 1 {
+            feedback.send(CommandFeedback::new(
+                event.executor,
+                Text::translate(
+                    "argument.integer.low",
+                    ["1".into_text(), count.to_string().into_text()],
+                )
+                .color(Color::RED),
+            ));
+            continue;
+        }
+        if count > max_stack * MAX_STACKS {
+            feedback.send(CommandFeedback::new(
+                event.executor,
+                Text::translate(
+                    "commands.give.failed.toomanyitems",
+                    [
+                        (max_stack * MAX_STACKS).to_string().into_text(),
+                        item.name().into_text(),
+                    ],
+                )
+                .color(Color::RED),
+            ));
+            continue;
+        }
+
+        let targets = match resolver.resolve(event.executor, &event.result.targets) {
+            Ok(targets) => targets,
+            Err(e) => {
+                feedback.send(CommandFeedback::new(event.executor, e.to_text()));
+                continue;
+            }
+        };
+        let targets: Vec<Entity> = targets
+            .into_iter()
+            .filter(|target| players.contains(*target))
+            .collect();
+
+        if targets.is_empty() {
+            feedback.send(CommandFeedback::new(
+                event.executor,
+                SelectorError::NoPlayerFound.to_text(),
+            ));
+            continue;
+        }
+
+        for target in &targets {
+            let Ok((mut inventory, pos, layer)) = players.get_mut(*target) else {
+                continue;
+            };
+
+            let mut remaining = count;
+            while remaining > 0 {
+                let amount = remaining.min(max_stack);
+                remaining -= amount;
+
+                let left = give_item(&mut inventory, item.stack(amount as i8));
+                if !left.is_empty() {
+                    drop_item(&mut commands, layer.0, pos.0, left, 0);
+                }
+            }
+        }
+
+        let (key, target_name) = match targets.as_slice() {
+            [target] => ("commands.give.success.single", resolver.display_name(*target)),
+            _ => ("commands.give.success.multiple", targets.len().to_string()),
+        };
+        let message = Text::translate(
+            key,
+            [
+                count.to_string().into_text(),
+                item.name().into_text(),
+                target_name.into_text(),
+            ],
+        );
+        feedback.send(CommandFeedback::new(event.executor, message));
+    }
+}
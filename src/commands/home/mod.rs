@@ -0,0 +1,212 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+};
+
+use valence::{
+    command::{handler::CommandResultEvent, scopes::CommandScopes},
+    command_macros::Command,
+    entity::Look,
+    prelude::*,
+    text::{Color, IntoText},
+};
+
+use crate::{
+    commands::{
+        feedback::CommandFeedback,
+        teleport::apply::{TeleportCause, TeleportEvent},
+    },
+    server::shutdown::ShutdownEvent,
+    setup::settings::Settings,
+    world::{
+        spawn::LayerName,
+        storage::{self, SavedLocation},
+    },
+};
+
+/// The name used when a home command is given no name
+const DEFAULT_HOME: &str = "home";
+
+#[derive(Command, Debug, Clone)]
+#[paths("home")]
+#[scopes("command.home")]
+pub enum Command {
+    #[paths("{name?}")]
+    Teleport { name: Option<String> },
+    #[paths("{/} sethome {name?}")]
+    Set { name: Option<String> },
+    #[paths("{/} delhome {name?}")]
+    Delete { name: Option<String> },
+    #[paths("{/} homes")]
+    List {},
+}
+
+/// Every player's homes by UUID, saved to `homes.json` in the world's data
+/// directory whenever they change.
+#[derive(Resource)]
+pub struct Homes {
+    path: PathBuf,
+    players: HashMap<String, BTreeMap<String, SavedLocation>>,
+}
+
+impl Homes {
+    fn save(&self) {
+        storage::save(&self.path, &self.players);
+    }
+}
+
+pub fn load_homes(mut commands: Commands, settings: Res<Settings>) {
+    let path = storage::data_dir(&settings).join("homes.json");
+
+    commands.insert_resource(Homes {
+        players: storage::load(&path),
+        path,
+    });
+}
+
+/// Writes homes out one last time when the server stops
+pub fn save_homes(homes: Res<Homes>, mut shutdown: EventReader<ShutdownEvent>) {
+    if shutdown.read().next().is_some() {
+        homes.save();
+    }
+}
+
+/// The number of homes a player may set, the highest limit of their groups
+fn home_limit(settings: &Settings, scopes: &CommandScopes) -> usize {
+    settings
+        .home_limits
+        .iter()
+        .filter(|(group, _)| scopes.0.contains(group.as_str()))
+        .map(|(_, limit)| *limit)
+        .max()
+        .unwrap_or(0)
+}
+
+fn error(message: impl Into<String>) -> Text {
+    message.into().color(Color::RED)
+}
+
+pub fn handle(
+    mut events: EventReader<CommandResultEvent<Command>>,
+    players: Query<(&UniqueId, &Position, &Look, &EntityLayerId, &CommandScopes), With<Client>>,
+    layers: Query<(Entity, &LayerName)>,
+    mut homes: ResMut<Homes>,
+    settings: Res<Settings>,
+    mut teleports: EventWriter<TeleportEvent>,
+    mut feedback: EventWriter<CommandFeedback>,
+) {
+    for event in events.read() {
+        let executor = event.executor;
+
+        let Ok((uuid, pos, look, layer, scopes)) = players.get(executor) else {
+            feedback.send(CommandFeedback::new(
+                executor,
+                Text::translate("permissions.requires.player", []).color(Color::RED),
+            ));
+            continue;
+        };
+
+        let uuid = uuid.0.to_string();
+
+        match &event.result {
+            Command::Teleport { name } => {
+                let name = name.as_deref().unwrap_or(DEFAULT_HOME);
+
+                let Some(home) = homes.players.get(&uuid).and_then(|homes| homes.get(name)) else {
+                    feedback.send(CommandFeedback::new(
+                        executor,
+                        error(format!("You have no home called {name}")),
+                    ));
+                    continue;
+                };
+
+                let Some(layer) = home.find_layer(layers.iter()) else {
+                    feedback.send(CommandFeedback::new(
+                        executor,
+                        error(format!("Home {name} is in {}, which isn't loaded", home.layer)),
+                    ));
+                    continue;
+                };
+
+                teleports.send(TeleportEvent {
+                    look: Some(home.look()),
+                    layer: Some(layer),
+                    cause: TeleportCause::Home,
+                    ..TeleportEvent::new(executor, home.position())
+                });
+
+                feedback.send(CommandFeedback::new(
+                    executor,
+                    format!("Teleporting to {name}").color(Color::GREEN),
+                ));
+            }
+            Command::Set { name } => {
+                let name = name.as_deref().unwrap_or(DEFAULT_HOME);
+                let limit = home_limit(&settings, scopes);
+                let player_homes = homes.players.entry(uuid).or_default();
+
+                // Moving an existing home never counts against the limit
+                if !player_homes.contains_key(name) && player_homes.len() >= limit {
+                    feedback.send(CommandFeedback::new(
+                        executor,
+                        error(format!("You can't set more than {limit} homes")),
+                    ));
+                    continue;
+                }
+
+                let Ok((_, layer_name)) = layers.get(layer.0) else {
+                    continue;
+                };
+                let home = SavedLocation::new(layer_name, pos.0, *look);
+                player_homes.insert(name.to_owned(), home);
+                homes.save();
+
+                feedback.send(CommandFeedback::new(
+                    executor,
+                    format!("Home {name} set").color(Color::GREEN),
+                ));
+            }
+            Command::Delete { name } => {
+                let name = name.as_deref().unwrap_or(DEFAULT_HOME);
+
+                let removed = homes
+                    .players
+                    .get_mut(&uuid)
+                    .and_then(|homes| homes.remove(name));
+
+                if removed.is_none() {
+                    feedback.send(CommandFeedback::new(
+                        executor,
+                        error(format!("You have no home called {name}")),
+                    ));
+                    continue;
+                }
+
+                homes.save();
+
+                feedback.send(CommandFeedback::new(
+                    executor,
+                    format!("Home {name} deleted").color(Color::GOLD),
+                ));
+            }
+            Command::List {} => {
+                let names: Vec<_> = homes
+                    .players
+                    .get(&uuid)
+                    .map(|homes| homes.keys().cloned().collect())
+                    .unwrap_or_default();
+
+                let limit = home_limit(&settings, scopes);
+
+                let message = if names.is_empty() {
+                    "You have no homes, set one with /sethome".color(Color::GOLD)
+                } else {
+                    format!("Homes ({}/{limit}): {}", names.len(), names.join(", "))
+                        .color(Color::GOLD)
+                };
+
+                feedback.send(CommandFeedback::new(executor, message));
+            }
+        }
+    }
+}
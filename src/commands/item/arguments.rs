@@ -0,0 +1,152 @@
+use valence::{
+    command::parsers::{CommandArg, CommandArgParseError, ParseInput},
+    inventory::HeldItem,
+    nbt::Compound,
+    protocol::packets::play::command_tree_s2c::Parser,
+    ItemKind, ItemStack,
+};
+
+use crate::commands::snbt;
+
+/// An item id with optional NBT, such as `diamond_sword{Damage:5}`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ItemInput {
+    pub item: ItemKind,
+    pub nbt: Option<Compound>,
+}
+
+impl ItemInput {
+    pub fn stack(&self, count: i8) -> ItemStack {
+        ItemStack::new(self.item, count, self.nbt.clone())
+    }
+
+    /// Whether `stack` is this item, with at least the given tags
+    pub fn matches(&self, stack: &ItemStack) -> bool {
+        stack.item == self.item
+            && match (&self.nbt, &stack.nbt) {
+                (None, _) => true,
+                (Some(wanted), Some(nbt)) => snbt::compound_contains(nbt, wanted),
+                (Some(_), None) => false,
+            }
+    }
+
+    /// The item id without the namespace, used to name the item in feedback
+    pub fn name(&self) -> &'static str {
+        self.item.to_str()
+    }
+}
+
+impl CommandArg for ItemInput {
+    fn parse_arg(input: &mut ParseInput) -> Result<Self, CommandArgParseError> {
+        input.skip_whitespace();
+        let mut text = input.pop_word().to_owned();
+
+        // NBT with spaces in it spans several words. They're joined back with
+        // single spaces, which only matters inside quoted strings.
+        if let Some(start) = text.find('{') {
+            while snbt::compound_end(&text[start..]).is_none() {
+                input.skip_whitespace();
+                let word = input.pop_word();
+                if word.is_empty() {
+                    break;
+                }
+                text.push(' ');
+                text.push_str(word);
+            }
+        }
+
+        let (id, nbt) = match text.find('{') {
+            Some(start) => (&text[..start], Some(&text[start..])),
+            None => (text.as_str(), None),
+        };
+        let name = id.strip_prefix("minecraft:").unwrap_or(id);
+
+        let item = ItemKind::from_str(name)
+            .filter(|item| *item != ItemKind::Air)
+            .ok_or_else(|| CommandArgParseError::InvalidArgument {
+                expected: "item".to_owned(),
+                got: id.to_owned(),
+            })?;
+
+        let nbt = nbt
+            .map(snbt::parse_compound)
+            .transpose()
+            .map_err(|_| CommandArgParseError::InvalidArgument {
+                expected: "item NBT".to_owned(),
+                got: nbt.unwrap_or_default().to_owned(),
+            })?;
+
+        Ok(ItemInput { item, nbt })
+    }
+
+    fn display() -> Parser {
+        Parser::ItemStack
+    }
+}
+
+/// A named inventory slot, such as `hotbar.0`, `armor.head` or
+/// `weapon.offhand`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemSlot {
+    /// A fixed slot index in the player's inventory
+    Index(u16),
+    /// Whichever hotbar slot is selected
+    MainHand,
+}
+
+impl ItemSlot {
+    /// The player inventory slot this refers to
+    pub fn index(self, held: &HeldItem) -> u16 {
+        match self {
+            ItemSlot::Index(index) => index,
+            ItemSlot::MainHand => held.slot(),
+        }
+    }
+
+    /// The slot's name as written in commands
+    pub fn name(self) -> String {
+        match self {
+            ItemSlot::Index(5) => "armor.head".to_owned(),
+            ItemSlot::Index(6) => "armor.chest".to_owned(),
+            ItemSlot::Index(7) => "armor.legs".to_owned(),
+            ItemSlot::Index(8) => "armor.feet".to_owned(),
+            ItemSlot::Index(45) => "weapon.offhand".to_owned(),
+            ItemSlot::Index(index @ 36..=44) => format!("hotbar.{}", index - 36),
+            ItemSlot::Index(index) => format!("inventory.{}", index - 9),
+            ItemSlot::MainHand => "weapon.mainhand".to_owned(),
+        }
+    }
+}
+
+impl CommandArg for ItemSlot {
+    fn parse_arg(input: &mut ParseInput) -> Result<Self, CommandArgParseError> {
+        input.skip_whitespace();
+        let word = input.pop_word();
+
+        let numbered = |prefix: &str, first: u16, count: u16| {
+            word.strip_prefix(prefix)
+                .and_then(|n| n.parse::<u16>().ok())
+                .filter(|n| *n < count)
+                .map(|n| ItemSlot::Index(first + n))
+        };
+
+        let slot = match word {
+            "armor.head" => Some(ItemSlot::Index(5)),
+            "armor.chest" => Some(ItemSlot::Index(6)),
+            "armor.legs" => Some(ItemSlot::Index(7)),
+            "armor.feet" => Some(ItemSlot::Index(8)),
+            "weapon" | "weapon.mainhand" => Some(ItemSlot::MainHand),
+            "weapon.offhand" => Some(ItemSlot::Index(45)),
+            _ => numbered("hotbar.", 36, 9).or_else(|| numbered("inventory.", 9, 27)),
+        };
+
+        slot.ok_or_else(|| CommandArgParseError::InvalidArgument {
+            expected: "slot".to_owned(),
+            got: word.to_owned(),
+        })
+    }
+
+    fn display() -> Parser {
+        Parser::ItemSlot
+    }
+}
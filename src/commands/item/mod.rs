@@ -0,0 +1,148 @@
+pub mod arguments;
+
+use arguments::{ItemInput, ItemSlot};
+use valence::{
+    command::{handler::CommandResultEvent, parsers::EntitySelector},
+    command_macros::Command,
+    inventory::HeldItem,
+    prelude::*,
+    text::{Color, IntoText},
+    ItemStack,
+};
+
+use crate::commands::{feedback::CommandFeedback, selector::SelectorResolver};
+
+#[derive(Command, Debug, Clone)]
+#[paths("item")]
+#[scopes("command.item")]
+pub enum Command {
+    #[paths("replace entity {targets} {slot} with {item} {count?}")]
+    ReplaceWith {
+        targets: EntitySelector,
+        slot: ItemSlot,
+        item: ItemInput,
+        count: Option<i32>,
+    },
+    #[paths("replace entity {targets} {slot} from entity {source} {source_slot}")]
+    ReplaceFrom {
+        targets: EntitySelector,
+        slot: ItemSlot,
+        source: EntitySelector,
+        source_slot: ItemSlot,
+    },
+}
+
+impl Command {
+    fn targets(&self) -> &EntitySelector {
+        match self {
+            Command::ReplaceWith { targets, .. } | Command::ReplaceFrom { targets, .. } => targets,
+        }
+    }
+
+    fn slot(&self) -> ItemSlot {
+        match self {
+            Command::ReplaceWith { slot, .. } | Command::ReplaceFrom { slot, .. } => *slot,
+        }
+    }
+}
+
+/// Copies the stack in a player's slot, for `from entity`
+fn source_stack(
+    resolver: &SelectorResolver,
+    inventories: &Query<(&mut Inventory, &HeldItem), With<Client>>,
+    executor: Entity,
+    source: &EntitySelector,
+    slot: ItemSlot,
+) -> Result<ItemStack, Text> {
+    let sources = resolver.resolve(executor, source).map_err(|e| e.to_text())?;
+
+    let [source] = sources.as_slice() else {
+        return Err(Text::translate("argument.entity.toomany", []).color(Color::RED));
+    };
+
+    inventories
+        .get(*source)
+        .map(|(inventory, held)| inventory.slot(slot.index(held)).clone())
+        .map_err(|_| {
+            Text::translate("commands.item.source.no_such_slot", [slot.name().into_text()])
+                .color(Color::RED)
+        })
+}
+
+/// Replaces a slot in the inventories of the target players, either with a
+/// new stack or a copy of another player's slot.
+pub fn handle(
+    mut events: EventReader<CommandResultEvent<Command>>,
+    resolver: SelectorResolver,
+    mut inventories: Query<(&mut Inventory, &HeldItem), With<Client>>,
+    mut feedback: EventWriter<CommandFeedback>,
+) {
+    for event in events.read() {
+        let stack = match &event.result {
+            Command::ReplaceWith { item, count, .. } => {
+                let count = count.unwrap_or(1).clamp(1, i32::from(item.item.max_stack()));
+                Ok(item.stack(count as i8))
+            }
+            Command::ReplaceFrom {
+                source,
+                source_slot,
+                ..
+            } => source_stack(&resolver, &inventories, event.executor, source, *source_slot),
+        };
+
+        let stack = match stack {
+            Ok(stack) => stack,
+            Err(error) => {
+                feedback.send(CommandFeedback::new(event.executor, error));
+                continue;
+            }
+        };
+
+        let targets = match resolver.resolve(event.executor, event.result.targets()) {
+            Ok(targets) => targets,
+            Err(e) => {
+                feedback.send(CommandFeedback::new(event.executor, e.to_text()));
+                continue;
+            }
+        };
+
+        // Only players have inventories to put the item in
+        let players: Vec<Entity> = targets
+            .into_iter()
+            .filter(|target| inventories.contains(*target))
+            .collect();
+
+        if players.is_empty() {
+            feedback.send(CommandFeedback::new(
+                event.executor,
+                Text::translate(
+                    "commands.item.target.no_changes",
+                    [event.result.slot().name().into_text()],
+                )
+                .color(Color::RED),
+            ));
+            continue;
+        }
+
+        for player in &players {
+            if let Ok((mut inventory, held)) = inventories.get_mut(*player) {
+                let slot = event.result.slot().index(held);
+                inventory.set_slot(slot, stack.clone());
+            }
+        }
+
+        let item = stack.item.to_str().into_text();
+        let message = if let [player] = players.as_slice() {
+            Text::translate(
+                "commands.item.entity.set.success.single",
+                [resolver.display_name(*player).into_text(), item],
+            )
+        } else {
+            Text::translate(
+                "commands.item.entity.set.success.multiple",
+                [players.len().to_string().into_text(), item],
+            )
+        };
+        feedback.send(CommandFeedback::new(event.executor, message));
+    }
+}
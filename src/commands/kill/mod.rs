@@ -0,0 +1,79 @@
+use valence::{
+    command::{handler::CommandResultEvent, parsers::EntitySelector},
+    command_macros::Command,
+    prelude::*,
+    text::IntoText,
+};
+
+use crate::{
+    commands::{
+        feedback::CommandFeedback,
+        selector::{SelectorError, SelectorResolver},
+    },
+    mobs::Mob,
+    survival::{
+        damage::{DamageEvent, DamageSource},
+        Vitals,
+    },
+};
+
+#[derive(Command, Debug, Clone)]
+#[paths("kill {targets?}")]
+#[scopes("command.kill")]
+pub struct Command {
+    targets: Option<EntitySelector>,
+}
+
+/// Kills the targets, or the executor when none are given.
+///
+/// Players and mobs die through the usual damage path, so death messages and
+/// sounds still happen. Anything else is simply removed.
+pub fn handle(
+    mut commands: Commands,
+    mut events: EventReader<CommandResultEvent<Command>>,
+    resolver: SelectorResolver,
+    killable: Query<(), Or<(With<Vitals>, With<Mob>)>>,
+    mut damage: EventWriter<DamageEvent>,
+    mut feedback: EventWriter<CommandFeedback>,
+) {
+    for event in events.read() {
+        let targets = match &event.result.targets {
+            Some(selector) => resolver.resolve(event.executor, selector),
+            None if resolver.location(event.executor).is_some() => Ok(vec![event.executor]),
+            None => Err(SelectorError::RequiresEntity),
+        };
+
+        let targets = match targets {
+            Ok(targets) => targets,
+            Err(e) => {
+                feedback.send(CommandFeedback::new(event.executor, e.to_text()));
+                continue;
+            }
+        };
+
+        for target in &targets {
+            if killable.contains(*target) {
+                damage.send(DamageEvent {
+                    entity: *target,
+                    amount: f32::MAX,
+                    source: DamageSource::Kill,
+                });
+            } else {
+                commands.entity(*target).insert(Despawned);
+            }
+        }
+
+        let message = if let [target] = targets.as_slice() {
+            Text::translate(
+                "commands.kill.success.single",
+                [resolver.display_name(*target).into_text()],
+            )
+        } else {
+            Text::translate(
+                "commands.kill.success.multiple",
+                [targets.len().to_string().into_text()],
+            )
+        };
+        feedback.send(CommandFeedback::new(event.executor, message));
+    }
+}
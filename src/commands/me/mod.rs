@@ -0,0 +1,48 @@
+use valence::{
+    command::{handler::CommandResultEvent, parsers::GreedyString},
+    command_macros::Command,
+    prelude::*,
+    text::IntoText,
+};
+
+use crate::{
+    chat::{self, ChatState, SERVER_NAME},
+    commands::feedback::CommandFeedback,
+    setup::settings::Settings,
+};
+
+#[derive(Command, Debug, Clone)]
+#[paths("me {action}")]
+#[scopes("command.me")]
+pub struct Command {
+    action: GreedyString,
+}
+
+pub fn handle(
+    mut events: EventReader<CommandResultEvent<Command>>,
+    usernames: Query<&Username>,
+    mut clients: Query<&mut Client>,
+    mut chat: ResMut<ChatState>,
+    settings: Res<Settings>,
+    mut feedback: EventWriter<CommandFeedback>,
+) {
+    for event in events.read() {
+        let name = match usernames.get(event.executor) {
+            Ok(username) => {
+                if let Err(e) = chat.check_sender(event.executor, &username.0, &settings) {
+                    feedback.send(CommandFeedback::new(event.executor, e));
+                    continue;
+                }
+                username.0.clone()
+            }
+            Err(_) => SERVER_NAME.to_owned(),
+        };
+
+        let action = chat::filter(&event.result.action.0, &settings);
+
+        chat::broadcast(
+            &mut clients,
+            Text::translate("chat.type.emote", [name.into_text(), action.into_text()]),
+        );
+    }
+}
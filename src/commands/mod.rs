@@ -1,2 +1,25 @@
+pub mod back;
+pub mod bossbar;
+pub mod clear;
+pub mod feedback;
 pub mod gamemode;
+pub mod give;
+pub mod home;
+pub mod item;
+pub mod kill;
+pub mod me;
+pub mod msg;
+pub mod mute;
+pub mod say;
+pub mod scoreboard;
+pub mod selector;
+pub mod setworldspawn;
+pub mod snbt;
+pub mod spawn;
+pub mod stop;
+pub mod summon;
+pub mod team;
 pub mod teleport;
+pub mod title;
+pub mod tpa;
+pub mod warp;
@@ -0,0 +1,151 @@
+use valence::{
+    command::{
+        handler::CommandResultEvent,
+        parsers::{EntitySelector, GreedyString},
+    },
+    command_macros::Command,
+    prelude::*,
+    text::{Color, IntoText},
+};
+
+use crate::{
+    chat::{self, ChatState, SERVER_NAME},
+    commands::{
+        feedback::CommandFeedback,
+        selector::{SelectorError, SelectorResolver},
+    },
+    setup::settings::Settings,
+};
+
+#[derive(Command, Debug, Clone)]
+#[paths("msg {targets} {message}", "tell {targets} {message}", "w {targets} {message}")]
+#[scopes("command.msg")]
+pub struct Command {
+    targets: EntitySelector,
+    message: GreedyString,
+}
+
+/// Answers whoever last messaged the executor, or was messaged by them
+#[derive(Command, Debug, Clone)]
+#[paths("r {message}")]
+#[scopes("command.msg")]
+pub struct ReplyCommand {
+    message: GreedyString,
+}
+
+/// Whispers a message to each target, in the vanilla style.
+///
+/// Players are held to the same mutes, rate limit and filter as chat.
+fn send(
+    from: Entity,
+    targets: &[Entity],
+    message: &str,
+    usernames: &Query<&Username>,
+    chat: &mut ChatState,
+    settings: &Settings,
+    feedback: &mut EventWriter<CommandFeedback>,
+) {
+    let name_of = |entity: Entity| match usernames.get(entity) {
+        Ok(username) => username.0.clone(),
+        Err(_) => SERVER_NAME.to_owned(),
+    };
+
+    if let Ok(username) = usernames.get(from) {
+        if let Err(e) = chat.check_sender(from, &username.0, settings) {
+            feedback.send(CommandFeedback::new(from, e));
+            return;
+        }
+    }
+
+    let message = chat::filter(message, settings);
+    let from_name = name_of(from);
+    let style = |text: Text| text.color(Color::GRAY).italic();
+
+    for &to in targets {
+        feedback.send(CommandFeedback::new(
+            from,
+            style(Text::translate(
+                "commands.message.display.outgoing",
+                [name_of(to).into_text(), message.clone().into_text()],
+            )),
+        ));
+        feedback.send(CommandFeedback::new(
+            to,
+            style(Text::translate(
+                "commands.message.display.incoming",
+                [from_name.clone().into_text(), message.clone().into_text()],
+            )),
+        ));
+
+        chat.set_reply(from, to);
+    }
+}
+
+pub fn handle(
+    mut events: EventReader<CommandResultEvent<Command>>,
+    resolver: SelectorResolver,
+    usernames: Query<&Username>,
+    mut chat: ResMut<ChatState>,
+    settings: Res<Settings>,
+    mut feedback: EventWriter<CommandFeedback>,
+) {
+    for event in events.read() {
+        // Only players can be messaged, other entities would never see it
+        let targets = match resolver.resolve(event.executor, &event.result.targets) {
+            Ok(targets) => targets
+                .into_iter()
+                .filter(|target| resolver.is_player(*target))
+                .collect::<Vec<_>>(),
+            Err(e) => {
+                feedback.send(CommandFeedback::new(event.executor, e.to_text()));
+                continue;
+            }
+        };
+
+        if targets.is_empty() {
+            feedback.send(CommandFeedback::new(
+                event.executor,
+                SelectorError::NoPlayerFound.to_text(),
+            ));
+            continue;
+        }
+
+        send(
+            event.executor,
+            &targets,
+            &event.result.message.0,
+            &usernames,
+            &mut chat,
+            &settings,
+            &mut feedback,
+        );
+    }
+}
+
+pub fn handle_reply(
+    mut events: EventReader<CommandResultEvent<ReplyCommand>>,
+    usernames: Query<&Username>,
+    mut chat: ResMut<ChatState>,
+    settings: Res<Settings>,
+    mut feedback: EventWriter<CommandFeedback>,
+) {
+    for event in events.read() {
+        let Some(target) = chat.reply_target(event.executor) else {
+            feedback.send(CommandFeedback::new(
+                event.executor,
+                "You have nobody to reply to".color(Color::RED),
+            ));
+            continue;
+        };
+
+        send(
+            event.executor,
+            &[target],
+            &event.result.message.0,
+            &usernames,
+            &mut chat,
+            &settings,
+            &mut feedback,
+        );
+    }
+}
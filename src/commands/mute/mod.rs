@@ -0,0 +1,120 @@
+use valence::{
+    command::{handler::CommandResultEvent, parsers::EntitySelector},
+    command_macros::Command,
+    prelude::*,
+    text::{Color, IntoText},
+};
+
+use crate::{
+    chat::{self, ChatState, DurationArg},
+    commands::{
+        feedback::CommandFeedback,
+        selector::{SelectorError, SelectorResolver},
+    },
+};
+
+#[derive(Command, Debug, Clone)]
+#[paths("mute {targets} {duration?}")]
+#[scopes("command.mute")]
+pub struct Command {
+    targets: EntitySelector,
+    /// Mutes until unmuted when None
+    duration: Option<DurationArg>,
+}
+
+#[derive(Command, Debug, Clone)]
+#[paths("unmute {targets}")]
+#[scopes("command.mute")]
+pub struct UnmuteCommand {
+    targets: EntitySelector,
+}
+
+/// The players a selector matches, with their usernames
+fn resolve_players(
+    resolver: &SelectorResolver,
+    usernames: &Query<&Username>,
+    executor: Entity,
+    selector: &EntitySelector,
+) -> Result<Vec<(Entity, String)>, SelectorError> {
+    let players: Vec<_> = resolver
+        .resolve(executor, selector)?
+        .into_iter()
+        .filter_map(|target| Some((target, usernames.get(target).ok()?.0.clone())))
+        .collect();
+
+    if players.is_empty() {
+        return Err(SelectorError::NoPlayerFound);
+    }
+
+    Ok(players)
+}
+
+pub fn handle(
+    mut events: EventReader<CommandResultEvent<Command>>,
+    mut unmutes: EventReader<CommandResultEvent<UnmuteCommand>>,
+    resolver: SelectorResolver,
+    usernames: Query<&Username>,
+    mut chat: ResMut<ChatState>,
+    mut feedback: EventWriter<CommandFeedback>,
+) {
+    for event in events.read() {
+        let executor = event.executor;
+        let players = match resolve_players(&resolver, &usernames, executor, &event.result.targets) {
+            Ok(players) => players,
+            Err(e) => {
+                feedback.send(CommandFeedback::new(executor, e.to_text()));
+                continue;
+            }
+        };
+
+        let duration = event.result.duration.map(|duration| duration.0);
+        let length = match duration {
+            Some(duration) => format!(" for {}", chat::format_duration(duration)),
+            None => String::new(),
+        };
+
+        for (player, name) in &players {
+            chat.mute(name, duration);
+            feedback.send(CommandFeedback::new(
+                *player,
+                format!("You have been muted{length}").color(Color::RED),
+            ));
+        }
+
+        let names: Vec<_> = players.into_iter().map(|(_, name)| name).collect();
+        feedback.send(CommandFeedback::new(
+            executor,
+            format!("Muted {}{length}", names.join(", ")).color(Color::GOLD),
+        ));
+    }
+
+    for event in unmutes.read() {
+        let executor = event.executor;
+        let players = match resolve_players(&resolver, &usernames, executor, &event.result.targets) {
+            Ok(players) => players,
+            Err(e) => {
+                feedback.send(CommandFeedback::new(executor, e.to_text()));
+                continue;
+            }
+        };
+
+        let mut unmuted = vec![];
+        for (player, name) in players {
+            if chat.unmute(&name) {
+                feedback.send(CommandFeedback::new(
+                    player,
+                    "You are no longer muted".color(Color::GREEN),
+                ));
+                unmuted.push(name);
+            }
+        }
+
+        let message = if unmuted.is_empty() {
+            "Nobody was muted".color(Color::RED)
+        } else {
+            format!("Unmuted {}", unmuted.join(", ")).color(Color::GOLD)
+        };
+
+        feedback.send(CommandFeedback::new(executor, message));
+    }
+}
@@ -0,0 +1,36 @@
+use valence::{
+    command::{handler::CommandResultEvent, parsers::GreedyString},
+    command_macros::Command,
+    prelude::*,
+    text::IntoText,
+};
+
+use crate::chat::{self, SERVER_NAME};
+
+#[derive(Command, Debug, Clone)]
+#[paths("say {message}")]
+#[scopes("command.say")]
+pub struct Command {
+    message: GreedyString,
+}
+
+/// Announces a message to every player, unfiltered.
+pub fn handle(
+    mut events: EventReader<CommandResultEvent<Command>>,
+    usernames: Query<&Username>,
+    mut clients: Query<&mut Client>,
+) {
+    for event in events.read() {
+        let name = usernames
+            .get(event.executor)
+            .map_or_else(|_| SERVER_NAME.to_owned(), |username| username.0.clone());
+
+        chat::broadcast(
+            &mut clients,
+            Text::translate(
+                "chat.type.announcement",
+                [name.into_text(), event.result.message.0.clone().into_text()],
+            ),
+        );
+    }
+}
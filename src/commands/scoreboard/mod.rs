@@ -0,0 +1,295 @@
+use valence::{
+    command::{
+        handler::CommandResultEvent,
+        parsers::{entity_selector::EntitySelectors, EntitySelector, GreedyString},
+    },
+    command_macros::Command,
+    prelude::*,
+    text::IntoText,
+};
+
+use crate::{
+    commands::{
+        feedback::CommandFeedback,
+        selector::{SelectorError, SelectorResolver},
+    },
+    scoreboard::{DisplaySlot, Scoreboard, ScoreboardError},
+};
+
+#[derive(Command, Debug, Clone)]
+#[paths("scoreboard")]
+#[scopes("command.scoreboard")]
+pub enum Command {
+    #[paths("objectives add {name} {criterion} {display_name?}")]
+    AddObjective {
+        name: String,
+        criterion: String,
+        display_name: Option<GreedyString>,
+    },
+    #[paths("objectives remove {name}")]
+    RemoveObjective { name: String },
+    #[paths("objectives setdisplay {slot} {name?}")]
+    SetDisplay {
+        slot: DisplaySlot,
+        name: Option<String>,
+    },
+    #[paths("objectives list")]
+    ListObjectives {},
+    #[paths("players set {targets} {objective} {score}")]
+    SetScore {
+        targets: EntitySelector,
+        objective: String,
+        score: i32,
+    },
+    #[paths("players add {targets} {objective} {score}")]
+    AddScore {
+        targets: EntitySelector,
+        objective: String,
+        score: i32,
+    },
+    #[paths("players remove {targets} {objective} {score}")]
+    RemoveScore {
+        targets: EntitySelector,
+        objective: String,
+        score: i32,
+    },
+    #[paths("players reset {targets} {objective?}")]
+    ResetScore {
+        targets: EntitySelector,
+        objective: Option<String>,
+    },
+}
+
+/// Why a scoreboard command failed
+enum Error {
+    Selector(SelectorError),
+    Scoreboard(ScoreboardError),
+}
+
+impl From<SelectorError> for Error {
+    fn from(e: SelectorError) -> Self {
+        Error::Selector(e)
+    }
+}
+
+impl From<ScoreboardError> for Error {
+    fn from(e: ScoreboardError) -> Self {
+        Error::Scoreboard(e)
+    }
+}
+
+/// The score holder names a selector stands for.
+///
+/// A plain name that isn't online is used as is, so scores can be kept for
+/// offline or fake players, and `*` means everyone with a score. Players are
+/// tracked by name and other entities by UUID, as in vanilla.
+pub fn score_holders(
+    resolver: &SelectorResolver,
+    uuids: &Query<&UniqueId>,
+    scoreboard: &Scoreboard,
+    executor: Entity,
+    selector: &EntitySelector,
+) -> Result<Vec<String>, SelectorError> {
+    if let EntitySelector::SimpleSelector(EntitySelectors::SinglePlayer(name)) = selector {
+        return Ok(match name.as_str() {
+            "*" => scoreboard.holders(),
+            name => vec![name.to_owned()],
+        });
+    }
+
+    Ok(resolver
+        .resolve(executor, selector)?
+        .into_iter()
+        .filter_map(|entity| {
+            if resolver.is_player(entity) {
+                Some(resolver.display_name(entity))
+            } else {
+                uuids.get(entity).ok().map(|uuid| uuid.0.to_string())
+            }
+        })
+        .collect())
+}
+
+/// Picks the single or multiple form of a vanilla message, whose first
+/// argument is the holder name or the number of holders
+fn holders_text(holders: &[String]) -> (bool, Text) {
+    match holders {
+        [single] => (false, single.clone().into_text()),
+        _ => (true, holders.len().to_string().into_text()),
+    }
+}
+
+pub fn handle(
+    mut events: EventReader<CommandResultEvent<Command>>,
+    resolver: SelectorResolver,
+    uuids: Query<&UniqueId>,
+    mut scoreboard: ResMut<Scoreboard>,
+    mut feedback: EventWriter<CommandFeedback>,
+) {
+    for event in events.read() {
+        let message = run(&event.result, event.executor, &resolver, &uuids, &mut scoreboard);
+
+        let message = match message {
+            Ok(message) => message,
+            Err(Error::Selector(e)) => e.to_text(),
+            Err(Error::Scoreboard(e)) => e.to_text(),
+        };
+
+        feedback.send(CommandFeedback::new(event.executor, message));
+    }
+}
+
+fn run(
+    command: &Command,
+    executor: Entity,
+    resolver: &SelectorResolver,
+    uuids: &Query<&UniqueId>,
+    scoreboard: &mut Scoreboard,
+) -> Result<Text, Error> {
+    let holders = |selector: &EntitySelector, scoreboard: &Scoreboard| {
+        score_holders(resolver, uuids, scoreboard, executor, selector)
+    };
+
+    Ok(match command {
+        Command::AddObjective {
+            name,
+            criterion,
+            display_name,
+        } => {
+            let display_name = display_name.as_ref().map(|name| name.0.clone());
+            scoreboard.add_objective(name, criterion, display_name)?;
+
+            Text::translate(
+                "commands.scoreboard.objectives.add.success",
+                [name.clone().into_text()],
+            )
+        }
+        Command::RemoveObjective { name } => {
+            scoreboard.remove_objective(name)?;
+
+            Text::translate(
+                "commands.scoreboard.objectives.remove.success",
+                [name.clone().into_text()],
+            )
+        }
+        Command::SetDisplay { slot, name } => {
+            scoreboard.set_display(*slot, name.as_deref())?;
+
+            match name {
+                Some(name) => Text::translate(
+                    "commands.scoreboard.objectives.display.set",
+                    [slot.name().into_text(), name.clone().into_text()],
+                ),
+                None => Text::translate(
+                    "commands.scoreboard.objectives.display.cleared",
+                    [slot.name().into_text()],
+                ),
+            }
+        }
+        Command::ListObjectives {} => {
+            let names: Vec<_> = scoreboard.objectives().map(|(name, _)| name.as_str()).collect();
+
+            if names.is_empty() {
+                Text::translate("commands.scoreboard.objectives.list.empty", [])
+            } else {
+                Text::translate(
+                    "commands.scoreboard.objectives.list.success",
+                    [
+                        names.len().to_string().into_text(),
+                        names.join(", ").into_text(),
+                    ],
+                )
+            }
+        }
+        Command::SetScore {
+            targets,
+            objective,
+            score,
+        } => {
+            let holders = holders(targets, scoreboard)?;
+            for holder in &holders {
+                scoreboard.set_score(objective, holder, *score)?;
+            }
+
+            let (multiple, holders) = holders_text(&holders);
+            let key = if multiple {
+                "commands.scoreboard.players.set.success.multiple"
+            } else {
+                "commands.scoreboard.players.set.success.single"
+            };
+
+            Text::translate(
+                key,
+                [
+                    objective.clone().into_text(),
+                    holders,
+                    score.to_string().into_text(),
+                ],
+            )
+        }
+        Command::AddScore {
+            targets,
+            objective,
+            score,
+        }
+        | Command::RemoveScore {
+            targets,
+            objective,
+            score,
+        } => {
+            let removing = matches!(command, Command::RemoveScore { .. });
+            let amount = if removing { score.wrapping_neg() } else { *score };
+
+            let holders = holders(targets, scoreboard)?;
+            let mut now = 0;
+            for holder in &holders {
+                now = scoreboard.add_score(objective, holder, amount)?;
+            }
+
+            let (multiple, holder_text) = holders_text(&holders);
+            let key = match (removing, multiple) {
+                (false, false) => "commands.scoreboard.players.add.success.single",
+                (false, true) => "commands.scoreboard.players.add.success.multiple",
+                (true, false) => "commands.scoreboard.players.remove.success.single",
+                (true, true) => "commands.scoreboard.players.remove.success.multiple",
+            };
+
+            let mut with = vec![
+                score.to_string().into_text(),
+                objective.clone().into_text(),
+                holder_text,
+            ];
+            if !multiple {
+                with.push(now.to_string().into_text());
+            }
+
+            Text::translate(key, with)
+        }
+        Command::ResetScore { targets, objective } => {
+            let holders = holders(targets, scoreboard)?;
+            for holder in &holders {
+                scoreboard.reset_score(objective.as_deref(), holder)?;
+            }
+
+            let (multiple, holder_text) = holders_text(&holders);
+
+            match (objective, multiple) {
+                (None, false) => {
+                    Text::translate("commands.scoreboard.players.reset.all.single", [holder_text])
+                }
+                (None, true) => Text::translate(
+                    "commands.scoreboard.players.reset.all.multiple",
+                    [holder_text],
+                ),
+                (Some(objective), false) => Text::translate(
+                    "commands.scoreboard.players.reset.specific.single",
+                    [objective.clone().into_text(), holder_text],
+                ),
+                (Some(objective), true) => Text::translate(
+                    "commands.scoreboard.players.reset.specific.multiple",
+                    [objective.clone().into_text(), holder_text],
+                ),
+            }
+        }
+    })
+}
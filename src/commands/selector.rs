@@ -0,0 +1,542 @@
+use std::cmp::Ordering;
+
+use valence::{
+    command::parsers::{entity_selector::EntitySelectors, EntitySelector},
+    ecs::system::SystemParam,
+    prelude::*,
+    rand::{self, seq::SliceRandom},
+    text::{Color, IntoText},
+};
+
+use crate::{setup::settings::Settings, world::spawn::WorldSpawn};
+
+/// Covers every entity kind id in this protocol version. Ids past the last
+/// kind have no translation key, so overshooting is harmless.
+const ENTITY_KIND_IDS: i32 = 160;
+
+/// Why a selector didn't produce any targets.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SelectorError {
+    NoPlayerFound,
+    NoEntityFound,
+    /// `@s` was used by an executor that isn't an entity in the world
+    RequiresEntity,
+    UnknownOption(String),
+    ExpectedValue(String),
+    InvalidGameMode(String),
+    InvalidSort(String),
+    InvalidInteger(String),
+    InvalidDouble(String),
+    InvalidType(String),
+    InvalidRange(String),
+    NegativeDistance,
+    LimitTooSmall,
+}
+
+impl SelectorError {
+    pub fn to_text(&self) -> Text {
+        let (key, arg) = match self {
+            Self::NoPlayerFound => ("argument.entity.notfound.player", None),
+            Self::NoEntityFound => ("argument.entity.notfound.entity", None),
+            Self::RequiresEntity => ("permissions.requires.entity", None),
+            Self::UnknownOption(option) => ("argument.entity.options.unknown", Some(option)),
+            Self::ExpectedValue(option) => ("argument.entity.options.valueless", Some(option)),
+            Self::InvalidGameMode(mode) => ("argument.entity.options.mode.invalid", Some(mode)),
+            Self::InvalidSort(sort) => ("argument.entity.options.sort.irreversible", Some(sort)),
+            Self::InvalidInteger(value) => ("parsing.int.invalid", Some(value)),
+            Self::InvalidDouble(value) => ("parsing.double.invalid", Some(value)),
+            Self::InvalidType(kind) => ("argument.entity.options.type.invalid", Some(kind)),
+            Self::InvalidRange(_) => ("argument.range.empty", None),
+            Self::NegativeDistance => ("argument.entity.options.distance.negative", None),
+            Self::LimitTooSmall => ("argument.entity.options.limit.toosmall", None),
+        };
+
+        let with: Vec<Text> = arg.into_iter().map(|arg| arg.clone().into_text()).collect();
+        Text::translate(key, with).color(Color::RED)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Sort {
+    Nearest,
+    Furthest,
+    Random,
+    Arbitrary,
+}
+
+/// A value that may be negated with `!`, as in `gamemode=!creative`.
+#[derive(Clone, Debug)]
+pub struct Negatable<T> {
+    pub value: T,
+    pub negated: bool,
+}
+
+impl<T: PartialEq> Negatable<T> {
+    fn matches(&self, value: &T) -> bool {
+        (self.value == *value) != self.negated
+    }
+}
+
+/// An inclusive range such as `..5`, `2..` or `3`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Range {
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+impl Range {
+    fn parse(value: &str) -> Result<Self, SelectorError> {
+        let bound = |s: &str| -> Result<Option<f64>, SelectorError> {
+            if s.is_empty() {
+                Ok(None)
+            } else {
+                s.parse()
+                    .map(Some)
+                    .map_err(|_| SelectorError::InvalidDouble(s.to_owned()))
+            }
+        };
+
+        let range = match value.split_once("..") {
+            Some((min, max)) => Self {
+                min: bound(min)?,
+                max: bound(max)?,
+            },
+            None => {
+                let exact = bound(value)?;
+                Self {
+                    min: exact,
+                    max: exact,
+                }
+            }
+        };
+
+        if range.min.is_none() && range.max.is_none() {
+            return Err(SelectorError::InvalidRange(value.to_owned()));
+        }
+
+        Ok(range)
+    }
+
+    pub fn contains(&self, value: f64) -> bool {
+        self.min.map_or(true, |min| value >= min) && self.max.map_or(true, |max| value <= max)
+    }
+}
+
+/// The arguments inside the brackets of a complex selector.
+#[derive(Clone, Debug, Default)]
+pub struct SelectorOptions {
+    pub names: Vec<Negatable<String>>,
+    pub game_modes: Vec<Negatable<GameMode>>,
+    /// Entity type ids without the `minecraft:` namespace
+    pub types: Vec<Negatable<String>>,
+    pub limit: Option<usize>,
+    pub sort: Option<Sort>,
+    pub distance: Option<Range>,
+    pub x: Option<f64>,
+    pub y: Option<f64>,
+    pub z: Option<f64>,
+    pub dx: Option<f64>,
+    pub dy: Option<f64>,
+    pub dz: Option<f64>,
+}
+
+impl SelectorOptions {
+    /// Parses `key=value` pairs separated by commas
+    pub fn parse(args: &str) -> Result<Self, SelectorError> {
+        let args = args.trim().trim_start_matches('[').trim_end_matches(']');
+        let mut options = Self::default();
+
+        for pair in split_args(args) {
+            let pair = pair.trim();
+            if pair.is_empty() {
+                continue;
+            }
+
+            let Some((key, value)) = pair.split_once('=') else {
+                return Err(SelectorError::ExpectedValue(pair.to_owned()));
+            };
+
+            let key = key.trim();
+            let value = value.trim();
+
+            let (negated, value) = match value.strip_prefix('!') {
+                Some(value) => (true, value.trim()),
+                None => (false, value),
+            };
+            let value = unquote(value);
+
+            match key {
+                "name" => options.names.push(Negatable {
+                    value: value.to_owned(),
+                    negated,
+                }),
+                "gamemode" => options.game_modes.push(Negatable {
+                    value: parse_game_mode(value)
+                        .ok_or_else(|| SelectorError::InvalidGameMode(value.to_owned()))?,
+                    negated,
+                }),
+                "limit" => {
+                    let limit: i32 = value
+                        .parse()
+                        .map_err(|_| SelectorError::InvalidInteger(value.to_owned()))?;
+                    if limit < 1 {
+                        return Err(SelectorError::LimitTooSmall);
+                    }
+                    options.limit = Some(limit as usize);
+                }
+                "type" => options.types.push(Negatable {
+                    value: parse_entity_type(value)?,
+                    negated,
+                }),
+                "distance" => {
+                    let range = Range::parse(value)?;
+                    if range.min.is_some_and(|min| min < 0.0)
+                        || range.max.is_some_and(|max| max < 0.0)
+                    {
+                        return Err(SelectorError::NegativeDistance);
+                    }
+                    options.distance = Some(range);
+                }
+                "x" => options.x = Some(parse_double(value)?),
+                "y" => options.y = Some(parse_double(value)?),
+                "z" => options.z = Some(parse_double(value)?),
+                "dx" => options.dx = Some(parse_double(value)?),
+                "dy" => options.dy = Some(parse_double(value)?),
+                "dz" => options.dz = Some(parse_double(value)?),
+                "sort" => {
+                    options.sort = Some(match value {
+                        "nearest" => Sort::Nearest,
+                        "furthest" => Sort::Furthest,
+                        "random" => Sort::Random,
+                        "arbitrary" => Sort::Arbitrary,
+                        _ => return Err(SelectorError::InvalidSort(value.to_owned())),
+                    })
+                }
+                _ => return Err(SelectorError::UnknownOption(key.to_owned())),
+            }
+        }
+
+        Ok(options)
+    }
+
+    /// The position the selector is evaluated from, with `x`, `y` and `z`
+    /// replacing the executor's coordinates
+    fn origin(&self, executor_origin: DVec3) -> DVec3 {
+        DVec3::new(
+            self.x.unwrap_or(executor_origin.x),
+            self.y.unwrap_or(executor_origin.y),
+            self.z.unwrap_or(executor_origin.z),
+        )
+    }
+
+    /// Whether any positional argument was given, which makes `@a` and `@e`
+    /// select from the executor's layer only
+    fn is_positional(&self) -> bool {
+        self.distance.is_some()
+            || self.x.is_some()
+            || self.y.is_some()
+            || self.z.is_some()
+            || self.has_volume()
+    }
+
+    fn has_volume(&self) -> bool {
+        self.dx.is_some() || self.dy.is_some() || self.dz.is_some()
+    }
+
+    /// Checks `distance` and the `dx`/`dy`/`dz` box against a position
+    fn contains(&self, origin: DVec3, pos: DVec3) -> bool {
+        if let Some(distance) = self.distance {
+            if !distance.contains(pos.distance(origin)) {
+                return false;
+            }
+        }
+
+        if self.has_volume() {
+            let delta = DVec3::new(
+                self.dx.unwrap_or(0.0),
+                self.dy.unwrap_or(0.0),
+                self.dz.unwrap_or(0.0),
+            );
+            let min = origin.min(origin + delta);
+            // Vanilla grows the box by one block so a delta of 0 selects the
+            // block the origin is in.
+            let max = origin.max(origin + delta) + DVec3::ONE;
+
+            if pos.cmplt(min).any() || pos.cmpge(max).any() {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+fn parse_double(value: &str) -> Result<f64, SelectorError> {
+    value
+        .parse()
+        .map_err(|_| SelectorError::InvalidDouble(value.to_owned()))
+}
+
+/// Checks an entity type id (`cow` or `minecraft:cow`) against the known
+/// entity kinds and returns it without the namespace.
+fn parse_entity_type(value: &str) -> Result<String, SelectorError> {
+    let name = value.strip_prefix("minecraft:").unwrap_or(value);
+
+    if entity_kind_from_name(name).is_some() {
+        Ok(name.to_owned())
+    } else {
+        Err(SelectorError::InvalidType(value.to_owned()))
+    }
+}
+
+/// Looks up an entity kind by its id, without the namespace
+pub fn entity_kind_from_name(name: &str) -> Option<EntityKind> {
+    let key = format!("entity.minecraft.{name}");
+
+    (0..ENTITY_KIND_IDS)
+        .map(EntityKind::new)
+        .find(|kind| kind.translation_key() == Some(key.as_str()))
+}
+
+/// The entity id of a kind without the namespace, such as `cow`
+pub fn entity_kind_name(kind: EntityKind) -> Option<&'static str> {
+    kind.translation_key()
+        .and_then(|key| key.strip_prefix("entity.minecraft."))
+}
+
+/// Splits on commas that aren't inside quotes.
+fn split_args(args: &str) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut in_quotes = false;
+    let mut start = 0;
+
+    for (i, c) in args.char_indices() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => {
+                parts.push(&args[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+
+    parts.push(&args[start..]);
+    parts
+}
+
+fn unquote(value: &str) -> &str {
+    value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .unwrap_or(value)
+}
+
+/// Accepts the vanilla names, their abbreviations and the numeric ids.
+pub fn parse_game_mode(value: &str) -> Option<GameMode> {
+    match value {
+        "survival" | "s" | "0" => Some(GameMode::Survival),
+        "creative" | "c" | "1" => Some(GameMode::Creative),
+        "adventure" | "a" | "2" => Some(GameMode::Adventure),
+        "spectator" | "sp" | "3" => Some(GameMode::Spectator),
+        _ => None,
+    }
+}
+
+/// Resolves entity selectors against the world.
+///
+/// Commands take this as a system parameter and call `resolve` with the
+/// executor and the parsed selector. Handlers that also need to mutate the
+/// components read here should put it in a `ParamSet`.
+#[derive(SystemParam)]
+pub struct SelectorResolver<'w, 's> {
+    entities: Query<
+        'w,
+        's,
+        (
+            Entity,
+            &'static Position,
+            &'static EntityLayerId,
+            Option<&'static Username>,
+            Option<&'static GameMode>,
+            &'static EntityKind,
+            Has<Client>,
+        ),
+    >,
+    spawns: Query<'w, 's, &'static WorldSpawn>,
+    settings: Res<'w, Settings>,
+}
+
+impl SelectorResolver<'_, '_> {
+    /// Returns the matching entities, or an error if none matched
+    pub fn resolve(
+        &self,
+        executor: Entity,
+        selector: &EntitySelector,
+    ) -> Result<Vec<Entity>, SelectorError> {
+        let (base, options) = match selector {
+            EntitySelector::SimpleSelector(base) => (base, SelectorOptions::default()),
+            EntitySelector::ComplexSelector(base, args) => (base, SelectorOptions::parse(args)?),
+        };
+
+        self.resolve_with(executor, base, &options)
+    }
+
+    /// The position and layer selectors are evaluated from.
+    ///
+    /// Executors outside the world (console, RCON) select from the world
+    /// spawn on every layer.
+    pub fn origin(&self, executor: Entity) -> (DVec3, Option<Entity>) {
+        match self.entities.get(executor) {
+            Ok((_, pos, layer, ..)) => (pos.0, Some(layer.0)),
+            Err(_) => {
+                let spawn = self.spawns.iter().next();
+                (spawn.map_or(self.settings.spawn_point, |spawn| spawn.position), None)
+            }
+        }
+    }
+
+    pub fn resolve_with(
+        &self,
+        executor: Entity,
+        base: &EntitySelectors,
+        options: &SelectorOptions,
+    ) -> Result<Vec<Entity>, SelectorError> {
+        let (origin, origin_layer) = self.origin(executor);
+        let origin = options.origin(origin);
+
+        let (players_only, mut sort, mut limit) = match base {
+            EntitySelectors::AllEntities => (false, Sort::Arbitrary, None),
+            EntitySelectors::AllPlayers => (true, Sort::Arbitrary, None),
+            EntitySelectors::NearestPlayer => (true, Sort::Nearest, Some(1)),
+            EntitySelectors::RandomPlayer => (true, Sort::Random, Some(1)),
+            EntitySelectors::SelfPlayer => {
+                return match self.entities.get(executor) {
+                    Ok(target)
+                        if self.matches(&target, options)
+                            && options.contains(origin, target.1 .0) =>
+                    {
+                        Ok(vec![executor])
+                    }
+                    Ok(_) => Err(SelectorError::NoEntityFound),
+                    Err(_) => Err(SelectorError::RequiresEntity),
+                };
+            }
+            EntitySelectors::SinglePlayer(name) => {
+                return self
+                    .entities
+                    .iter()
+                    .find(|(.., username, _, _, is_client)| {
+                        *is_client && username.is_some_and(|username| username.0 == *name)
+                    })
+                    .map(|(entity, ..)| vec![entity])
+                    .ok_or(SelectorError::NoPlayerFound);
+            }
+        };
+
+        sort = options.sort.unwrap_or(sort);
+        limit = options.limit.or(limit);
+
+        // `@a` and `@e` span every layer unless a position is involved, the
+        // nearest/random selectors always stay on the executor's layer.
+        let same_layer_only = match base {
+            EntitySelectors::AllEntities | EntitySelectors::AllPlayers => {
+                options.is_positional()
+            }
+            _ => true,
+        };
+
+        let mut targets: Vec<(Entity, f64)> = self
+            .entities
+            .iter()
+            .filter(|(.., is_client)| !players_only || *is_client)
+            .filter(|(_, _, layer, ..)| {
+                !same_layer_only || origin_layer.map_or(true, |origin| layer.0 == origin)
+            })
+            .filter(|target| self.matches(target, options))
+            .filter(|(_, pos, ..)| options.contains(origin, pos.0))
+            .map(|(entity, pos, ..)| (entity, pos.0.distance_squared(origin)))
+            .collect();
+
+        match sort {
+            Sort::Nearest => {
+                targets.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal))
+            }
+            Sort::Furthest => {
+                targets.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal))
+            }
+            Sort::Random => targets.shuffle(&mut rand::thread_rng()),
+            Sort::Arbitrary => {}
+        }
+
+        if let Some(limit) = limit {
+            targets.truncate(limit);
+        }
+
+        if targets.is_empty() {
+            return Err(if players_only {
+                SelectorError::NoPlayerFound
+            } else {
+                SelectorError::NoEntityFound
+            });
+        }
+
+        Ok(targets.into_iter().map(|(entity, _)| entity).collect())
+    }
+
+    fn matches(
+        &self,
+        (_, _, _, username, game_mode, kind, _): &(
+            Entity,
+            &Position,
+            &EntityLayerId,
+            Option<&Username>,
+            Option<&GameMode>,
+            &EntityKind,
+            bool,
+        ),
+        options: &SelectorOptions,
+    ) -> bool {
+        let name_matches = options.names.iter().all(|name| {
+            let username = username.map(|username| username.0.clone()).unwrap_or_default();
+            name.matches(&username)
+        });
+
+        let game_mode_matches = options.game_modes.iter().all(|mode| match game_mode {
+            Some(game_mode) => mode.matches(game_mode),
+            None => false,
+        });
+
+        let type_matches = options.types.iter().all(|ty| {
+            let name = entity_kind_name(**kind).unwrap_or_default().to_owned();
+            ty.matches(&name)
+        });
+
+        name_matches && game_mode_matches && type_matches
+    }
+
+    /// The name shown for a target in command feedback
+    pub fn display_name(&self, entity: Entity) -> String {
+        match self.entities.get(entity) {
+            Ok((_, _, _, Some(username), ..)) => username.0.clone(),
+            Ok((_, _, _, None, _, kind, _)) => entity_kind_name(*kind)
+                .map(str::to_owned)
+                .unwrap_or_else(|| format!("{entity:?}")),
+            _ => format!("{entity:?}"),
+        }
+    }
+
+    /// The position and layer of an entity, if it is in the world
+    pub fn location(&self, entity: Entity) -> Option<(DVec3, Entity)> {
+        self.entities
+            .get(entity)
+            .ok()
+            .map(|(_, pos, layer, ..)| (pos.0, layer.0))
+    }
+
+    pub fn is_player(&self, entity: Entity) -> bool {
+        self.entities
+            .get(entity)
+            .is_ok_and(|(.., is_client)| is_client)
+    }
+}
@@ -0,0 +1,110 @@
+use valence::{
+    command::handler::CommandResultEvent,
+    command_macros::Command,
+    entity::Look,
+    prelude::*,
+    spawn::RespawnPosition,
+    text::IntoText,
+};
+
+use crate::{
+    commands::{
+        feedback::CommandFeedback,
+        selector::SelectorError,
+        teleport::coordinates::{Coordinate, Coordinates},
+    },
+    world::spawn::{LayerName, SpawnPoints, WorldSpawn},
+};
+
+#[derive(Command, Debug, Clone)]
+#[paths("setworldspawn {pos?} {angle?}")]
+#[scopes("command.setworldspawn")]
+pub struct Command {
+    pos: Option<Coordinates>,
+    angle: Option<f32>,
+}
+
+/// Moves the spawn of the executor's layer, or of every layer when run from
+/// outside the world.
+pub fn handle(
+    mut events: EventReader<CommandResultEvent<Command>>,
+    entities: Query<(&Position, &Look, &EntityLayerId)>,
+    mut layers: Query<(Entity, &LayerName, &mut WorldSpawn)>,
+    mut respawn_positions: Query<(&EntityLayerId, &mut RespawnPosition)>,
+    mut spawn_points: ResMut<SpawnPoints>,
+    mut feedback: EventWriter<CommandFeedback>,
+) {
+    for event in events.read() {
+        let executor = event.executor;
+        let executor_location = entities.get(executor).ok();
+
+        // Without an executor only fully absolute coordinates make sense
+        let absolute = matches!(
+            event.result.pos,
+            Some(Coordinates::World(coordinates))
+                if coordinates.iter().all(|c| matches!(c, Coordinate::Absolute(_)))
+        );
+
+        let pos = match (executor_location, event.result.pos) {
+            (Some((pos, ..)), None) => pos.0,
+            (Some((pos, look, _)), Some(target)) => target.resolve(pos.0, *look),
+            (None, Some(target)) if absolute => target.resolve(DVec3::ZERO, Look::default()),
+            (None, _) => {
+                feedback.send(CommandFeedback::new(
+                    executor,
+                    SelectorError::RequiresEntity.to_text(),
+                ));
+                continue;
+            }
+        };
+        let origin_layer = executor_location.map(|(.., layer)| layer.0);
+        let block = BlockPos::new(
+            pos.x.floor() as i32,
+            pos.y.floor() as i32,
+            pos.z.floor() as i32,
+        );
+        let angle = event.result.angle.unwrap_or(0.0);
+
+        let spawn = WorldSpawn {
+            position: DVec3::new(
+                f64::from(block.x) + 0.5,
+                f64::from(block.y),
+                f64::from(block.z) + 0.5,
+            ),
+            look: Look {
+                yaw: angle,
+                pitch: 0.0,
+            },
+        };
+
+        for (layer, name, mut world_spawn) in &mut layers {
+            if origin_layer.is_some_and(|origin_layer| origin_layer != layer) {
+                continue;
+            }
+
+            *world_spawn = spawn;
+            spawn_points.set(name, &spawn);
+
+            // Keeps compasses pointing at the new spawn
+            for (layer_id, mut respawn_position) in &mut respawn_positions {
+                if layer_id.0 == layer {
+                    respawn_position.pos = block;
+                    respawn_position.yaw = angle;
+                }
+            }
+        }
+
+        feedback.send(CommandFeedback::new(
+            executor,
+            Text::translate(
+                "commands.setworldspawn.success",
+                [
+                    block.x.to_string().into_text(),
+                    block.y.to_string().into_text(),
+                    block.z.to_string().into_text(),
+                    angle.to_string().into_text(),
+                ],
+            ),
+        ));
+    }
+}
@@ -0,0 +1,534 @@
+use valence::{
+    nbt::{Compound, List, Value},
+    prelude::*,
+    text::{Color, IntoText},
+};
+
+/// How deep compounds and lists may nest, as in vanilla's NBT reader
+const MAX_DEPTH: u32 = 512;
+
+/// Why a piece of SNBT couldn't be read.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SnbtError {
+    ExpectedCompound,
+    ExpectedKey,
+    ExpectedValue,
+    UnclosedString,
+    /// A list or array holding values of different types
+    MixedList(&'static str, &'static str),
+    TrailingData,
+    /// Compounds and lists nested deeper than `MAX_DEPTH`
+    TooDeep,
+}
+
+impl SnbtError {
+    pub fn to_text(&self) -> Text {
+        let (key, with): (_, Vec<Text>) = match self {
+            Self::ExpectedCompound => ("argument.nbt.expected.compound", vec![]),
+            Self::ExpectedKey => ("argument.nbt.expected.key", vec![]),
+            Self::ExpectedValue => ("argument.nbt.expected.value", vec![]),
+            Self::UnclosedString => ("parsing.quote.expected.end", vec![]),
+            Self::MixedList(value, list) => (
+                "argument.nbt.list.mixed",
+                vec![(*value).into_text(), (*list).into_text()],
+            ),
+            Self::TrailingData => ("argument.nbt.trailing", vec![]),
+            Self::TooDeep => {
+                return format!("NBT can't be nested more than {MAX_DEPTH} levels deep")
+                    .color(Color::RED)
+            }
+        };
+
+        Text::translate(key, with).color(Color::RED)
+    }
+}
+
+/// Reads a compound tag written as SNBT, such as
+/// `{display:{Name:'"Bob"'},Damage:5}`.
+pub fn parse_compound(input: &str) -> Result<Compound, SnbtError> {
+    let mut reader = Reader {
+        input,
+        pos: 0,
+        depth: 0,
+    };
+
+    reader.skip_whitespace();
+    if reader.peek() != Some('{') {
+        return Err(SnbtError::ExpectedCompound);
+    }
+    let compound = reader.nested(Reader::compound)?;
+
+    reader.skip_whitespace();
+    if reader.pos < input.len() {
+        return Err(SnbtError::TrailingData);
+    }
+
+    Ok(compound)
+}
+
+/// Finds where the SNBT compound starting `input` ends, so it can be split
+/// off whatever follows. Returns `None` while brackets or quotes are open.
+pub fn compound_end(input: &str) -> Option<usize> {
+    let mut quote = None;
+    let mut escaped = false;
+    let mut depth = 0;
+
+    for (i, c) in input.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+
+        match (c, quote) {
+            ('\\', Some(_)) => escaped = true,
+            (c, Some(open)) if c == open => quote = None,
+            (_, Some(_)) => {}
+            ('"' | '\'', None) => quote = Some(c),
+            ('{' | '[', None) => depth += 1,
+            ('}' | ']', None) => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Whether `big` has every tag of `small` at the same value, with nested
+/// compounds compared the same way. Used to match items against a partial
+/// tag, like vanilla's item predicates.
+pub fn compound_contains(big: &Compound, small: &Compound) -> bool {
+    small.iter().all(|(key, value)| match (big.get(key), value) {
+        (Some(Value::Compound(big)), Value::Compound(small)) => compound_contains(big, small),
+        (Some(found), value) => found == value,
+        (None, _) => false,
+    })
+}
+
+/// Writes a compound as SNBT that `parse_compound` reads back unchanged
+pub fn to_snbt(compound: &Compound) -> String {
+    let mut out = String::new();
+    write_compound(&mut out, compound);
+    out
+}
+
+fn write_string(out: &mut String, string: &str) {
+    out.push('"');
+    for c in string.chars() {
+        if matches!(c, '"' | '\\') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out.push('"');
+}
+
+fn write_compound(out: &mut String, compound: &Compound) {
+    out.push('{');
+    for (i, (key, value)) in compound.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+
+        let plain = !key.is_empty()
+            && key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '+'));
+        if plain {
+            out.push_str(key);
+        } else {
+            write_string(out, key);
+        }
+        out.push(':');
+        write_value(out, value);
+    }
+    out.push('}');
+}
+
+/// Writes `values` between brackets, with `prefix` for typed arrays
+fn write_seq<T>(
+    out: &mut String,
+    prefix: &str,
+    values: &[T],
+    mut write: impl FnMut(&mut String, &T),
+) {
+    out.push('[');
+    out.push_str(prefix);
+    for (i, value) in values.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        write(out, value);
+    }
+    out.push(']');
+}
+
+fn write_value(out: &mut String, value: &Value) {
+    match value {
+        Value::Byte(v) => out.push_str(&format!("{v}b")),
+        Value::Short(v) => out.push_str(&format!("{v}s")),
+        Value::Int(v) => out.push_str(&v.to_string()),
+        Value::Long(v) => out.push_str(&format!("{v}L")),
+        Value::Float(v) => out.push_str(&format!("{v}f")),
+        Value::Double(v) => out.push_str(&format!("{v}d")),
+        Value::String(v) => write_string(out, v),
+        Value::Compound(v) => write_compound(out, v),
+        Value::List(v) => write_list(out, v),
+        Value::ByteArray(v) => write_seq(out, "B;", v, |out, v| out.push_str(&format!("{v}b"))),
+        Value::IntArray(v) => write_seq(out, "I;", v, |out, v| out.push_str(&v.to_string())),
+        Value::LongArray(v) => write_seq(out, "L;", v, |out, v| out.push_str(&format!("{v}L"))),
+    }
+}
+
+fn write_list(out: &mut String, list: &List) {
+    macro_rules! seq {
+        ($values:expr, $variant:ident) => {
+            write_seq(out, "", $values, |out, v| write_value(out, &Value::$variant(v.clone())))
+        };
+    }
+
+    match list {
+        List::End => out.push_str("[]"),
+        List::Byte(v) => seq!(v, Byte),
+        List::Short(v) => seq!(v, Short),
+        List::Int(v) => seq!(v, Int),
+        List::Long(v) => seq!(v, Long),
+        List::Float(v) => seq!(v, Float),
+        List::Double(v) => seq!(v, Double),
+        List::ByteArray(v) => seq!(v, ByteArray),
+        List::String(v) => seq!(v, String),
+        List::List(v) => seq!(v, List),
+        List::Compound(v) => seq!(v, Compound),
+        List::IntArray(v) => seq!(v, IntArray),
+        List::LongArray(v) => seq!(v, LongArray),
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Byte(_) => "TAG_Byte",
+        Value::Short(_) => "TAG_Short",
+        Value::Int(_) => "TAG_Int",
+        Value::Long(_) => "TAG_Long",
+        Value::Float(_) => "TAG_Float",
+        Value::Double(_) => "TAG_Double",
+        Value::ByteArray(_) => "TAG_Byte_Array",
+        Value::String(_) => "TAG_String",
+        Value::List(_) => "TAG_List",
+        Value::Compound(_) => "TAG_Compound",
+        Value::IntArray(_) => "TAG_Int_Array",
+        Value::LongArray(_) => "TAG_Long_Array",
+    }
+}
+
+/// Builds a list out of values that must all share the first one's type
+fn into_list(values: Vec<Value>) -> Result<List, SnbtError> {
+    let Some(first) = values.first() else {
+        return Ok(List::End);
+    };
+    let expected = type_name(first);
+
+    macro_rules! collect {
+        ($variant:ident) => {
+            values
+                .into_iter()
+                .map(|value| match value {
+                    Value::$variant(value) => Ok(value),
+                    other => Err(SnbtError::MixedList(type_name(&other), expected)),
+                })
+                .collect::<Result<Vec<_>, _>>()
+                .map(List::$variant)
+        };
+    }
+
+    match first {
+        Value::Byte(_) => collect!(Byte),
+        Value::Short(_) => collect!(Short),
+        Value::Int(_) => collect!(Int),
+        Value::Long(_) => collect!(Long),
+        Value::Float(_) => collect!(Float),
+        Value::Double(_) => collect!(Double),
+        Value::ByteArray(_) => collect!(ByteArray),
+        Value::String(_) => collect!(String),
+        Value::List(_) => collect!(List),
+        Value::Compound(_) => collect!(Compound),
+        Value::IntArray(_) => collect!(IntArray),
+        Value::LongArray(_) => collect!(LongArray),
+    }
+}
+
+/// Reads an unquoted value: a number with an optional type suffix, a
+/// boolean, or otherwise a plain string
+fn parse_unquoted(token: &str) -> Value {
+    let (digits, suffix) = match token.char_indices().last() {
+        Some((i, c)) if c.is_ascii_alphabetic() => (&token[..i], Some(c.to_ascii_lowercase())),
+        _ => (token, None),
+    };
+
+    let number = match suffix {
+        Some('b') => digits.parse().ok().map(Value::Byte),
+        Some('s') => digits.parse().ok().map(Value::Short),
+        Some('l') => digits.parse().ok().map(Value::Long),
+        Some('f') => digits.parse().ok().map(Value::Float),
+        Some('d') => digits.parse().ok().map(Value::Double),
+        Some(_) => None,
+        None if token.contains(['.', 'e', 'E']) => token.parse().ok().map(Value::Double),
+        None => token.parse().ok().map(Value::Int),
+    };
+
+    number.unwrap_or_else(|| match token {
+        "true" => Value::Byte(1),
+        "false" => Value::Byte(0),
+        _ => Value::String(token.to_owned()),
+    })
+}
+
+struct Reader<'a> {
+    input: &'a str,
+    pos: usize,
+    /// How many compounds and lists are open
+    depth: u32,
+}
+
+impl Reader<'_> {
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.next();
+        }
+    }
+
+    /// Skips whitespace and consumes `expected` if it comes next
+    fn eat(&mut self, expected: char) -> bool {
+        self.skip_whitespace();
+        if self.peek() == Some(expected) {
+            self.next();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn unquoted(&mut self) -> &str {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '+'))
+        {
+            self.next();
+        }
+        &self.input[start..self.pos]
+    }
+
+    fn quoted(&mut self, quote: char) -> Result<String, SnbtError> {
+        let mut string = String::new();
+
+        loop {
+            match self.next() {
+                Some('\\') => string.extend(self.next()),
+                Some(c) if c == quote => return Ok(string),
+                Some(c) => string.push(c),
+                None => return Err(SnbtError::UnclosedString),
+            }
+        }
+    }
+
+    fn key(&mut self) -> Result<String, SnbtError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(quote @ ('"' | '\'')) => {
+                self.next();
+                self.quoted(quote)
+            }
+            _ => match self.unquoted() {
+                "" => Err(SnbtError::ExpectedKey),
+                key => Ok(key.to_owned()),
+            },
+        }
+    }
+
+    /// Reads a compound or list, failing once they're nested too deep
+    fn nested<T>(
+        &mut self,
+        read: fn(&mut Self) -> Result<T, SnbtError>,
+    ) -> Result<T, SnbtError> {
+        if self.depth >= MAX_DEPTH {
+            return Err(SnbtError::TooDeep);
+        }
+
+        self.depth += 1;
+        let result = read(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn value(&mut self) -> Result<Value, SnbtError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('{') => self.nested(Self::compound).map(Value::Compound),
+            Some('[') => self.nested(Self::list),
+            Some(quote @ ('"' | '\'')) => {
+                self.next();
+                self.quoted(quote).map(Value::String)
+            }
+            _ => match self.unquoted() {
+                "" => Err(SnbtError::ExpectedValue),
+                token => Ok(parse_unquoted(token)),
+            },
+        }
+    }
+
+    fn compound(&mut self) -> Result<Compound, SnbtError> {
+        self.next();
+        let mut compound = Compound::new();
+
+        if self.eat('}') {
+            return Ok(compound);
+        }
+
+        loop {
+            let key = self.key()?;
+            if !self.eat(':') {
+                return Err(SnbtError::ExpectedValue);
+            }
+            let value = self.value()?;
+            compound.insert(key, value);
+
+            if self.eat(',') {
+                continue;
+            }
+            if self.eat('}') {
+                return Ok(compound);
+            }
+            return Err(SnbtError::ExpectedKey);
+        }
+    }
+
+    /// Reads a list, or a typed array like `[I;1,2,3]`
+    fn list(&mut self) -> Result<Value, SnbtError> {
+        self.next();
+
+        let rest = &self.input[self.pos..];
+        let array = ['B', 'I', 'L']
+            .into_iter()
+            .find(|prefix| rest.starts_with(*prefix) && rest[1..].trim_start().starts_with(';'));
+        if array.is_some() {
+            self.next();
+            self.eat(';');
+        }
+
+        let mut values = vec![];
+        if !self.eat(']') {
+            loop {
+                values.push(self.value()?);
+
+                if self.eat(',') {
+                    continue;
+                }
+                if self.eat(']') {
+                    break;
+                }
+                return Err(SnbtError::ExpectedValue);
+            }
+        }
+
+        let list = into_list(values)?;
+        Ok(match (array, list) {
+            (Some('B'), List::Byte(bytes)) => Value::ByteArray(bytes),
+            (Some('B'), List::End) => Value::ByteArray(vec![]),
+            (Some('I'), List::Int(ints)) => Value::IntArray(ints),
+            (Some('I'), List::End) => Value::IntArray(vec![]),
+            (Some('L'), List::Long(longs)) => Value::LongArray(longs),
+            (Some('L'), List::End) => Value::LongArray(vec![]),
+            (Some(_), _) => return Err(SnbtError::MixedList("TAG_Int", "TAG_Int_Array")),
+            (None, list) => Value::List(list),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value(input: &str) -> Value {
+        let compound = parse_compound(&format!("{{v:{input}}}")).unwrap();
+        compound.get("v").cloned().unwrap()
+    }
+
+    #[test]
+    fn number_suffixes() {
+        assert_eq!(value("1b"), Value::Byte(1));
+        assert_eq!(value("-2S"), Value::Short(-2));
+        assert_eq!(value("3"), Value::Int(3));
+        assert_eq!(value("4l"), Value::Long(4));
+        assert_eq!(value("1.5f"), Value::Float(1.5));
+        assert_eq!(value("2.5d"), Value::Double(2.5));
+        assert_eq!(value("2.5"), Value::Double(2.5));
+        assert_eq!(value("true"), Value::Byte(1));
+        assert_eq!(value("12x"), Value::String("12x".to_owned()));
+    }
+
+    #[test]
+    fn typed_arrays() {
+        assert_eq!(value("[B;1b,2b]"), Value::ByteArray(vec![1, 2]));
+        assert_eq!(value("[I; 1, 2, 3]"), Value::IntArray(vec![1, 2, 3]));
+        assert_eq!(value("[L;5L]"), Value::LongArray(vec![5]));
+        assert_eq!(value("[I;]"), Value::IntArray(vec![]));
+        assert!(parse_compound("{v:[I;1b]}").is_err());
+    }
+
+    #[test]
+    fn lists_must_share_a_type() {
+        assert_eq!(value("[1,2]"), Value::List(List::Int(vec![1, 2])));
+        assert_eq!(
+            parse_compound("{v:[1,2b]}"),
+            Err(SnbtError::MixedList("TAG_Byte", "TAG_Int"))
+        );
+    }
+
+    #[test]
+    fn quoted_strings_and_keys() {
+        let compound = parse_compound(r#"{"a b":'it''s',c:"say \"hi\""}"#);
+        assert!(compound.is_err());
+
+        let compound = parse_compound(r#"{"a b":'it\'s',c:"say \"hi\""}"#).unwrap();
+        assert_eq!(compound.get("a b"), Some(&Value::String("it's".to_owned())));
+        assert_eq!(compound.get("c"), Some(&Value::String("say \"hi\"".to_owned())));
+    }
+
+    #[test]
+    fn nesting_is_limited() {
+        // `levels` compounds, each inside the last
+        let nested = |levels: u32| {
+            let levels = levels as usize - 1;
+            format!("{}{{}}{}", "{a:".repeat(levels), "}".repeat(levels))
+        };
+
+        assert!(parse_compound(&nested(MAX_DEPTH)).is_ok());
+        assert_eq!(parse_compound(&nested(MAX_DEPTH + 1)), Err(SnbtError::TooDeep));
+    }
+
+    #[test]
+    fn written_snbt_reads_back() {
+        let input = concat!(
+            r#"{Items:[{Slot:0b,id:"minecraft:stone",Count:64b}],"#,
+            r#""odd key":1.5f,Ids:[I;1,2],Big:7L,Name:'say "hi"'}"#,
+        );
+        let compound = parse_compound(input).unwrap();
+
+        assert_eq!(parse_compound(&to_snbt(&compound)), Ok(compound));
+    }
+}
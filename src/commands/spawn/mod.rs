@@ -0,0 +1,49 @@
+use valence::{
+    command::handler::CommandResultEvent,
+    command_macros::Command,
+    prelude::*,
+    text::{Color, IntoText},
+};
+
+use crate::{
+    commands::{
+        feedback::CommandFeedback,
+        teleport::apply::{TeleportCause, TeleportEvent},
+    },
+    world::spawn::WorldSpawn,
+};
+
+#[derive(Command, Debug, Clone)]
+#[paths("spawn")]
+#[scopes("command.spawn")]
+pub struct Command {}
+
+/// Sends the executor to the highest safe block at their layer's spawn.
+pub fn handle(
+    mut events: EventReader<CommandResultEvent<Command>>,
+    players: Query<&EntityLayerId, With<Client>>,
+    spawns: Query<&WorldSpawn>,
+    mut teleports: EventWriter<TeleportEvent>,
+    mut feedback: EventWriter<CommandFeedback>,
+) {
+    for event in events.read() {
+        let Ok(layer) = players.get(event.executor) else {
+            feedback.send(CommandFeedback::new(
+                event.executor,
+                Text::translate("permissions.requires.player", []).color(Color::RED),
+            ));
+            continue;
+        };
+
+        let Ok(spawn) = spawns.get(layer.0) else {
+            continue;
+        };
+
+        teleports.send(TeleportEvent {
+            look: Some(spawn.look),
+            safe: true,
+            cause: TeleportCause::Spawn,
+            ..TeleportEvent::new(event.executor, spawn.position)
+        });
+    }
+}
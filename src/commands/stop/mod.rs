@@ -0,0 +1,28 @@
+use valence::{
+    command::{handler::CommandResultEvent, parsers::GreedyString},
+    command_macros::Command,
+    prelude::*,
+};
+
+use crate::server::shutdown::Shutdown;
+
+#[derive(Command, Debug, Clone)]
+#[paths("stop {reason?}")]
+#[scopes("command.stop")]
+pub struct Command {
+    reason: Option<GreedyString>,
+}
+
+pub fn handle(
+    mut events: EventReader<CommandResultEvent<Command>>,
+    mut shutdown: ResMut<Shutdown>,
+) {
+    for event in events.read() {
+        let reason = match &event.result.reason {
+            Some(reason) => reason.0.clone(),
+            None => "Server closed".to_owned(),
+        };
+
+        shutdown.request(reason);
+    }
+}
@@ -0,0 +1,427 @@
+use valence::{
+    command::{
+        handler::CommandResultEvent,
+        parsers::{CommandArg, CommandArgParseError, GreedyString, ParseInput},
+    },
+    command_macros::Command,
+    entity::{entity::CustomName, HeadYaw, Look},
+    nbt::{Compound, Value},
+    prelude::*,
+    ItemKind, ItemStack,
+    protocol::packets::play::command_tree_s2c::Parser,
+    text::{Color, IntoText},
+};
+
+use crate::{
+    commands::{
+        feedback::CommandFeedback,
+        selector::{entity_kind_from_name, entity_kind_name, SelectorError},
+        snbt,
+        teleport::coordinates::{Coordinate, Coordinates},
+    },
+    containers, hud,
+    interacting::items::{drop_item, PICKUP_DELAY},
+    mobs::{hostile::HostileAi, passive::PassiveAi, Mob, MobKind},
+    world::{self, spawn::WorldSpawn},
+};
+
+/// An entity type id such as `cow` or `minecraft:armor_stand`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EntityType(pub EntityKind);
+
+impl CommandArg for EntityType {
+    fn parse_arg(input: &mut ParseInput) -> Result<Self, CommandArgParseError> {
+        input.skip_whitespace();
+        let word = input.pop_word();
+        let name = word.strip_prefix("minecraft:").unwrap_or(word);
+
+        entity_kind_from_name(name)
+            .map(EntityType)
+            .ok_or_else(|| CommandArgParseError::InvalidArgument {
+                expected: "entity type".to_owned(),
+                got: word.to_owned(),
+            })
+    }
+
+    fn display() -> Parser {
+        Parser::ResourceLocation
+    }
+}
+
+#[derive(Command, Debug, Clone)]
+#[paths("summon {kind} {pos?} {nbt?}")]
+#[scopes("command.summon")]
+pub struct Command {
+    kind: EntityType,
+    pos: Option<Coordinates>,
+    nbt: Option<GreedyString>,
+}
+
+/// The few entity tags `/summon` understands. Anything else in the compound
+/// is ignored.
+#[derive(Debug, Default)]
+struct SummonTags {
+    custom_name: Option<Text>,
+    no_ai: bool,
+    invulnerable: bool,
+    /// The stack of an `item` entity
+    item: Option<ItemStack>,
+}
+
+impl SummonTags {
+    fn from_compound(nbt: &Compound) -> Self {
+        let is_set = |key| matches!(nbt.get(key), Some(Value::Byte(value)) if *value != 0);
+
+        Self {
+            custom_name: match nbt.get("CustomName") {
+                Some(Value::String(name)) => Some(hud::parse_text(name)),
+                _ => None,
+            },
+            no_ai: is_set("NoAI"),
+            invulnerable: is_set("Invulnerable"),
+            item: match nbt.get("Item") {
+                Some(Value::Compound(item)) => containers::stack_from_nbt(item),
+                _ => None,
+            },
+        }
+    }
+}
+
+/// Spawns an entity without AI from its bundle, by entity id. Living
+/// entities also get their head turned to match.
+macro_rules! spawn_bundle {
+    (
+        $commands:expr, $name:expr, $layer:expr, $position:expr, $look:expr,
+        living: [$($living:literal => $living_module:ident::$living_bundle:ident),* $(,)?],
+        other: [$($other:literal => $other_module:ident::$other_bundle:ident),* $(,)?] $(,)?
+    ) => {
+        match $name {
+            $($living => Some(
+                $commands
+                    .spawn(valence::entity::$living_module::$living_bundle {
+                        layer: $layer,
+                        position: $position,
+                        look: $look,
+                        head_yaw: HeadYaw($look.yaw),
+                        ..Default::default()
+                    })
+                    .id(),
+            ),)*
+            $($other => Some(
+                $commands
+                    .spawn(valence::entity::$other_module::$other_bundle {
+                        layer: $layer,
+                        position: $position,
+                        look: $look,
+                        ..Default::default()
+                    })
+                    .id(),
+            ),)*
+            _ => None,
+        }
+    };
+}
+
+/// Spawns an entity the server has no AI for. Returns `None` for kinds that
+/// can't be summoned, like players, or that need more data than an id to
+/// make sense, like falling blocks.
+fn spawn_entity(
+    commands: &mut Commands,
+    name: &str,
+    layer: Entity,
+    pos: DVec3,
+    look: Look,
+) -> Option<Entity> {
+    let layer = EntityLayerId(layer);
+    let position = Position::new(pos);
+
+    spawn_bundle!(
+        commands, name, layer, position, look,
+        living: [
+            "allay" => allay::AllayEntityBundle,
+            "armor_stand" => armor_stand::ArmorStandEntityBundle,
+            "axolotl" => axolotl::AxolotlEntityBundle,
+            "bat" => bat::BatEntityBundle,
+            "bee" => bee::BeeEntityBundle,
+            "blaze" => blaze::BlazeEntityBundle,
+            "camel" => camel::CamelEntityBundle,
+            "cat" => cat::CatEntityBundle,
+            "cave_spider" => cave_spider::CaveSpiderEntityBundle,
+            "cod" => cod::CodEntityBundle,
+            "dolphin" => dolphin::DolphinEntityBundle,
+            "donkey" => donkey::DonkeyEntityBundle,
+            "drowned" => drowned::DrownedEntityBundle,
+            "elder_guardian" => elder_guardian::ElderGuardianEntityBundle,
+            "ender_dragon" => ender_dragon::EnderDragonEntityBundle,
+            "enderman" => enderman::EndermanEntityBundle,
+            "endermite" => endermite::EndermiteEntityBundle,
+            "evoker" => evoker::EvokerEntityBundle,
+            "fox" => fox::FoxEntityBundle,
+            "frog" => frog::FrogEntityBundle,
+            "ghast" => ghast::GhastEntityBundle,
+            "giant" => giant::GiantEntityBundle,
+            "glow_squid" => glow_squid::GlowSquidEntityBundle,
+            "goat" => goat::GoatEntityBundle,
+            "guardian" => guardian::GuardianEntityBundle,
+            "hoglin" => hoglin::HoglinEntityBundle,
+            "horse" => horse::HorseEntityBundle,
+            "husk" => husk::HuskEntityBundle,
+            "illusioner" => illusioner::IllusionerEntityBundle,
+            "iron_golem" => iron_golem::IronGolemEntityBundle,
+            "llama" => llama::LlamaEntityBundle,
+            "magma_cube" => magma_cube::MagmaCubeEntityBundle,
+            "mooshroom" => mooshroom::MooshroomEntityBundle,
+            "mule" => mule::MuleEntityBundle,
+            "ocelot" => ocelot::OcelotEntityBundle,
+            "panda" => panda::PandaEntityBundle,
+            "parrot" => parrot::ParrotEntityBundle,
+            "phantom" => phantom::PhantomEntityBundle,
+            "piglin" => piglin::PiglinEntityBundle,
+            "piglin_brute" => piglin_brute::PiglinBruteEntityBundle,
+            "pillager" => pillager::PillagerEntityBundle,
+            "polar_bear" => polar_bear::PolarBearEntityBundle,
+            "pufferfish" => pufferfish::PufferfishEntityBundle,
+            "rabbit" => rabbit::RabbitEntityBundle,
+            "ravager" => ravager::RavagerEntityBundle,
+            "salmon" => salmon::SalmonEntityBundle,
+            "shulker" => shulker::ShulkerEntityBundle,
+            "silverfish" => silverfish::SilverfishEntityBundle,
+            "skeleton_horse" => skeleton_horse::SkeletonHorseEntityBundle,
+            "slime" => slime::SlimeEntityBundle,
+            "sniffer" => sniffer::SnifferEntityBundle,
+            "snow_golem" => snow_golem::SnowGolemEntityBundle,
+            "spider" => spider::SpiderEntityBundle,
+            "squid" => squid::SquidEntityBundle,
+            "stray" => stray::StrayEntityBundle,
+            "strider" => strider::StriderEntityBundle,
+            "tadpole" => tadpole::TadpoleEntityBundle,
+            "trader_llama" => trader_llama::TraderLlamaEntityBundle,
+            "tropical_fish" => tropical_fish::TropicalFishEntityBundle,
+            "turtle" => turtle::TurtleEntityBundle,
+            "vex" => vex::VexEntityBundle,
+            "villager" => villager::VillagerEntityBundle,
+            "vindicator" => vindicator::VindicatorEntityBundle,
+            "wandering_trader" => wandering_trader::WanderingTraderEntityBundle,
+            "warden" => warden::WardenEntityBundle,
+            "witch" => witch::WitchEntityBundle,
+            "wither" => wither::WitherEntityBundle,
+            "wither_skeleton" => wither_skeleton::WitherSkeletonEntityBundle,
+            "wolf" => wolf::WolfEntityBundle,
+            "zoglin" => zoglin::ZoglinEntityBundle,
+            "zombie_horse" => zombie_horse::ZombieHorseEntityBundle,
+            "zombie_villager" => zombie_villager::ZombieVillagerEntityBundle,
+            "zombified_piglin" => zombified_piglin::ZombifiedPiglinEntityBundle,
+        ],
+        other: [
+            "arrow" => arrow::ArrowEntityBundle,
+            "area_effect_cloud" => area_effect_cloud::AreaEffectCloudEntityBundle,
+            "block_display" => block_display::BlockDisplayEntityBundle,
+            "boat" => boat::BoatEntityBundle,
+            "chest_boat" => chest_boat::ChestBoatEntityBundle,
+            "chest_minecart" => chest_minecart::ChestMinecartEntityBundle,
+            "command_block_minecart" => command_block_minecart::CommandBlockMinecartEntityBundle,
+            "dragon_fireball" => dragon_fireball::DragonFireballEntityBundle,
+            "egg" => egg::EggEntityBundle,
+            "end_crystal" => end_crystal::EndCrystalEntityBundle,
+            "ender_pearl" => ender_pearl::EnderPearlEntityBundle,
+            "evoker_fangs" => evoker_fangs::EvokerFangsEntityBundle,
+            "experience_bottle" => experience_bottle::ExperienceBottleEntityBundle,
+            "experience_orb" => experience_orb::ExperienceOrbEntityBundle,
+            "eye_of_ender" => eye_of_ender::EyeOfEnderEntityBundle,
+            "fireball" => fireball::FireballEntityBundle,
+            "firework_rocket" => firework_rocket::FireworkRocketEntityBundle,
+            "furnace_minecart" => furnace_minecart::FurnaceMinecartEntityBundle,
+            "glow_item_frame" => glow_item_frame::GlowItemFrameEntityBundle,
+            "hopper_minecart" => hopper_minecart::HopperMinecartEntityBundle,
+            "interaction" => interaction::InteractionEntityBundle,
+            "item_display" => item_display::ItemDisplayEntityBundle,
+            "item_frame" => item_frame::ItemFrameEntityBundle,
+            "leash_knot" => leash_knot::LeashKnotEntityBundle,
+            "lightning_bolt" => lightning::LightningEntityBundle,
+            "llama_spit" => llama_spit::LlamaSpitEntityBundle,
+            "marker" => marker::MarkerEntityBundle,
+            "minecart" => minecart::MinecartEntityBundle,
+            "painting" => painting::PaintingEntityBundle,
+            "potion" => potion::PotionEntityBundle,
+            "shulker_bullet" => shulker_bullet::ShulkerBulletEntityBundle,
+            "small_fireball" => small_fireball::SmallFireballEntityBundle,
+            "snowball" => snowball::SnowballEntityBundle,
+            "spawner_minecart" => spawner_minecart::SpawnerMinecartEntityBundle,
+            "spectral_arrow" => spectral_arrow::SpectralArrowEntityBundle,
+            "text_display" => text_display::TextDisplayEntityBundle,
+            "tnt" => tnt::TntEntityBundle,
+            "tnt_minecart" => tnt_minecart::TntMinecartEntityBundle,
+            "trident" => trident::TridentEntityBundle,
+            "wither_skull" => wither_skull::WitherSkullEntityBundle,
+        ],
+    )
+}
+
+/// Summons an entity at the executor, or at the given position.
+///
+/// Mobs the server has AI for get it like naturally spawned ones, unless
+/// `NoAI` is set. Executors outside the world need absolute coordinates and
+/// summon into the default world.
+pub fn handle(
+    mut commands: Commands,
+    mut events: EventReader<CommandResultEvent<Command>>,
+    entities: Query<(&Position, &Look, &EntityLayerId)>,
+    layers: Query<(Entity, &ChunkLayer), With<WorldSpawn>>,
+    mut feedback: EventWriter<CommandFeedback>,
+) {
+    for event in events.read() {
+        let executor = event.executor;
+        let executor_location = entities.get(executor).ok();
+
+        let absolute = matches!(
+            event.result.pos,
+            Some(Coordinates::World(coordinates))
+                if coordinates.iter().all(|c| matches!(c, Coordinate::Absolute(_)))
+        );
+
+        let (pos, layer) = match (executor_location, event.result.pos) {
+            (Some((pos, _, layer)), None) => (pos.0, layer.0),
+            (Some((pos, look, layer)), Some(target)) => (target.resolve(pos.0, *look), layer.0),
+            (None, Some(target)) if absolute => {
+                let Some((layer, _)) = layers.iter().next() else {
+                    continue;
+                };
+                (target.resolve(DVec3::ZERO, Look::default()), layer)
+            }
+            (None, _) => {
+                feedback.send(CommandFeedback::new(
+                    executor,
+                    SelectorError::RequiresEntity.to_text(),
+                ));
+                continue;
+            }
+        };
+
+        let tags = match &event.result.nbt {
+            Some(nbt) => match snbt::parse_compound(&nbt.0) {
+                Ok(nbt) => SummonTags::from_compound(&nbt),
+                Err(e) => {
+                    feedback.send(CommandFeedback::new(executor, e.to_text()));
+                    continue;
+                }
+            },
+            None => SummonTags::default(),
+        };
+
+        let loaded = layers
+            .get(layer)
+            .is_ok_and(|(_, layer)| layer.chunk(world::chunk_pos_at(pos)).is_some());
+        if !loaded {
+            feedback.send(CommandFeedback::new(
+                executor,
+                Text::translate("commands.summon.invalidPosition", []).color(Color::RED),
+            ));
+            continue;
+        }
+
+        let kind = event.result.kind.0;
+        let name = entity_kind_name(kind).unwrap_or_default();
+        let look = executor_location
+            .map_or(Look::default(), |(_, look, _)| Look::new(look.yaw, 0.0));
+
+        let entity = match MobKind::from_name(name) {
+            // A stone unless the `Item` tag says otherwise, since an item
+            // entity can't be empty
+            None if name == "item" => {
+                let stack = tags
+                    .item
+                    .clone()
+                    .unwrap_or_else(|| ItemStack::new(ItemKind::Stone, 1, None));
+                Some(drop_item(&mut commands, layer, pos, stack, PICKUP_DELAY))
+            }
+            Some(mob) => {
+                let entity = mob.spawn(&mut commands, layer, pos, look.yaw);
+
+                if tags.no_ai {
+                    commands.entity(entity).remove::<(PassiveAi, HostileAi)>();
+                }
+                if tags.invulnerable {
+                    commands.entity(entity).insert(Mob {
+                        invulnerable: true,
+                        ..Mob::new(mob)
+                    });
+                }
+                Some(entity)
+            }
+            None => spawn_entity(&mut commands, name, layer, pos, look),
+        };
+
+        let Some(entity) = entity else {
+            feedback.send(CommandFeedback::new(
+                executor,
+                Text::translate("commands.summon.failed", []).color(Color::RED),
+            ));
+            continue;
+        };
+
+        let display_name = match tags.custom_name {
+            Some(custom_name) => {
+                commands
+                    .entity(entity)
+                    .insert(CustomName(Some(custom_name.clone())));
+                custom_name
+            }
+            None => name.to_owned().into_text(),
+        };
+
+        feedback.send(CommandFeedback::new(
+            executor,
+            Text::translate("commands.summon.success", [display_name]),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tags(input: &str) -> SummonTags {
+        SummonTags::from_compound(&snbt::parse_compound(input).unwrap())
+    }
+
+    #[test]
+    fn no_tags_leave_the_defaults() {
+        let parsed = tags("{}");
+        assert_eq!(parsed.custom_name, None);
+        assert!(!parsed.no_ai);
+        assert!(!parsed.invulnerable);
+        assert_eq!(parsed.item, None);
+    }
+
+    #[test]
+    fn flags_are_set_by_non_zero_bytes() {
+        let parsed = tags("{NoAI:1b,Invulnerable:true}");
+        assert!(parsed.no_ai);
+        assert!(parsed.invulnerable);
+
+        assert!(!tags("{NoAI:0b}").no_ai);
+        // Only bytes count, as in vanilla
+        assert!(!tags("{NoAI:1}").no_ai);
+    }
+
+    #[test]
+    fn custom_names_can_be_plain_or_json() {
+        let plain = tags("{CustomName:Bob}");
+        assert_eq!(plain.custom_name, Some("Bob".into_text()));
+
+        let json = tags(r#"{CustomName:'{"text":"Bob","color":"red"}'}"#);
+        assert_eq!(json.custom_name, Some("Bob".color(Color::RED)));
+    }
+
+    #[test]
+    fn item_entities_read_their_stack() {
+        let parsed = tags(r#"{Item:{id:"minecraft:diamond",Count:3b}}"#);
+        let diamonds = ItemStack::new(ItemKind::Diamond, 3, None);
+        assert_eq!(parsed.item, Some(diamonds));
+
+        let unknown = tags(r#"{Item:{id:"not_an_item",Count:1b}}"#);
+        assert_eq!(unknown.item, None);
+        let no_count = tags(r#"{Item:{id:"minecraft:diamond"}}"#);
+        assert_eq!(no_count.item, None);
+    }
+}
@@ -0,0 +1,215 @@
+use valence::{
+    command::{
+        handler::CommandResultEvent,
+        parsers::{EntitySelector, GreedyString},
+    },
+    command_macros::Command,
+    prelude::*,
+    text::IntoText,
+};
+
+use crate::{
+    commands::{
+        feedback::CommandFeedback,
+        scoreboard::score_holders,
+        selector::{SelectorError, SelectorResolver},
+    },
+    scoreboard::{teams::TeamOption, Scoreboard, ScoreboardError},
+};
+
+#[derive(Command, Debug, Clone)]
+#[paths("team")]
+#[scopes("command.team")]
+pub enum Command {
+    #[paths("add {name} {display_name?}")]
+    Add {
+        name: String,
+        display_name: Option<GreedyString>,
+    },
+    #[paths("remove {name}")]
+    Remove { name: String },
+    #[paths("empty {name}")]
+    Empty { name: String },
+    #[paths("join {name} {members?}")]
+    Join {
+        name: String,
+        members: Option<EntitySelector>,
+    },
+    #[paths("leave {members}")]
+    Leave { members: EntitySelector },
+    #[paths("list {name?}")]
+    List { name: Option<String> },
+    #[paths("modify {name} displayName {value}")]
+    DisplayName { name: String, value: GreedyString },
+    #[paths("modify {name} color {value}")]
+    Color { name: String, value: String },
+    #[paths("modify {name} prefix {value}")]
+    Prefix { name: String, value: GreedyString },
+    #[paths("modify {name} suffix {value}")]
+    Suffix { name: String, value: GreedyString },
+}
+
+/// Why a team command failed
+enum Error {
+    Selector(SelectorError),
+    Scoreboard(ScoreboardError),
+}
+
+impl From<SelectorError> for Error {
+    fn from(e: SelectorError) -> Self {
+        Error::Selector(e)
+    }
+}
+
+impl From<ScoreboardError> for Error {
+    fn from(e: ScoreboardError) -> Self {
+        Error::Scoreboard(e)
+    }
+}
+
+pub fn handle(
+    mut events: EventReader<CommandResultEvent<Command>>,
+    resolver: SelectorResolver,
+    uuids: Query<&UniqueId>,
+    mut scoreboard: ResMut<Scoreboard>,
+    mut feedback: EventWriter<CommandFeedback>,
+) {
+    for event in events.read() {
+        let message = run(&event.result, event.executor, &resolver, &uuids, &mut scoreboard);
+
+        let message = match message {
+            Ok(message) => message,
+            Err(Error::Selector(e)) => e.to_text(),
+            Err(Error::Scoreboard(e)) => e.to_text(),
+        };
+
+        feedback.send(CommandFeedback::new(event.executor, message));
+    }
+}
+
+fn run(
+    command: &Command,
+    executor: Entity,
+    resolver: &SelectorResolver,
+    uuids: &Query<&UniqueId>,
+    scoreboard: &mut Scoreboard,
+) -> Result<Text, Error> {
+    let members = |selector: &EntitySelector, scoreboard: &Scoreboard| {
+        score_holders(resolver, uuids, scoreboard, executor, selector)
+    };
+
+    Ok(match command {
+        Command::Add { name, display_name } => {
+            let display_name = display_name.as_ref().map(|name| name.0.clone());
+            scoreboard.add_team(name, display_name)?;
+
+            Text::translate("commands.team.add.success", [name.clone().into_text()])
+        }
+        Command::Remove { name } => {
+            scoreboard.remove_team(name)?;
+
+            Text::translate("commands.team.remove.success", [name.clone().into_text()])
+        }
+        Command::Empty { name } => match scoreboard.empty_team(name)? {
+            0 => Text::translate("commands.team.empty.unchanged", []),
+            count => Text::translate(
+                "commands.team.empty.success",
+                [count.to_string().into_text(), name.clone().into_text()],
+            ),
+        },
+        Command::Join { name, members: selector } => {
+            let joining = match selector {
+                Some(selector) => members(selector, scoreboard)?,
+                None if resolver.is_player(executor) => vec![resolver.display_name(executor)],
+                None => return Err(SelectorError::RequiresEntity.into()),
+            };
+            scoreboard.join_team(name, &joining)?;
+
+            match joining.as_slice() {
+                [single] => Text::translate(
+                    "commands.team.join.success.single",
+                    [single.clone().into_text(), name.clone().into_text()],
+                ),
+                _ => Text::translate(
+                    "commands.team.join.success.multiple",
+                    [joining.len().to_string().into_text(), name.clone().into_text()],
+                ),
+            }
+        }
+        Command::Leave { members: selector } => {
+            let leaving = members(selector, scoreboard)?;
+            scoreboard.leave_team(&leaving);
+
+            match leaving.as_slice() {
+                [single] => Text::translate(
+                    "commands.team.leave.success.single",
+                    [single.clone().into_text()],
+                ),
+                _ => Text::translate(
+                    "commands.team.leave.success.multiple",
+                    [leaving.len().to_string().into_text()],
+                ),
+            }
+        }
+        Command::List { name: None } => {
+            let names: Vec<_> = scoreboard.teams().map(|(name, _)| name.as_str()).collect();
+
+            if names.is_empty() {
+                Text::translate("commands.team.list.teams.empty", [])
+            } else {
+                Text::translate(
+                    "commands.team.list.teams.success",
+                    [
+                        names.len().to_string().into_text(),
+                        names.join(", ").into_text(),
+                    ],
+                )
+            }
+        }
+        Command::List { name: Some(name) } => {
+            let team = scoreboard
+                .team(name)
+                .ok_or_else(|| ScoreboardError::UnknownTeam(name.clone()))?;
+            let members: Vec<_> = team.members.iter().map(String::as_str).collect();
+
+            if members.is_empty() {
+                Text::translate(
+                    "commands.team.list.members.empty",
+                    [name.clone().into_text()],
+                )
+            } else {
+                Text::translate(
+                    "commands.team.list.members.success",
+                    [
+                        name.clone().into_text(),
+                        members.len().to_string().into_text(),
+                        members.join(", ").into_text(),
+                    ],
+                )
+            }
+        }
+        Command::DisplayName { name, value } => {
+            scoreboard.modify_team(name, TeamOption::DisplayName(value.0.clone()))?;
+
+            Text::translate("commands.team.option.name.success", [name.clone().into_text()])
+        }
+        Command::Color { name, value } => {
+            scoreboard.modify_team(name, TeamOption::Color(value.clone()))?;
+
+            Text::translate(
+                "commands.team.option.color.success",
+                [name.clone().into_text(), value.clone().into_text()],
+            )
+        }
+        Command::Prefix { name, value } => {
+            scoreboard.modify_team(name, TeamOption::Prefix(value.0.clone()))?;
+
+            Text::translate("commands.team.option.prefix.success", [value.0.clone().into_text()])
+        }
+        Command::Suffix { name, value } => {
+            scoreboard.modify_team(name, TeamOption::Suffix(value.0.clone()))?;
+
+            Text::translate("commands.team.option.suffix.success", [value.0.clone().into_text()])
+        }
+    })
+}
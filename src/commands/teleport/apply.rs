@@ -0,0 +1,193 @@
+use valence::{
+    anvil::AnvilLevel,
+    entity::{HeadYaw, Look},
+    prelude::*,
+    text::{Color, IntoText},
+};
+
+use crate::{
+    commands::feedback::CommandFeedback,
+    world::{self, chunks::GameState},
+};
+
+/// How long a teleport waits for its destination chunk before giving up
+const LOAD_TIMEOUT_TICKS: u32 = 20 * 15;
+/// How often an Anvil chunk load is re-requested while waiting
+const ANVIL_RETRY_TICKS: u32 = 20;
+
+/// Moves an entity, optionally rotating it or moving it to another layer.
+///
+/// Every teleport goes through this event so other systems can react to
+/// them, no matter which command caused it. The entity stays where it is
+/// until the destination chunk is loaded.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct TeleportEvent {
+    pub entity: Entity,
+    pub position: DVec3,
+    /// Keeps the current look when None
+    pub look: Option<Look>,
+    /// Stays on the current layer when None
+    pub layer: Option<Entity>,
+    /// Replaces the height with the highest safe block in the column once
+    /// the destination is loaded
+    pub safe: bool,
+    pub cause: TeleportCause,
+}
+
+impl TeleportEvent {
+    pub fn new(entity: Entity, position: DVec3) -> Self {
+        Self {
+            entity,
+            position,
+            look: None,
+            layer: None,
+            safe: false,
+            cause: TeleportCause::Command,
+        }
+    }
+}
+
+/// What asked for a teleport
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TeleportCause {
+    /// Placing a player who just joined on the surface
+    Join,
+    Command,
+    Spawn,
+    Request,
+    Back,
+    /// Returning to spawn after death
+    Respawn,
+    Home,
+    Warp,
+}
+
+/// Sent after a teleport has been applied, with where the entity came from.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct TeleportedEvent {
+    pub entity: Entity,
+    pub from: DVec3,
+    pub from_look: Look,
+    pub from_layer: Entity,
+    pub cause: TeleportCause,
+}
+
+/// Teleports waiting for their destination chunk, with how many ticks
+/// they've waited.
+#[derive(Resource, Default)]
+pub struct PendingTeleports(Vec<(TeleportEvent, u32)>);
+
+impl PendingTeleports {
+    /// Whether a teleport is waiting for `chunk` to be loaded
+    pub fn is_waiting_for(&self, chunk: ChunkPos) -> bool {
+        self.0
+            .iter()
+            .any(|(event, _)| world::chunk_pos_at(event.position) == chunk)
+    }
+}
+
+pub fn apply_teleports(
+    mut events: EventReader<TeleportEvent>,
+    mut pending: ResMut<PendingTeleports>,
+    mut layers: Query<(&ChunkLayer, Option<&mut AnvilLevel>)>,
+    mut game_state: Option<ResMut<GameState>>,
+    mut entities: Query<(
+        &mut Position,
+        &mut Look,
+        Option<&mut HeadYaw>,
+        &mut EntityLayerId,
+        Option<&mut VisibleChunkLayer>,
+        Option<&mut VisibleEntityLayers>,
+    )>,
+    mut feedback: EventWriter<CommandFeedback>,
+    mut teleported: EventWriter<TeleportedEvent>,
+) {
+    let queued: Vec<_> = pending
+        .0
+        .drain(..)
+        .chain(events.read().map(|event| (*event, 0)))
+        .collect();
+
+    for (mut event, ticks) in queued {
+        let Ok((mut pos, mut look, head_yaw, mut layer_id, visible_chunk_layer, visible_entity_layers)) =
+            entities.get_mut(event.entity)
+        else {
+            continue;
+        };
+
+        let layer = event.layer.unwrap_or(layer_id.0);
+        let Ok((chunk_layer, anvil)) = layers.get_mut(layer) else {
+            continue;
+        };
+
+        let chunk_pos = world::chunk_pos_at(event.position);
+
+        if chunk_layer.chunk(chunk_pos).is_none() {
+            if ticks >= LOAD_TIMEOUT_TICKS {
+                feedback.send(CommandFeedback::new(
+                    event.entity,
+                    "Teleport cancelled, the destination could not be loaded".color(Color::RED),
+                ));
+                continue;
+            }
+
+            // Generated chunks are queued with the highest priority, Anvil
+            // chunks are read from disk directly.
+            if let Some(mut anvil) = anvil {
+                if ticks % ANVIL_RETRY_TICKS == 0 {
+                    anvil.force_chunk_load(chunk_pos);
+                }
+            } else if let Some(state) = game_state.as_mut() {
+                state.pending.entry(chunk_pos).or_insert(Some(0));
+            }
+
+            pending.0.push((event, ticks + 1));
+            continue;
+        }
+
+        if event.safe {
+            match world::safe_surface(chunk_layer, event.position) {
+                Some(surface) => event.position = surface,
+                None => {
+                    feedback.send(CommandFeedback::new(
+                        event.entity,
+                        "There is no safe place to stand there".color(Color::RED),
+                    ));
+                    continue;
+                }
+            }
+        }
+
+        teleported.send(TeleportedEvent {
+            entity: event.entity,
+            from: pos.0,
+            from_look: *look,
+            from_layer: layer_id.0,
+            cause: event.cause,
+        });
+
+        if layer_id.0 != layer {
+            let old_layer = layer_id.0;
+            layer_id.0 = layer;
+
+            if let Some(mut visible_chunk_layer) = visible_chunk_layer {
+                visible_chunk_layer.0 = layer;
+            }
+
+            if let Some(mut visible_entity_layers) = visible_entity_layers {
+                visible_entity_layers.0.remove(&old_layer);
+                visible_entity_layers.0.insert(layer);
+            }
+        }
+
+        pos.set(event.position);
+
+        if let Some(new_look) = event.look {
+            *look = new_look;
+
+            if let Some(mut head_yaw) = head_yaw {
+                head_yaw.0 = new_look.yaw;
+            }
+        }
+    }
+}
@@ -0,0 +1,187 @@
+use valence::{
+    command::parsers::{CommandArg, CommandArgParseError, ParseInput},
+    entity::Look,
+    math::DVec3,
+    protocol::packets::play::command_tree_s2c::Parser,
+};
+
+/// A single world coordinate, either absolute or relative (`~`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Coordinate {
+    Absolute(f64),
+    Relative(f64),
+}
+
+impl Coordinate {
+    pub fn get(self, original: f64) -> f64 {
+        match self {
+            Coordinate::Absolute(value) => value,
+            Coordinate::Relative(offset) => original + offset,
+        }
+    }
+
+    fn parse(word: &str) -> Result<Self, CommandArgParseError> {
+        let invalid = || CommandArgParseError::InvalidArgument {
+            expected: "coordinate".to_owned(),
+            got: word.to_owned(),
+        };
+
+        match word.strip_prefix('~') {
+            Some("") => Ok(Coordinate::Relative(0.0)),
+            Some(offset) => offset
+                .parse()
+                .map(Coordinate::Relative)
+                .map_err(|_| invalid()),
+            None => word
+                .parse()
+                .map(Coordinate::Absolute)
+                .map_err(|_| invalid()),
+        }
+    }
+}
+
+/// A position argument in any of the vanilla forms: absolute (`1 64 -3`),
+/// relative (`~ ~1 ~`) or local (`^ ^ ^5`, left/up/forwards from the facing
+/// direction). Relative and local forms can't be mixed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Coordinates {
+    World([Coordinate; 3]),
+    Local([f64; 3]),
+}
+
+impl Coordinates {
+    /// Resolves the coordinates against an entity's position and look
+    pub fn resolve(&self, pos: DVec3, look: Look) -> DVec3 {
+        match *self {
+            Coordinates::World([x, y, z]) => DVec3::new(x.get(pos.x), y.get(pos.y), z.get(pos.z)),
+            Coordinates::Local([left, up, forwards]) => {
+                let yaw = f64::from(look.yaw + 90.0).to_radians();
+                let pitch = f64::from(-look.pitch).to_radians();
+                let pitch_up = f64::from(-look.pitch + 90.0).to_radians();
+
+                let forwards_dir =
+                    DVec3::new(yaw.cos() * pitch.cos(), pitch.sin(), yaw.sin() * pitch.cos());
+                let up_dir = DVec3::new(
+                    yaw.cos() * pitch_up.cos(),
+                    pitch_up.sin(),
+                    yaw.sin() * pitch_up.cos(),
+                );
+                let left_dir = -forwards_dir.cross(up_dir);
+
+                pos + forwards_dir * forwards + up_dir * up + left_dir * left
+            }
+        }
+    }
+}
+
+impl CommandArg for Coordinates {
+    fn parse_arg(input: &mut ParseInput) -> Result<Self, CommandArgParseError> {
+        let mut words = [""; 3];
+        for word in &mut words {
+            input.skip_whitespace();
+            *word = input.pop_word();
+        }
+
+        let local = words.iter().filter(|word| word.starts_with('^')).count();
+
+        match local {
+            0 => Ok(Coordinates::World([
+                Coordinate::parse(words[0])?,
+                Coordinate::parse(words[1])?,
+                Coordinate::parse(words[2])?,
+            ])),
+            3 => {
+                let mut offsets = [0.0; 3];
+                for (offset, word) in offsets.iter_mut().zip(words) {
+                    let value = &word[1..];
+                    if !value.is_empty() {
+                        *offset =
+                            value
+                                .parse()
+                                .map_err(|_| CommandArgParseError::InvalidArgument {
+                                    expected: "local coordinate".to_owned(),
+                                    got: word.to_owned(),
+                                })?;
+                    }
+                }
+                Ok(Coordinates::Local(offsets))
+            }
+            _ => Err(CommandArgParseError::InvalidArgument {
+                expected: "local coordinates for every axis".to_owned(),
+                got: words.join(" "),
+            }),
+        }
+    }
+
+    fn display() -> Parser {
+        Parser::Vec3
+    }
+}
+
+/// A yaw and pitch argument, each absolute or relative.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rotation {
+    pub yaw: Coordinate,
+    pub pitch: Coordinate,
+}
+
+impl Rotation {
+    pub fn resolve(&self, look: Look) -> Look {
+        Look {
+            yaw: self.yaw.get(f64::from(look.yaw)) as f32,
+            pitch: (self.pitch.get(f64::from(look.pitch)) as f32).clamp(-90.0, 90.0),
+        }
+    }
+}
+
+impl CommandArg for Rotation {
+    fn parse_arg(input: &mut ParseInput) -> Result<Self, CommandArgParseError> {
+        input.skip_whitespace();
+        let yaw = Coordinate::parse(input.pop_word())?;
+        input.skip_whitespace();
+        let pitch = Coordinate::parse(input.pop_word())?;
+
+        Ok(Rotation { yaw, pitch })
+    }
+
+    fn display() -> Parser {
+        Parser::Rotation
+    }
+}
+
+/// Which part of an entity `facing entity` looks at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EntityAnchor {
+    #[default]
+    Feet,
+    Eyes,
+}
+
+impl CommandArg for EntityAnchor {
+    fn parse_arg(input: &mut ParseInput) -> Result<Self, CommandArgParseError> {
+        input.skip_whitespace();
+        match input.pop_word() {
+            "feet" => Ok(EntityAnchor::Feet),
+            "eyes" => Ok(EntityAnchor::Eyes),
+            word => Err(CommandArgParseError::InvalidArgument {
+                expected: "feet or eyes".to_owned(),
+                got: word.to_owned(),
+            }),
+        }
+    }
+
+    fn display() -> Parser {
+        Parser::EntityAnchor
+    }
+}
+
+/// The look needed to face `target` from `from`
+pub fn look_towards(from: DVec3, target: DVec3) -> Look {
+    let delta = target - from;
+    let horizontal = (delta.x * delta.x + delta.z * delta.z).sqrt();
+
+    Look {
+        yaw: (delta.z.atan2(delta.x).to_degrees() - 90.0) as f32,
+        pitch: (-delta.y.atan2(horizontal).to_degrees()) as f32,
+    }
+}
@@ -1,4 +1,9 @@
-use valence::command::parsers::{EntitySelector, Vec3 as Vec3Parser};
+pub mod apply;
+pub mod coordinates;
+
+use apply::{TeleportCause, TeleportEvent};
+use coordinates::{look_towards, Coordinates, EntityAnchor, Rotation};
+use valence::command::parsers::EntitySelector;
 use valence::command_macros::Command;
 use valence::prelude::Entity;
 
@@ -10,7 +15,7 @@ use valence::prelude::Entity;
 #[scopes("command.teleport")]
 pub enum Command {
     #[paths = "{location}"]
-    ExecutorToLocation { location: Vec3Parser },
+    ExecutorToLocation { location: Coordinates },
     #[paths = "{target}"]
     ExecutorToTarget { target: EntitySelector },
     #[paths = "{from} {to}"]
@@ -21,7 +26,34 @@ pub enum Command {
     #[paths = "{target} {location}"]
     TargetToLocation {
         target: EntitySelector,
-        location: Vec3Parser,
+        location: Coordinates,
+    },
+    #[paths = "{target} {location} {rotation}"]
+    TargetToLocationRotated {
+        target: EntitySelector,
+        location: Coordinates,
+        rotation: Rotation,
+    },
+    #[paths = "{target} {location} facing {facing}"]
+    TargetToLocationFacingLocation {
+        target: EntitySelector,
+        location: Coordinates,
+        facing: Coordinates,
+    },
+    #[paths = "{target} {location} facing entity {facing} {anchor?}"]
+    TargetToLocationFacingEntity {
+        target: EntitySelector,
+        location: Coordinates,
+        facing: EntitySelector,
+        anchor: Option<EntityAnchor>,
+    },
+    /// Lands on the highest safe block of the column, ignoring the height
+    #[paths = "{/} tpsafe {location}"]
+    ExecutorToSafeLocation { location: Coordinates },
+    #[paths = "{/} tpsafe {target} {location}"]
+    TargetToSafeLocation {
+        target: EntitySelector,
+        location: Coordinates,
     },
 }
 
@@ -29,220 +61,238 @@ pub enum TeleportTarget {
     Targets(Vec<Entity>),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum TeleportDestination {
-    Location(Vec3Parser),
-    Target(Option<Entity>),
+    Location(Coordinates),
+    Target(Entity),
 }
 
+/// Where teleported entities look once they arrive
+#[derive(Debug, Clone, Copy)]
+pub enum TeleportFacing {
+    Unchanged,
+    Rotation(Rotation),
+    Location(Coordinates),
+    Entity(Entity, EntityAnchor),
+}
+
+use crate::commands::{
+    feedback::CommandFeedback,
+    selector::{SelectorError, SelectorResolver},
+};
 use valence::command::handler::CommandResultEvent;
-use valence::command::parsers::entity_selector::EntitySelectors;
-use valence::rand;
-use valence::rand::seq::IteratorRandom;
-use valence::{entity::living::LivingEntity, prelude::*};
+use valence::entity::Look;
+use valence::prelude::*;
+use valence::text::IntoText;
+
+/// Eye height of a standing player. Facing is aimed from the eyes, and at
+/// them for `facing entity ... eyes`.
+const EYE_HEIGHT: f64 = 1.62;
 
 pub fn handle(
     mut events: EventReader<CommandResultEvent<Command>>,
-    living_entities: Query<Entity, With<LivingEntity>>,
-    mut clients: Query<(Entity, &mut Client)>,
-    entity_layers: Query<&EntityLayerId>,
-    mut positions: Query<&mut Position>,
-    usernames: Query<(Entity, &Username)>,
+    resolver: SelectorResolver,
+    looks: Query<&Look>,
+    mut teleports: EventWriter<TeleportEvent>,
+    mut feedback: EventWriter<CommandFeedback>,
 ) {
     for event in events.read() {
-        let compiled_command = match &event.result {
-            Command::ExecutorToLocation { location } => (
-                TeleportTarget::Targets(vec![event.executor]),
-                TeleportDestination::Location(*location),
-            ),
-            Command::ExecutorToTarget { target } => (
-                TeleportTarget::Targets(vec![event.executor]),
-                TeleportDestination::Target(
-                    find_targets(
-                        &living_entities,
-                        &mut clients,
-                        &positions,
-                        &entity_layers,
-                        &usernames,
-                        event,
-                        target,
-                    )
-                    .first()
-                    .copied(),
-                ),
-            ),
-            Command::TargetToTarget { from, to } => (
-                TeleportTarget::Targets(
-                    find_targets(
-                        &living_entities,
-                        &mut clients,
-                        &positions,
-                        &entity_layers,
-                        &usernames,
-                        event,
-                        from,
-                    )
-                    .clone(),
-                ),
-                TeleportDestination::Target(
-                    find_targets(
-                        &living_entities,
-                        &mut clients,
-                        &positions,
-                        &entity_layers,
-                        &usernames,
-                        event,
-                        to,
-                    )
-                    .first()
-                    .copied(),
-                ),
-            ),
-            Command::TargetToLocation { target, location } => (
-                TeleportTarget::Targets(
-                    find_targets(
-                        &living_entities,
-                        &mut clients,
-                        &positions,
-                        &entity_layers,
-                        &usernames,
-                        event,
-                        target,
-                    )
-                    .clone(),
-                ),
-                TeleportDestination::Location(*location),
-            ),
+        let (TeleportTarget::Targets(targets), destination, facing, safe) =
+            match compile(&resolver, event) {
+                Ok(compiled_command) => compiled_command,
+                Err(e) => {
+                    feedback.send(CommandFeedback::new(event.executor, e.to_text()));
+                    continue;
+                }
+            };
+
+        let destination_location = match destination {
+            TeleportDestination::Target(entity) => resolver.location(entity),
+            TeleportDestination::Location(_) => None,
         };
 
-        let (TeleportTarget::Targets(targets), destination) = compiled_command;
+        let mut last = DVec3::ZERO;
+
+        for target in &targets {
+            let Some((pos, _)) = resolver.location(*target) else {
+                continue;
+            };
+            let look = looks.get(*target).copied().unwrap_or_default();
 
-        println!("executing teleport command {targets:#?} -> {destination:#?}");
-        match destination {
-            TeleportDestination::Location(location) => {
-                for target in targets {
-                    let mut pos = positions.get_mut(target).unwrap();
-                    pos.0.x = f64::from(location.x.get(pos.0.x as f32));
-                    pos.0.y = f64::from(location.y.get(pos.0.y as f32));
-                    pos.0.z = f64::from(location.z.get(pos.0.z as f32));
+            let (position, layer) = match destination {
+                TeleportDestination::Location(location) => (location.resolve(pos, look), None),
+                TeleportDestination::Target(_) => match destination_location {
+                    Some((position, layer)) => (position, Some(layer)),
+                    None => continue,
+                },
+            };
+
+            let look = match facing {
+                TeleportFacing::Unchanged => None,
+                TeleportFacing::Rotation(rotation) => Some(rotation.resolve(look)),
+                TeleportFacing::Location(facing) => {
+                    Some(look_towards(position + DVec3::Y * EYE_HEIGHT, facing.resolve(pos, look)))
                 }
-            }
-            TeleportDestination::Target(target) => {
-                let target = target.unwrap();
-                let target_pos = **positions.get(target).unwrap();
-                for target in targets {
-                    let mut position = positions.get_mut(target).unwrap();
-                    position.0 = target_pos;
+                TeleportFacing::Entity(facing, anchor) => {
+                    resolver.location(facing).map(|(facing_pos, _)| {
+                        let facing_pos = match anchor {
+                            EntityAnchor::Feet => facing_pos,
+                            EntityAnchor::Eyes => facing_pos + DVec3::Y * EYE_HEIGHT,
+                        };
+                        look_towards(position + DVec3::Y * EYE_HEIGHT, facing_pos)
+                    })
                 }
-            }
+            };
+
+            teleports.send(TeleportEvent {
+                entity: *target,
+                position,
+                look,
+                layer,
+                safe,
+                cause: TeleportCause::Command,
+            });
+
+            last = position;
         }
-    }
 
-    fn find_targets(
-        living_entities: &Query<Entity, With<LivingEntity>>,
-        clients: &mut Query<(Entity, &mut Client)>,
-        positions: &Query<&mut Position>,
-        entity_layers: &Query<&EntityLayerId>,
-        usernames: &Query<(Entity, &Username)>,
-        event: &CommandResultEvent<Command>,
-        target: &EntitySelector,
-    ) -> Vec<Entity> {
-        match target {
-            EntitySelector::SimpleSelector(selector) => match selector {
-                EntitySelectors::AllEntities => {
-                    let executor_entity_layer = *entity_layers.get(event.executor).unwrap();
-                    living_entities
-                        .iter()
-                        .filter(|entity| {
-                            let entity_layer = entity_layers.get(*entity).unwrap();
-                            entity_layer.0 == executor_entity_layer.0
-                        })
-                        .collect()
-                }
-                EntitySelectors::SinglePlayer(name) => {
-                    let target = usernames.iter().find(|(_, username)| username.0 == *name);
-                    match target {
-                        None => {
-                            let client = &mut clients.get_mut(event.executor).unwrap().1;
-                            client.send_chat_message(format!("Could not find target: {name}"));
-                            vec![]
-                        }
-                        Some(target_entity) => {
-                            vec![target_entity.0]
-                        }
-                    }
-                }
-                EntitySelectors::AllPlayers => {
-                    let executor_entity_layer = *entity_layers.get(event.executor).unwrap();
-                    clients
-                        .iter_mut()
-                        .filter_map(|(entity, ..)| {
-                            let entity_layer = entity_layers.get(entity).unwrap();
-                            if entity_layer.0 == executor_entity_layer.0 {
-                                Some(entity)
-                            } else {
-                                None
-                            }
-                        })
-                        .collect()
-                }
-                EntitySelectors::SelfPlayer => {
-                    vec![event.executor]
-                }
-                EntitySelectors::NearestPlayer => {
-                    let executor_entity_layer = *entity_layers.get(event.executor).unwrap();
-                    let executor_pos = positions.get(event.executor).unwrap();
-                    let target = clients
-                        .iter_mut()
-                        .filter(|(entity, ..)| {
-                            *entity_layers.get(*entity).unwrap() == executor_entity_layer
-                        })
-                        .filter(|(target, ..)| *target != event.executor)
-                        .map(|(target, ..)| target)
-                        .min_by(|target, target2| {
-                            let target_pos = positions.get(*target).unwrap();
-                            let target2_pos = positions.get(*target2).unwrap();
-                            let target_dist = target_pos.distance(**executor_pos);
-                            let target2_dist = target2_pos.distance(**executor_pos);
-                            target_dist.partial_cmp(&target2_dist).unwrap()
-                        });
-                    match target {
-                        None => {
-                            let mut client = clients.get_mut(event.executor).unwrap().1;
-                            client.send_chat_message("Could not find target".to_owned());
-                            vec![]
-                        }
-                        Some(target_entity) => {
-                            vec![target_entity]
-                        }
-                    }
-                }
-                EntitySelectors::RandomPlayer => {
-                    let executor_entity_layer = *entity_layers.get(event.executor).unwrap();
-                    let target = clients
-                        .iter_mut()
-                        .filter(|(entity, ..)| {
-                            *entity_layers.get(*entity).unwrap() == executor_entity_layer
-                        })
-                        .choose(&mut rand::thread_rng())
-                        .map(|(target, ..)| target);
-                    match target {
-                        None => {
-                            let mut client = clients.get_mut(event.executor).unwrap().1;
-                            client.send_chat_message("Could not find target".to_owned());
-                            vec![]
-                        }
-                        Some(target_entity) => {
-                            vec![target_entity]
-                        }
-                    }
-                }
-            },
-            EntitySelector::ComplexSelector(_, _) => {
-                let mut client = clients.get_mut(event.executor).unwrap().1;
-                client.send_chat_message("complex selector not implemented".to_owned());
-                vec![]
+        let target_name = match targets.as_slice() {
+            [single] => resolver.display_name(*single),
+            _ => targets.len().to_string(),
+        };
+        let plural = targets.len() != 1;
+
+        let message = match destination {
+            TeleportDestination::Location(_) => {
+                let key = if plural {
+                    "commands.teleport.success.location.multiple"
+                } else {
+                    "commands.teleport.success.location.single"
+                };
+                Text::translate(
+                    key,
+                    [
+                        target_name.into_text(),
+                        format!("{:.2}", last.x).into_text(),
+                        format!("{:.2}", last.y).into_text(),
+                        format!("{:.2}", last.z).into_text(),
+                    ],
+                )
             }
-        }
+            TeleportDestination::Target(destination) => {
+                let key = if plural {
+                    "commands.teleport.success.entity.multiple"
+                } else {
+                    "commands.teleport.success.entity.single"
+                };
+                Text::translate(
+                    key,
+                    [
+                        target_name.into_text(),
+                        resolver.display_name(destination).into_text(),
+                    ],
+                )
+            }
+        };
+
+        feedback.send(CommandFeedback::new(event.executor, message));
     }
 }
+
+/// Resolves the selectors of a teleport command into the entities to move,
+/// where to move them, where they should look and whether to land on the
+/// surface.
+fn compile(
+    resolver: &SelectorResolver,
+    event: &CommandResultEvent<Command>,
+) -> Result<(TeleportTarget, TeleportDestination, TeleportFacing, bool), SelectorError> {
+    let executor = || {
+        resolver
+            .location(event.executor)
+            .map(|_| vec![event.executor])
+            .ok_or(SelectorError::RequiresEntity)
+    };
+
+    // Only the first match of the destination is used, as in vanilla.
+    let single = |selector: &EntitySelector| {
+        resolver
+            .resolve(event.executor, selector)
+            .map(|targets| targets[0])
+    };
+
+    let targets = |selector: &EntitySelector| {
+        resolver
+            .resolve(event.executor, selector)
+            .map(TeleportTarget::Targets)
+    };
+
+    Ok(match &event.result {
+        Command::ExecutorToLocation { location } => (
+            TeleportTarget::Targets(executor()?),
+            TeleportDestination::Location(*location),
+            TeleportFacing::Unchanged,
+            false,
+        ),
+        Command::ExecutorToTarget { target } => (
+            TeleportTarget::Targets(executor()?),
+            TeleportDestination::Target(single(target)?),
+            TeleportFacing::Unchanged,
+            false,
+        ),
+        Command::TargetToTarget { from, to } => (
+            targets(from)?,
+            TeleportDestination::Target(single(to)?),
+            TeleportFacing::Unchanged,
+            false,
+        ),
+        Command::TargetToLocation { target, location } => (
+            targets(target)?,
+            TeleportDestination::Location(*location),
+            TeleportFacing::Unchanged,
+            false,
+        ),
+        Command::TargetToLocationRotated {
+            target,
+            location,
+            rotation,
+        } => (
+            targets(target)?,
+            TeleportDestination::Location(*location),
+            TeleportFacing::Rotation(*rotation),
+            false,
+        ),
+        Command::TargetToLocationFacingLocation {
+            target,
+            location,
+            facing,
+        } => (
+            targets(target)?,
+            TeleportDestination::Location(*location),
+            TeleportFacing::Location(*facing),
+            false,
+        ),
+        Command::TargetToLocationFacingEntity {
+            target,
+            location,
+            facing,
+            anchor,
+        } => (
+            targets(target)?,
+            TeleportDestination::Location(*location),
+            TeleportFacing::Entity(single(facing)?, anchor.unwrap_or_default()),
+            false,
+        ),
+        Command::ExecutorToSafeLocation { location } => (
+            TeleportTarget::Targets(executor()?),
+            TeleportDestination::Location(*location),
+            TeleportFacing::Unchanged,
+            true,
+        ),
+        Command::TargetToSafeLocation { target, location } => (
+            targets(target)?,
+            TeleportDestination::Location(*location),
+            TeleportFacing::Unchanged,
+            true,
+        ),
+    })
+}
@@ -0,0 +1,136 @@
+use valence::{
+    command::{
+        handler::CommandResultEvent,
+        parsers::{EntitySelector, GreedyString},
+    },
+    command_macros::Command,
+    prelude::*,
+    text::IntoText,
+    title::SetTitle,
+};
+
+use crate::{
+    commands::{
+        feedback::CommandFeedback,
+        selector::{SelectorError, SelectorResolver},
+    },
+    hud,
+};
+
+#[derive(Command, Debug, Clone)]
+#[paths("title")]
+#[scopes("command.title")]
+pub enum Command {
+    #[paths("{targets} clear")]
+    Clear { targets: EntitySelector },
+    #[paths("{targets} reset")]
+    Reset { targets: EntitySelector },
+    #[paths("{targets} title {text}")]
+    Title {
+        targets: EntitySelector,
+        text: GreedyString,
+    },
+    #[paths("{targets} subtitle {text}")]
+    Subtitle {
+        targets: EntitySelector,
+        text: GreedyString,
+    },
+    #[paths("{targets} actionbar {text}")]
+    ActionBar {
+        targets: EntitySelector,
+        text: GreedyString,
+    },
+    #[paths("{targets} times {fade_in} {stay} {fade_out}")]
+    Times {
+        targets: EntitySelector,
+        fade_in: i32,
+        stay: i32,
+        fade_out: i32,
+    },
+}
+
+impl Command {
+    fn targets(&self) -> &EntitySelector {
+        match self {
+            Command::Clear { targets }
+            | Command::Reset { targets }
+            | Command::Title { targets, .. }
+            | Command::Subtitle { targets, .. }
+            | Command::ActionBar { targets, .. }
+            | Command::Times { targets, .. } => targets,
+        }
+    }
+}
+
+pub fn handle(
+    mut events: EventReader<CommandResultEvent<Command>>,
+    mut params: ParamSet<(SelectorResolver, Query<&mut Client>)>,
+    mut feedback: EventWriter<CommandFeedback>,
+) {
+    for event in events.read() {
+        let resolver = params.p0();
+
+        let targets: Vec<_> = match resolver.resolve(event.executor, event.result.targets()) {
+            Ok(targets) => targets
+                .into_iter()
+                .filter(|target| resolver.is_player(*target))
+                .collect(),
+            Err(e) => {
+                feedback.send(CommandFeedback::new(event.executor, e.to_text()));
+                continue;
+            }
+        };
+
+        let target_name = match targets.as_slice() {
+            [] => {
+                feedback.send(CommandFeedback::new(
+                    event.executor,
+                    SelectorError::NoPlayerFound.to_text(),
+                ));
+                continue;
+            }
+            [single] => resolver.display_name(*single),
+            _ => targets.len().to_string(),
+        };
+
+        let mut clients = params.p1();
+        for target in &targets {
+            let Ok(mut client) = clients.get_mut(*target) else {
+                continue;
+            };
+
+            match &event.result {
+                Command::Clear { .. } => client.clear_title(),
+                Command::Reset { .. } => client.reset_title(),
+                Command::Title { text, .. } => {
+                    hud::show_title(&mut client, hud::parse_text(&text.0), None, None)
+                }
+                Command::Subtitle { text, .. } => client.set_subtitle(hud::parse_text(&text.0)),
+                Command::ActionBar { text, .. } => {
+                    hud::show_action_bar(&mut client, hud::parse_text(&text.0))
+                }
+                Command::Times {
+                    fade_in,
+                    stay,
+                    fade_out,
+                    ..
+                } => client.set_title_times(*fade_in, *stay, *fade_out),
+            }
+        }
+
+        let key = match &event.result {
+            Command::Clear { .. } => "commands.title.cleared",
+            Command::Reset { .. } => "commands.title.reset",
+            Command::Title { .. } => "commands.title.show.title",
+            Command::Subtitle { .. } => "commands.title.show.subtitle",
+            Command::ActionBar { .. } => "commands.title.show.actionbar",
+            Command::Times { .. } => "commands.title.times",
+        };
+        let suffix = if targets.len() == 1 { "single" } else { "multiple" };
+
+        feedback.send(CommandFeedback::new(
+            event.executor,
+            Text::translate(format!("{key}.{suffix}"), [target_name.into_text()]),
+        ));
+    }
+}
@@ -0,0 +1,247 @@
+use std::{collections::HashMap, time::Instant};
+
+use valence::{
+    command::{handler::CommandResultEvent, parsers::EntitySelector},
+    command_macros::Command,
+    prelude::*,
+    text::{Color, IntoText},
+};
+
+use crate::{
+    commands::{
+        feedback::CommandFeedback,
+        selector::{SelectorError, SelectorResolver},
+        teleport::apply::{TeleportCause, TeleportEvent},
+    },
+    setup::settings::Settings,
+};
+
+#[derive(Command, Debug, Clone)]
+#[paths("tpa")]
+#[scopes("command.tpa")]
+pub enum Command {
+    #[paths("{target}")]
+    Request { target: EntitySelector },
+    #[paths("{/} tpaccept {from?}")]
+    Accept { from: Option<EntitySelector> },
+    #[paths("{/} tpdeny {from?}")]
+    Deny { from: Option<EntitySelector> },
+}
+
+/// A player asking to be teleported to another player
+#[derive(Clone, Copy, Debug)]
+pub struct TeleportRequest {
+    pub from: Entity,
+    pub to: Entity,
+    pub sent: Instant,
+}
+
+/// Open teleport requests and when each player last sent one.
+#[derive(Resource, Default)]
+pub struct TeleportRequests {
+    requests: Vec<TeleportRequest>,
+    last_sent: HashMap<Entity, Instant>,
+}
+
+impl TeleportRequests {
+    /// Removes and returns the newest request to `to`, optionally only the
+    /// one from `from`
+    fn take(&mut self, to: Entity, from: Option<Entity>) -> Option<TeleportRequest> {
+        let index = self
+            .requests
+            .iter()
+            .rposition(|request| request.to == to && from.map_or(true, |from| request.from == from))?;
+
+        Some(self.requests.remove(index))
+    }
+}
+
+fn error(message: impl Into<String>) -> Text {
+    message.into().color(Color::RED)
+}
+
+pub fn handle(
+    mut events: EventReader<CommandResultEvent<Command>>,
+    resolver: SelectorResolver,
+    usernames: Query<&Username, With<Client>>,
+    mut requests: ResMut<TeleportRequests>,
+    settings: Res<Settings>,
+    mut teleports: EventWriter<TeleportEvent>,
+    mut feedback: EventWriter<CommandFeedback>,
+) {
+    for event in events.read() {
+        let executor = event.executor;
+
+        let Ok(executor_name) = usernames.get(executor) else {
+            feedback.send(CommandFeedback::new(
+                executor,
+                Text::translate("permissions.requires.player", []).color(Color::RED),
+            ));
+            continue;
+        };
+
+        // Requests are between two players, so selectors must name exactly one
+        let single_player = |selector: &EntitySelector| match resolver.resolve(executor, selector) {
+            Ok(targets) => match targets.as_slice() {
+                [target] if usernames.contains(*target) => Ok(*target),
+                _ => Err(SelectorError::NoPlayerFound.to_text()),
+            },
+            Err(e) => Err(e.to_text()),
+        };
+
+        match &event.result {
+            Command::Request { target } => {
+                let target = match single_player(target) {
+                    Ok(target) => target,
+                    Err(e) => {
+                        feedback.send(CommandFeedback::new(executor, e));
+                        continue;
+                    }
+                };
+
+                if target == executor {
+                    feedback.send(CommandFeedback::new(
+                        executor,
+                        error("You can't send a teleport request to yourself"),
+                    ));
+                    continue;
+                }
+
+                if let Some(last_sent) = requests.last_sent.get(&executor) {
+                    let elapsed = last_sent.elapsed();
+                    if elapsed < settings.tpa_cooldown {
+                        let wait = (settings.tpa_cooldown - elapsed).as_secs() + 1;
+                        feedback.send(CommandFeedback::new(
+                            executor,
+                            error(format!(
+                                "You must wait {wait}s before sending another request"
+                            )),
+                        ));
+                        continue;
+                    }
+                }
+
+                // A new request replaces any earlier one from the same player
+                requests.requests.retain(|request| request.from != executor);
+                requests.requests.push(TeleportRequest {
+                    from: executor,
+                    to: target,
+                    sent: Instant::now(),
+                });
+                requests.last_sent.insert(executor, Instant::now());
+
+                let target_name = resolver.display_name(target);
+                let name = &executor_name.0;
+
+                feedback.send(CommandFeedback::new(
+                    executor,
+                    format!("Teleport request sent to {target_name}").color(Color::GOLD),
+                ));
+                feedback.send(CommandFeedback::new(
+                    target,
+                    format!("{name} has requested to teleport to you. ")
+                        .color(Color::GOLD)
+                        .add_child(
+                            "[Accept]"
+                                .color(Color::GREEN)
+                                .on_click_run_command(format!("/tpaccept {name}")),
+                        )
+                        .add_child(" ")
+                        .add_child(
+                            "[Deny]"
+                                .color(Color::RED)
+                                .on_click_run_command(format!("/tpdeny {name}")),
+                        ),
+                ));
+            }
+            Command::Accept { from } | Command::Deny { from } => {
+                let from = match from.as_ref().map(single_player).transpose() {
+                    Ok(from) => from,
+                    Err(e) => {
+                        feedback.send(CommandFeedback::new(executor, e));
+                        continue;
+                    }
+                };
+
+                let Some(request) = requests.take(executor, from) else {
+                    feedback.send(CommandFeedback::new(
+                        executor,
+                        error("You have no pending teleport requests"),
+                    ));
+                    continue;
+                };
+
+                let requester_name = resolver.display_name(request.from);
+                let name = &executor_name.0;
+
+                if let Command::Deny { .. } = &event.result {
+                    feedback.send(CommandFeedback::new(
+                        executor,
+                        format!("Denied the teleport request from {requester_name}")
+                            .color(Color::GOLD),
+                    ));
+                    feedback.send(CommandFeedback::new(
+                        request.from,
+                        error(format!("{name} denied your teleport request")),
+                    ));
+                    continue;
+                }
+
+                let Some((position, layer)) = resolver.location(executor) else {
+                    continue;
+                };
+
+                teleports.send(TeleportEvent {
+                    layer: Some(layer),
+                    cause: TeleportCause::Request,
+                    ..TeleportEvent::new(request.from, position)
+                });
+
+                feedback.send(CommandFeedback::new(
+                    executor,
+                    format!("Accepted the teleport request from {requester_name}")
+                        .color(Color::GREEN),
+                ));
+                feedback.send(CommandFeedback::new(
+                    request.from,
+                    format!("{name} accepted your teleport request").color(Color::GREEN),
+                ));
+            }
+        }
+    }
+}
+
+/// Drops requests that timed out or whose players left.
+pub fn expire_requests(
+    mut requests: ResMut<TeleportRequests>,
+    usernames: Query<&Username, With<Client>>,
+    settings: Res<Settings>,
+    mut feedback: EventWriter<CommandFeedback>,
+) {
+    let requests = &mut *requests;
+
+    requests.requests.retain(|request| {
+        let (Ok(from), Ok(to)) = (usernames.get(request.from), usernames.get(request.to)) else {
+            return false;
+        };
+
+        if request.sent.elapsed() < settings.tpa_timeout {
+            return true;
+        }
+
+        feedback.send(CommandFeedback::new(
+            request.from,
+            error(format!("Your teleport request to {} expired", to.0)),
+        ));
+        feedback.send(CommandFeedback::new(
+            request.to,
+            error(format!("The teleport request from {} expired", from.0)),
+        ));
+
+        false
+    });
+
+    requests
+        .last_sent
+        .retain(|_, last_sent| last_sent.elapsed() < settings.tpa_cooldown);
+}
@@ -0,0 +1,193 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use valence::{
+    command::handler::CommandResultEvent,
+    command_macros::Command,
+    entity::Look,
+    prelude::*,
+    text::{Color, IntoText},
+};
+
+use crate::{
+    commands::{
+        feedback::CommandFeedback,
+        teleport::apply::{TeleportCause, TeleportEvent},
+    },
+    server::shutdown::ShutdownEvent,
+    setup::settings::Settings,
+    world::{
+        spawn::LayerName,
+        storage::{self, SavedLocation},
+    },
+};
+
+#[derive(Command, Debug, Clone)]
+#[paths("warp")]
+#[scopes("command.warp")]
+pub enum Command {
+    #[paths("{name}")]
+    Teleport { name: String },
+    #[paths("{/} warps")]
+    List {},
+}
+
+/// Creating and removing warps, kept apart so it can be granted separately
+#[derive(Command, Debug, Clone)]
+#[paths("setwarp")]
+#[scopes("command.setwarp")]
+pub enum ManageCommand {
+    #[paths("{name}")]
+    Set { name: String },
+    #[paths("{/} delwarp {name}")]
+    Delete { name: String },
+}
+
+/// Server-wide named locations, saved to `warps.json` in the world's data
+/// directory whenever they change.
+#[derive(Resource)]
+pub struct Warps {
+    path: PathBuf,
+    warps: BTreeMap<String, SavedLocation>,
+}
+
+impl Warps {
+    fn save(&self) {
+        storage::save(&self.path, &self.warps);
+    }
+}
+
+pub fn load_warps(mut commands: Commands, settings: Res<Settings>) {
+    let path = storage::data_dir(&settings).join("warps.json");
+
+    commands.insert_resource(Warps {
+        warps: storage::load(&path),
+        path,
+    });
+}
+
+/// Writes warps out one last time when the server stops
+pub fn save_warps(warps: Res<Warps>, mut shutdown: EventReader<ShutdownEvent>) {
+    if shutdown.read().next().is_some() {
+        warps.save();
+    }
+}
+
+fn error(message: impl Into<String>) -> Text {
+    message.into().color(Color::RED)
+}
+
+pub fn handle(
+    mut events: EventReader<CommandResultEvent<Command>>,
+    layers: Query<(Entity, &LayerName)>,
+    players: Query<(), With<Client>>,
+    warps: Res<Warps>,
+    mut teleports: EventWriter<TeleportEvent>,
+    mut feedback: EventWriter<CommandFeedback>,
+) {
+    for event in events.read() {
+        let executor = event.executor;
+
+        match &event.result {
+            Command::Teleport { name } => {
+                if !players.contains(executor) {
+                    feedback.send(CommandFeedback::new(
+                        executor,
+                        Text::translate("permissions.requires.player", []).color(Color::RED),
+                    ));
+                    continue;
+                }
+
+                let Some(warp) = warps.warps.get(name) else {
+                    feedback.send(CommandFeedback::new(
+                        executor,
+                        error(format!("There is no warp called {name}")),
+                    ));
+                    continue;
+                };
+
+                let Some(layer) = warp.find_layer(layers.iter()) else {
+                    feedback.send(CommandFeedback::new(
+                        executor,
+                        error(format!("Warp {name} is in {}, which isn't loaded", warp.layer)),
+                    ));
+                    continue;
+                };
+
+                teleports.send(TeleportEvent {
+                    look: Some(warp.look()),
+                    layer: Some(layer),
+                    cause: TeleportCause::Warp,
+                    ..TeleportEvent::new(executor, warp.position())
+                });
+
+                feedback.send(CommandFeedback::new(
+                    executor,
+                    format!("Warping to {name}").color(Color::GREEN),
+                ));
+            }
+            Command::List {} => {
+                let message = if warps.warps.is_empty() {
+                    "There are no warps".color(Color::GOLD)
+                } else {
+                    let names: Vec<_> = warps.warps.keys().map(String::as_str).collect();
+                    format!("Warps: {}", names.join(", ")).color(Color::GOLD)
+                };
+
+                feedback.send(CommandFeedback::new(executor, message));
+            }
+        }
+    }
+}
+
+pub fn handle_manage(
+    mut events: EventReader<CommandResultEvent<ManageCommand>>,
+    players: Query<(&Position, &Look, &EntityLayerId), With<Client>>,
+    layers: Query<&LayerName>,
+    mut warps: ResMut<Warps>,
+    mut feedback: EventWriter<CommandFeedback>,
+) {
+    for event in events.read() {
+        let executor = event.executor;
+
+        match &event.result {
+            ManageCommand::Set { name } => {
+                let Ok((pos, look, layer)) = players.get(executor) else {
+                    feedback.send(CommandFeedback::new(
+                        executor,
+                        Text::translate("permissions.requires.player", []).color(Color::RED),
+                    ));
+                    continue;
+                };
+
+                let Ok(layer_name) = layers.get(layer.0) else {
+                    continue;
+                };
+                warps
+                    .warps
+                    .insert(name.clone(), SavedLocation::new(layer_name, pos.0, *look));
+                warps.save();
+
+                feedback.send(CommandFeedback::new(
+                    executor,
+                    format!("Warp {name} set").color(Color::GREEN),
+                ));
+            }
+            ManageCommand::Delete { name } => {
+                if warps.warps.remove(name).is_none() {
+                    feedback.send(CommandFeedback::new(
+                        executor,
+                        error(format!("There is no warp called {name}")),
+                    ));
+                    continue;
+                }
+
+                warps.save();
+
+                feedback.send(CommandFeedback::new(
+                    executor,
+                    format!("Warp {name} deleted").color(Color::GOLD),
+                ));
+            }
+        }
+    }
+}
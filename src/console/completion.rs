@@ -0,0 +1,176 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, RwLock},
+};
+
+use rustyline::{
+    completion::Completer, highlight::Highlighter, hint::Hinter, validate::Validator, Context,
+    Helper,
+};
+use valence::{
+    command::{graph::CommandEdgeType, CommandRegistry},
+    prelude::*,
+    protocol::packets::play::command_tree_s2c::NodeData,
+};
+
+/// Redirects can point back at the root (`execute run ...`), so the walk is
+/// cut off at this many words.
+const MAX_DEPTH: usize = 8;
+
+/// A literal-only view of the command graph, used for tab completion.
+///
+/// Argument nodes are collapsed into a single wildcard child which completes
+/// to online player names.
+#[derive(Default, Debug)]
+pub struct CompletionNode {
+    literals: BTreeMap<String, CompletionNode>,
+    argument: Option<Box<CompletionNode>>,
+}
+
+impl CompletionNode {
+    fn child_mut(&mut self, step: &Option<String>) -> &mut CompletionNode {
+        match step {
+            Some(name) => self.literals.entry(name.clone()).or_default(),
+            None => self.argument.get_or_insert_with(Default::default),
+        }
+    }
+
+    fn child(&self, word: &str) -> Option<&CompletionNode> {
+        self.literals.get(word).or(self.argument.as_deref())
+    }
+}
+
+/// Completion data shared between the game loop and the console thread.
+#[derive(Resource, Clone, Default)]
+pub struct ConsoleCompletions {
+    tree: Arc<RwLock<CompletionNode>>,
+    players: Arc<RwLock<Vec<String>>>,
+}
+
+impl ConsoleCompletions {
+    /// Returns the byte offset the candidates replace from and the candidates
+    pub fn complete(&self, line: &str) -> (usize, Vec<String>) {
+        let start = line.rfind(char::is_whitespace).map_or(0, |i| i + 1);
+        let partial = &line[start..];
+
+        let tree = self.tree.read().unwrap();
+        let mut node = &*tree;
+
+        for word in line[..start].split_whitespace() {
+            match node.child(word) {
+                Some(child) => node = child,
+                None => return (start, vec![]),
+            }
+        }
+
+        let mut candidates: Vec<String> = node
+            .literals
+            .keys()
+            .filter(|name| name.starts_with(partial))
+            .cloned()
+            .collect();
+
+        if node.argument.is_some() {
+            let players = self.players.read().unwrap();
+            candidates.extend(
+                players
+                    .iter()
+                    .filter(|name| name.starts_with(partial))
+                    .cloned(),
+            );
+        }
+
+        (start, candidates)
+    }
+}
+
+/// The rustyline helper for the console prompt.
+pub struct ConsoleHelper {
+    pub completions: ConsoleCompletions,
+}
+
+impl Completer for ConsoleHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        Ok(self.completions.complete(&line[..pos]))
+    }
+}
+
+impl Hinter for ConsoleHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ConsoleHelper {}
+
+impl Validator for ConsoleHelper {}
+
+impl Helper for ConsoleHelper {}
+
+/// Walks the command graph once every command has been registered and
+/// rebuilds the completion tree from it.
+pub fn build_completions(registry: Res<CommandRegistry>, completions: Res<ConsoleCompletions>) {
+    let graph = &registry.graph.graph;
+    let mut root = CompletionNode::default();
+
+    let mut stack = vec![(registry.graph.root, Vec::<Option<String>>::new())];
+    let mut visited = 0;
+
+    while let Some((node, path)) = stack.pop() {
+        visited += 1;
+        if path.len() >= MAX_DEPTH || visited > 10_000 {
+            continue;
+        }
+
+        for neighbor in graph.neighbors(node) {
+            let Some(edge) = graph.find_edge(node, neighbor) else {
+                continue;
+            };
+
+            match graph[edge] {
+                CommandEdgeType::Redirect => stack.push((neighbor, path.clone())),
+                CommandEdgeType::Child => {
+                    let step = match &graph[neighbor].data {
+                        NodeData::Literal { name } => Some(name.clone()),
+                        NodeData::Argument { .. } => None,
+                        NodeData::Root => continue,
+                    };
+
+                    let mut path = path.clone();
+                    path.push(step);
+
+                    let mut tree = &mut root;
+                    for step in &path {
+                        tree = tree.child_mut(step);
+                    }
+
+                    stack.push((neighbor, path));
+                }
+            }
+        }
+    }
+
+    *completions.tree.write().unwrap() = root;
+}
+
+/// Keeps the list of player names offered for argument completion current.
+pub fn update_player_names(
+    added: Query<(), Added<Client>>,
+    mut removed: RemovedComponents<Client>,
+    usernames: Query<&Username, With<Client>>,
+    completions: Res<ConsoleCompletions>,
+) {
+    if added.is_empty() && removed.read().next().is_none() {
+        return;
+    }
+
+    let mut players: Vec<String> = usernames.iter().map(|name| name.0.clone()).collect();
+    players.sort();
+
+    *completions.players.write().unwrap() = players;
+}
@@ -0,0 +1,207 @@
+pub mod completion;
+
+use std::{
+    io::{self, Write},
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use completion::{ConsoleCompletions, ConsoleHelper};
+use flume::Receiver;
+use rustyline::{error::ReadlineError, history::DefaultHistory, Editor, ExternalPrinter};
+use valence::{
+    command::{scopes::CommandScopes, CommandExecutionEvent},
+    log::tracing_subscriber::{
+        fmt::{self, MakeWriter},
+        layer::SubscriberExt,
+        util::SubscriberInitExt,
+        EnvFilter,
+    },
+    prelude::*,
+};
+
+use crate::commands::feedback::{plain_text, CommandFeedback};
+
+/// Marks the entity that executes commands typed into the server console.
+///
+/// It holds the `admin` scope, so every command is available to it.
+#[derive(Component)]
+pub struct Console;
+
+/// Lines read from stdin, waiting to be executed.
+#[derive(Resource)]
+pub struct ConsoleInput(Receiver<String>);
+
+type Printer = Box<dyn ExternalPrinter + Send>;
+
+/// Prints above the prompt instead of through it.
+///
+/// Falls back to stderr when stdin is not a terminal.
+#[derive(Resource, Clone, Default)]
+pub struct ConsoleOutput(Arc<Mutex<Option<Printer>>>);
+
+impl ConsoleOutput {
+    pub fn print(&self, line: impl Into<String>) {
+        let line = line.into();
+        let mut printer = self.0.lock().unwrap();
+
+        match printer.as_mut() {
+            Some(printer) => {
+                if printer.print(line.clone()).is_err() {
+                    eprintln!("{line}");
+                }
+            }
+            None => eprintln!("{line}"),
+        }
+    }
+}
+
+/// A single formatted log event, printed as one line once it is complete.
+pub struct ConsoleLine {
+    output: ConsoleOutput,
+    buf: Vec<u8>,
+}
+
+impl Write for ConsoleLine {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for ConsoleLine {
+    fn drop(&mut self) {
+        if !self.buf.is_empty() {
+            let line = String::from_utf8_lossy(&self.buf);
+            self.output.print(line.trim_end());
+        }
+    }
+}
+
+impl<'a> MakeWriter<'a> for ConsoleOutput {
+    type Writer = ConsoleLine;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        ConsoleLine {
+            output: self.clone(),
+            buf: vec![],
+        }
+    }
+}
+
+/// Starts the prompt on its own thread
+///
+/// Ctrl-C and Ctrl-D at the prompt stop the server, as the terminal no longer
+/// delivers SIGINT while the prompt is active.
+pub fn start(completions: ConsoleCompletions) -> (ConsoleInput, ConsoleOutput) {
+    let (line_sender, line_receiver) = flume::unbounded();
+    let (printer_sender, printer_receiver) = flume::bounded(1);
+
+    thread::spawn(move || {
+        let mut editor = match Editor::<ConsoleHelper, DefaultHistory>::new() {
+            Ok(editor) => editor,
+            Err(e) => {
+                eprintln!("failed to start console: {e}");
+                let _ = printer_sender.send(None);
+                return;
+            }
+        };
+
+        editor.set_helper(Some(ConsoleHelper { completions }));
+
+        let printer = editor
+            .create_external_printer()
+            .ok()
+            .map(|printer| Box::new(printer) as Printer);
+        let _ = printer_sender.send(printer);
+
+        loop {
+            match editor.readline("> ") {
+                Ok(line) => {
+                    let _ = editor.add_history_entry(line.as_str());
+                    if line_sender.send(line).is_err() {
+                        break;
+                    }
+                }
+                Err(ReadlineError::Interrupted | ReadlineError::Eof) => {
+                    let _ = line_sender.send("stop".to_owned());
+                    break;
+                }
+                Err(e) => {
+                    eprintln!("console error: {e}");
+                    break;
+                }
+            }
+        }
+    });
+
+    let printer = printer_receiver.recv().ok().flatten();
+
+    (
+        ConsoleInput(line_receiver),
+        ConsoleOutput(Arc::new(Mutex::new(printer))),
+    )
+}
+
+/// Routes all log output through the console so it doesn't clobber the line
+/// being typed
+///
+/// This replaces the `LogPlugin`, which must be disabled.
+pub fn init_logging(output: ConsoleOutput) {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+
+    let result = valence::log::tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer().with_writer(output))
+        .try_init();
+
+    if let Err(e) = result {
+        eprintln!("failed to set up console logging: {e}");
+    }
+}
+
+pub fn spawn_console(mut commands: Commands) {
+    let mut scopes = CommandScopes::default();
+    scopes.add("admin");
+
+    commands.spawn((Console, scopes));
+}
+
+/// Executes lines typed into the console through the command graph.
+pub fn read_console(
+    input: Res<ConsoleInput>,
+    console: Query<Entity, With<Console>>,
+    mut events: EventWriter<CommandExecutionEvent>,
+) {
+    let Ok(executor) = console.get_single() else {
+        return;
+    };
+
+    for line in input.0.try_iter() {
+        let command = line.trim().trim_start_matches('/');
+        if command.is_empty() {
+            continue;
+        }
+
+        events.send(CommandExecutionEvent {
+            command: command.to_owned(),
+            executor,
+        });
+    }
+}
+
+pub fn print_feedback(
+    mut events: EventReader<CommandFeedback>,
+    console: Query<(), With<Console>>,
+    output: Res<ConsoleOutput>,
+) {
+    for event in events.read() {
+        if console.contains(event.executor) {
+            output.print(plain_text(&event.message));
+        }
+    }
+}
@@ -0,0 +1,333 @@
+use std::collections::HashSet;
+
+use valence::{
+    block::{PropName, PropValue},
+    inventory::{ClientInventoryState, OpenInventory},
+    layer::chunk::Block,
+    nbt::{Compound, Value},
+    prelude::*,
+    protocol::{packets::play::ScreenHandlerPropertyUpdateS2c, WritePacket},
+    ItemKind, ItemStack,
+};
+
+use super::{spawn_container, store::ContainerStore, Container, ContainerKind};
+use crate::crafting::recipes::Recipes;
+
+const INPUT_SLOT: u16 = 0;
+const FUEL_SLOT: u16 = 1;
+const OUTPUT_SLOT: u16 = 2;
+/// Ticks smelting takes for furnaces saved without it and for the built-in
+/// recipes
+const COOK_TICKS: i16 = 200;
+
+/// What smelting `item` gives when no data pack recipe covers it, so furnaces
+/// still work without one
+fn smelting_result(item: ItemKind) -> Option<ItemKind> {
+    let result = match item {
+        ItemKind::IronOre | ItemKind::DeepslateIronOre | ItemKind::RawIron => ItemKind::IronIngot,
+        ItemKind::GoldOre | ItemKind::DeepslateGoldOre | ItemKind::RawGold => ItemKind::GoldIngot,
+        ItemKind::CopperOre | ItemKind::DeepslateCopperOre | ItemKind::RawCopper => {
+            ItemKind::CopperIngot
+        }
+        ItemKind::Sand | ItemKind::RedSand => ItemKind::Glass,
+        ItemKind::Cobblestone => ItemKind::Stone,
+        ItemKind::Stone => ItemKind::SmoothStone,
+        ItemKind::ClayBall => ItemKind::Brick,
+        ItemKind::Beef => ItemKind::CookedBeef,
+        ItemKind::Porkchop => ItemKind::CookedPorkchop,
+        ItemKind::Chicken => ItemKind::CookedChicken,
+        ItemKind::Mutton => ItemKind::CookedMutton,
+        ItemKind::Rabbit => ItemKind::CookedRabbit,
+        ItemKind::Cod => ItemKind::CookedCod,
+        ItemKind::Salmon => ItemKind::CookedSalmon,
+        ItemKind::Potato => ItemKind::BakedPotato,
+        ItemKind::OakLog
+        | ItemKind::SpruceLog
+        | ItemKind::BirchLog
+        | ItemKind::JungleLog
+        | ItemKind::AcaciaLog
+        | ItemKind::DarkOakLog
+        | ItemKind::MangroveLog
+        | ItemKind::CherryLog => ItemKind::Charcoal,
+        _ => return None,
+    };
+
+    Some(result)
+}
+
+/// How many ticks `item` burns for as fuel
+fn fuel_ticks(item: ItemKind) -> Option<i16> {
+    let ticks = match item {
+        ItemKind::LavaBucket => 20000,
+        ItemKind::CoalBlock => 16000,
+        ItemKind::BlazeRod => 2400,
+        ItemKind::Coal | ItemKind::Charcoal => 1600,
+        ItemKind::OakLog
+        | ItemKind::SpruceLog
+        | ItemKind::BirchLog
+        | ItemKind::JungleLog
+        | ItemKind::AcaciaLog
+        | ItemKind::DarkOakLog
+        | ItemKind::MangroveLog
+        | ItemKind::CherryLog
+        | ItemKind::OakPlanks
+        | ItemKind::SprucePlanks
+        | ItemKind::BirchPlanks
+        | ItemKind::JunglePlanks
+        | ItemKind::AcaciaPlanks
+        | ItemKind::DarkOakPlanks
+        | ItemKind::MangrovePlanks
+        | ItemKind::CherryPlanks
+        | ItemKind::CraftingTable
+        | ItemKind::Chest
+        | ItemKind::Barrel => 300,
+        ItemKind::WoodenSword
+        | ItemKind::WoodenPickaxe
+        | ItemKind::WoodenAxe
+        | ItemKind::WoodenShovel
+        | ItemKind::WoodenHoe => 200,
+        ItemKind::Stick => 100,
+        _ => return None,
+    };
+
+    Some(ticks)
+}
+
+/// Burn and cook progress of a furnace, saved with its items.
+#[derive(Component, Debug, Default)]
+pub struct Furnace {
+    /// Ticks left on the fuel being burned
+    burn_time: i16,
+    /// Ticks the current fuel burns for in total, for the flame in the window
+    burn_total: i16,
+    cook_time: i16,
+    /// Ticks the item being smelted takes, from its recipe
+    cook_total: i16,
+}
+
+impl Furnace {
+    pub fn from_nbt(nbt: &Compound) -> Self {
+        let short = |key| match nbt.get(key) {
+            Some(Value::Short(value)) => *value,
+            _ => 0,
+        };
+
+        Self {
+            burn_time: short("BurnTime"),
+            // Vanilla doesn't save this and works it out from the fuel left
+            burn_total: short("BurnTime"),
+            cook_time: short("CookTime"),
+            cook_total: match short("CookTimeTotal") {
+                0 => COOK_TICKS,
+                ticks => ticks,
+            },
+        }
+    }
+
+    pub fn write_nbt(&self, nbt: &mut Compound) {
+        nbt.insert("BurnTime", Value::Short(self.burn_time));
+        nbt.insert("CookTime", Value::Short(self.cook_time));
+        nbt.insert("CookTimeTotal", Value::Short(self.cook_total));
+    }
+}
+
+/// Whether `result` can be added to what's in the output slot
+fn fits_output(output: &ItemStack, result: ItemKind) -> bool {
+    output.is_empty()
+        || (output.item == result && output.nbt.is_none() && output.count < result.max_stack())
+}
+
+/// Gives furnaces the store put back into a loaded chunk their inventory
+/// entity, so they carry on smelting without anyone opening them.
+///
+/// Furnaces the server has never changed, such as ones in an Anvil world
+/// that haven't been opened yet, wait until they are opened.
+pub fn load_furnaces(
+    mut commands: Commands,
+    mut store: ResMut<ContainerStore>,
+    layers: Query<(Entity, &ChunkLayer)>,
+    containers: Query<&Container>,
+) {
+    // A chunk loaded twice in one tick lists its furnaces twice
+    let restored: HashSet<_> = store.take_restored_furnaces().into_iter().collect();
+    for pos in restored {
+        for (layer_entity, layer) in &layers {
+            let Some(block) = layer.block(pos) else {
+                continue;
+            };
+            if ContainerKind::of(block.state.to_kind()) != Some(ContainerKind::Furnace) {
+                continue;
+            }
+            let open = containers
+                .iter()
+                .any(|container| container.layer == layer_entity && container.pos == pos);
+            if open {
+                continue;
+            }
+
+            let nbt = block.nbt.cloned().unwrap_or_default();
+            spawn_container(&mut commands, ContainerKind::Furnace, layer_entity, pos, &nbt);
+        }
+    }
+}
+
+/// Burns fuel and smelts items in furnaces, lighting and putting out the
+/// block as fuel runs out, and keeps the flame and arrow in open windows
+/// up to date.
+pub fn tick_furnaces(
+    mut furnaces: Query<(Entity, &Container, &mut Furnace, &mut Inventory)>,
+    mut viewers: Query<(&mut Client, &OpenInventory, &ClientInventoryState)>,
+    mut layers: Query<&mut ChunkLayer>,
+    recipes: Res<Recipes>,
+    mut store: ResMut<ContainerStore>,
+) {
+    for (entity, container, mut furnace, mut inventory) in &mut furnaces {
+        let was_lit = furnace.burn_time > 0;
+        if furnace.burn_time > 0 {
+            furnace.burn_time -= 1;
+        }
+
+        let input = inventory.slot(INPUT_SLOT).clone();
+        let recipe = recipes
+            .smelting(input.item)
+            .or_else(|| smelting_result(input.item).map(|result| (result, COOK_TICKS)))
+            .filter(|(result, _)| {
+                !input.is_empty() && fits_output(inventory.slot(OUTPUT_SLOT), *result)
+            });
+
+        if let Some((result, cook_total)) = recipe {
+            furnace.cook_total = cook_total;
+
+            if furnace.burn_time == 0 {
+                let fuel = inventory.slot(FUEL_SLOT).clone();
+
+                if let Some(ticks) = fuel_ticks(fuel.item).filter(|_| !fuel.is_empty()) {
+                    furnace.burn_time = ticks;
+                    furnace.burn_total = ticks;
+
+                    // Lava buckets leave their bucket behind
+                    let left = if fuel.item == ItemKind::LavaBucket {
+                        ItemStack::new(ItemKind::Bucket, 1, None)
+                    } else if fuel.count > 1 {
+                        ItemStack::new(fuel.item, fuel.count - 1, fuel.nbt)
+                    } else {
+                        ItemStack::EMPTY
+                    };
+                    inventory.set_slot(FUEL_SLOT, left);
+                }
+            }
+
+            if furnace.burn_time > 0 {
+                furnace.cook_time += 1;
+
+                if furnace.cook_time >= furnace.cook_total {
+                    furnace.cook_time = 0;
+
+                    let output = inventory.slot(OUTPUT_SLOT);
+                    let smelted = if output.is_empty() {
+                        ItemStack::new(result, 1, None)
+                    } else {
+                        ItemStack::new(result, output.count + 1, None)
+                    };
+                    inventory.set_slot(OUTPUT_SLOT, smelted);
+
+                    if input.count > 1 {
+                        inventory.set_slot_amount(INPUT_SLOT, input.count - 1);
+                    } else {
+                        inventory.set_slot(INPUT_SLOT, ItemStack::EMPTY);
+                    }
+                }
+            }
+        }
+
+        // Progress winds back when there's nothing to smelt or no fuel
+        if (recipe.is_none() || furnace.burn_time == 0) && furnace.cook_time > 0 {
+            furnace.cook_time = (furnace.cook_time - 2).max(0);
+        }
+
+        let lit = furnace.burn_time > 0;
+        if lit != was_lit {
+            if let Ok(mut layer) = layers.get_mut(container.layer) {
+                if let Some(block) = layer.block(container.pos) {
+                    let value = if lit { PropValue::True } else { PropValue::False };
+                    let state = block.state.set(PropName::Lit, value);
+                    let nbt = block.nbt.cloned();
+                    store.record(container.pos, state, nbt.as_ref());
+                    layer.set_block(container.pos, Block::new(state, nbt));
+                }
+            }
+        }
+
+        let properties = [
+            furnace.burn_time,
+            furnace.burn_total,
+            furnace.cook_time,
+            furnace.cook_total,
+        ];
+        for (mut client, open, state) in &mut viewers {
+            if open.entity != entity {
+                continue;
+            }
+
+            for (property, value) in properties.into_iter().enumerate() {
+                client.write_packet(&ScreenHandlerPropertyUpdateS2c {
+                    window_id: state.window_id(),
+                    property: property as i16,
+                    value,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ores_and_food_smelt_without_a_data_pack() {
+        assert_eq!(
+            smelting_result(ItemKind::RawIron),
+            Some(ItemKind::IronIngot)
+        );
+        assert_eq!(
+            smelting_result(ItemKind::DeepslateGoldOre),
+            Some(ItemKind::GoldIngot)
+        );
+        assert_eq!(smelting_result(ItemKind::Beef), Some(ItemKind::CookedBeef));
+        assert_eq!(
+            smelting_result(ItemKind::BirchLog),
+            Some(ItemKind::Charcoal)
+        );
+    }
+
+    #[test]
+    fn smelting_results_can_be_smelted_again() {
+        let stone = smelting_result(ItemKind::Cobblestone);
+        assert_eq!(stone, Some(ItemKind::Stone));
+        assert_eq!(stone.and_then(smelting_result), Some(ItemKind::SmoothStone));
+    }
+
+    #[test]
+    fn some_items_dont_smelt() {
+        assert_eq!(smelting_result(ItemKind::IronIngot), None);
+        assert_eq!(smelting_result(ItemKind::Diamond), None);
+        assert_eq!(smelting_result(ItemKind::Air), None);
+    }
+
+    #[test]
+    fn fuel_burns_for_whole_items() {
+        assert_eq!(fuel_ticks(ItemKind::Coal), Some(8 * COOK_TICKS));
+        assert_eq!(fuel_ticks(ItemKind::CoalBlock), Some(80 * COOK_TICKS));
+        assert_eq!(fuel_ticks(ItemKind::LavaBucket), Some(100 * COOK_TICKS));
+        assert_eq!(fuel_ticks(ItemKind::OakPlanks), Some(300));
+        assert_eq!(fuel_ticks(ItemKind::Stick), Some(COOK_TICKS / 2));
+    }
+
+    #[test]
+    fn only_burnable_items_are_fuel() {
+        assert_eq!(fuel_ticks(ItemKind::Stone), None);
+        assert_eq!(fuel_ticks(ItemKind::Bucket), None);
+        assert_eq!(fuel_ticks(ItemKind::Air), None);
+    }
+}
@@ -0,0 +1,340 @@
+pub mod furnace;
+pub mod store;
+
+use furnace::Furnace;
+use store::ContainerStore;
+use valence::{
+    block::BlockKind,
+    entity::entity::Flags,
+    interact_block::InteractBlockEvent,
+    inventory::{InventoryKind, OpenInventory},
+    layer::chunk::Block,
+    nbt::{Compound, List, Value},
+    op_level::OpLevel,
+    prelude::*,
+    sound::{Sound, SoundCategory},
+    ItemKind, ItemStack,
+};
+
+use crate::{
+    anticheat, hud,
+    interacting::{
+        self,
+        items::{drop_item, PICKUP_DELAY},
+    },
+    setup::settings::Settings,
+    world::spawn::WorldSpawn,
+};
+
+/// The blocks with an inventory that players can open.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContainerKind {
+    Chest,
+    Barrel,
+    Furnace,
+}
+
+impl ContainerKind {
+    pub fn of(block: BlockKind) -> Option<Self> {
+        match block {
+            BlockKind::Chest | BlockKind::TrappedChest => Some(ContainerKind::Chest),
+            BlockKind::Barrel => Some(ContainerKind::Barrel),
+            BlockKind::Furnace => Some(ContainerKind::Furnace),
+            _ => None,
+        }
+    }
+
+    fn inventory_kind(self) -> InventoryKind {
+        match self {
+            ContainerKind::Chest | ContainerKind::Barrel => InventoryKind::Generic9x3,
+            ContainerKind::Furnace => InventoryKind::Furnace,
+        }
+    }
+
+    /// The window title, translated by the client
+    fn title(self) -> Text {
+        let key = match self {
+            ContainerKind::Chest => "container.chest",
+            ContainerKind::Barrel => "container.barrel",
+            ContainerKind::Furnace => "container.furnace",
+        };
+
+        Text::translate(key, [])
+    }
+
+    fn open_sound(self) -> Option<Sound> {
+        match self {
+            ContainerKind::Chest => Some(Sound::BlockChestOpen),
+            ContainerKind::Barrel => Some(Sound::BlockBarrelOpen),
+            ContainerKind::Furnace => None,
+        }
+    }
+}
+
+/// The open inventory of a container block.
+///
+/// The entity holding this and the `Inventory` is spawned the first time the
+/// block is opened, or for furnaces when their chunk loads so they keep
+/// smelting, and lives as long as the block and its chunk. The chunk's
+/// block entity NBT, in the same format as Anvil files, is what the contents
+/// are kept in; it's rewritten whenever the inventory changes, and saved to
+/// the `ContainerStore` so it outlasts the chunk.
+#[derive(Component, Debug)]
+pub struct Container {
+    pub kind: ContainerKind,
+    pub layer: Entity,
+    pub pos: BlockPos,
+}
+
+/// Reads an `Items` list of block entity NBT into an inventory
+fn read_items(nbt: &Compound, inventory: &mut Inventory) {
+    let Some(Value::List(List::Compound(items))) = nbt.get("Items") else {
+        return;
+    };
+
+    for item in items {
+        let Some(Value::Byte(slot)) = item.get("Slot") else {
+            continue;
+        };
+        if let Some(stack) = stack_from_nbt(item) {
+            let slot = *slot as u16;
+            if slot < inventory.slot_count() {
+                inventory.set_slot(slot, stack);
+            }
+        }
+    }
+}
+
+/// Reads an item stack saved as `{id:"minecraft:stone",Count:1b,tag:{..}}`
+pub fn stack_from_nbt(item: &Compound) -> Option<ItemStack> {
+    let Some(Value::String(id)) = item.get("id") else {
+        return None;
+    };
+    let Some(Value::Byte(count)) = item.get("Count") else {
+        return None;
+    };
+
+    let kind = ItemKind::from_str(id.strip_prefix("minecraft:").unwrap_or(id))?;
+    let tag = match item.get("tag") {
+        Some(Value::Compound(tag)) => Some(tag.clone()),
+        _ => None,
+    };
+
+    Some(ItemStack::new(kind, *count, tag))
+}
+
+/// Writes the non-empty slots of an inventory as an `Items` list
+fn write_items(inventory: &Inventory) -> List {
+    let items = (0..inventory.slot_count())
+        .filter(|slot| !inventory.slot(*slot).is_empty())
+        .map(|slot| {
+            let stack = inventory.slot(slot);
+            let mut item = Compound::new();
+            item.insert("Slot", Value::Byte(slot as i8));
+            item.insert("id", Value::String(format!("minecraft:{}", stack.item.to_str())));
+            item.insert("Count", Value::Byte(stack.count));
+            if let Some(tag) = &stack.nbt {
+                item.insert("tag", Value::Compound(tag.clone()));
+            }
+            item
+        })
+        .collect();
+
+    List::Compound(items)
+}
+
+/// The inventories of opened containers
+pub type ContainerInventories<'w, 's> =
+    Query<'w, 's, (&'static Container, &'static mut Inventory), Without<Client>>;
+
+/// Drops the contents of a container block destroyed at `pos` around it.
+///
+/// An opened container's inventory is used when there is one, since the
+/// block's NBT is only brought up to date with it later in the tick. It's
+/// emptied so nothing is left behind in it. Otherwise the items come from
+/// the NBT the block had.
+pub fn drop_contents(
+    commands: &mut Commands,
+    containers: &mut ContainerInventories,
+    layer: Entity,
+    pos: BlockPos,
+    nbt: Option<&Compound>,
+) {
+    let open = containers
+        .iter_mut()
+        .find(|(container, _)| container.layer == layer && container.pos == pos);
+
+    let stacks: Vec<ItemStack> = match (open, nbt) {
+        (Some((_, mut inventory)), _) => (0..inventory.slot_count())
+            .map(|slot| inventory.replace_slot(slot, ItemStack::EMPTY))
+            .filter(|stack| !stack.is_empty())
+            .collect(),
+        (None, Some(nbt)) => match nbt.get("Items") {
+            Some(Value::List(List::Compound(items))) => {
+                items.iter().filter_map(stack_from_nbt).collect()
+            }
+            _ => return,
+        },
+        (None, None) => return,
+    };
+
+    let centre = DVec3::new(
+        f64::from(pos.x) + 0.5,
+        f64::from(pos.y) + 0.5,
+        f64::from(pos.z) + 0.5,
+    );
+    for stack in stacks {
+        drop_item(commands, layer, centre, stack, PICKUP_DELAY);
+    }
+}
+
+/// Spawns the open inventory of a container block from its block entity NBT
+pub fn spawn_container(
+    commands: &mut Commands,
+    kind: ContainerKind,
+    layer: Entity,
+    pos: BlockPos,
+    nbt: &Compound,
+) -> Entity {
+    let title = match nbt.get("CustomName") {
+        Some(Value::String(name)) => hud::parse_text(name),
+        _ => kind.title(),
+    };
+
+    let mut inventory = Inventory::with_title(kind.inventory_kind(), title);
+    read_items(nbt, &mut inventory);
+
+    let mut entity = commands.spawn((inventory, Container { kind, layer, pos }));
+    if kind == ContainerKind::Furnace {
+        entity.insert(Furnace::from_nbt(nbt));
+    }
+    entity.id()
+}
+
+/// Opens the window of chests, barrels and furnaces players right click,
+/// unless they're sneaking to place a block against it.
+///
+/// Containers inside spawn protection or out of reach stay shut, the same as
+/// breaking or building there.
+pub fn open_containers(
+    mut commands: Commands,
+    mut events: EventReader<InteractBlockEvent>,
+    players: Query<(&GameMode, &Flags, &EntityLayerId, &OpLevel, &Position)>,
+    containers: Query<(Entity, &Container)>,
+    mut layers: Query<(&mut ChunkLayer, Option<&WorldSpawn>)>,
+    settings: Res<Settings>,
+) {
+    for event in events.read() {
+        if event.hand != Hand::Main {
+            continue;
+        }
+        let Ok((game_mode, flags, layer_id, op_level, pos)) = players.get(event.client) else {
+            continue;
+        };
+        if *game_mode == GameMode::Spectator || flags.sneaking() {
+            continue;
+        }
+
+        let layer_entity = layer_id.0;
+        let Ok((mut layer, spawn)) = layers.get_mut(layer_entity) else {
+            continue;
+        };
+        // Placing reports clicks out of reach, so they're only refused here
+        if interacting::is_protected(spawn, op_level, event.position, &settings)
+            || anticheat::beyond_reach(&settings, pos.0, event.position).is_some()
+        {
+            continue;
+        }
+        let Some(block) = layer.block(event.position) else {
+            continue;
+        };
+        let Some(kind) = ContainerKind::of(block.state.to_kind()) else {
+            continue;
+        };
+
+        let existing = containers
+            .iter()
+            .find(|(_, container)| {
+                container.layer == layer_entity && container.pos == event.position
+            })
+            .map(|(entity, _)| entity);
+
+        let container = match existing {
+            Some(container) => container,
+            None => {
+                let nbt = block.nbt.cloned().unwrap_or_default();
+                spawn_container(&mut commands, kind, layer_entity, event.position, &nbt)
+            }
+        };
+
+        commands
+            .entity(event.client)
+            .insert(OpenInventory::new(container));
+
+        if let Some(sound) = kind.open_sound() {
+            let centre = DVec3::new(
+                f64::from(event.position.x) + 0.5,
+                f64::from(event.position.y) + 0.5,
+                f64::from(event.position.z) + 0.5,
+            );
+            layer.play_sound(sound, SoundCategory::Block, centre, 0.5, 1.0);
+        }
+    }
+}
+
+/// Keeps each container's block entity NBT in the chunk and the store up to
+/// date with its inventory, keeping any other tags like `CustomName`.
+pub fn save_contents(
+    containers: Query<(&Container, &Inventory, Option<&Furnace>), Changed<Inventory>>,
+    mut layers: Query<&mut ChunkLayer>,
+    mut store: ResMut<ContainerStore>,
+) {
+    for (container, inventory, furnace) in &containers {
+        let Ok(mut layer) = layers.get_mut(container.layer) else {
+            continue;
+        };
+        let Some(block) = layer.block(container.pos) else {
+            continue;
+        };
+        if ContainerKind::of(block.state.to_kind()) != Some(container.kind) {
+            continue;
+        }
+
+        let state = block.state;
+        let mut nbt = block.nbt.cloned().unwrap_or_default();
+        nbt.insert("Items", Value::List(write_items(inventory)));
+        if let Some(furnace) = furnace {
+            furnace.write_nbt(&mut nbt);
+        }
+
+        store.record(container.pos, state, Some(&nbt));
+        layer.set_block(container.pos, Block::new(state, Some(nbt)));
+    }
+}
+
+/// Closes and removes containers whose block was broken or whose chunk was
+/// unloaded. Their contents were already dropped or are kept in the store.
+pub fn remove_stale_containers(
+    mut commands: Commands,
+    containers: Query<(Entity, &Container)>,
+    viewers: Query<(Entity, &OpenInventory)>,
+    layers: Query<&ChunkLayer>,
+) {
+    for (entity, container) in &containers {
+        let intact = layers.get(container.layer).is_ok_and(|layer| {
+            layer.block(container.pos).is_some_and(|block| {
+                ContainerKind::of(block.state.to_kind()) == Some(container.kind)
+            })
+        });
+        if intact {
+            continue;
+        }
+
+        for (viewer, open) in &viewers {
+            if open.entity == entity {
+                commands.entity(viewer).remove::<OpenInventory>();
+            }
+        }
+        commands.entity(entity).insert(Despawned);
+    }
+}
@@ -0,0 +1,257 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+};
+
+use serde::{Deserialize, Serialize};
+use valence::{block::BlockKind, layer::chunk::Block, log::warn, nbt::Compound, prelude::*};
+
+use crate::{
+    commands::snbt, server::shutdown::ShutdownEvent, setup::settings::Settings, world::storage,
+};
+
+/// A container block as it was last seen, or air once it's been broken
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct SavedBlock {
+    x: i32,
+    y: i32,
+    z: i32,
+    /// The raw block state id
+    state: u16,
+    /// The block entity NBT, written as SNBT
+    nbt: Option<String>,
+}
+
+/// How often changed chunks are written to disk
+const SAVE_INTERVAL_TICKS: u32 = 20 * 30;
+
+/// Container blocks and their contents, kept per chunk in the world's data
+/// directory as `containers/<x>.<z>.json`.
+///
+/// Chunks nobody is viewing are dropped from memory, and generated ones are
+/// made again from scratch when they come back, so the NBT in the chunk alone
+/// doesn't last. Every change to a container block is recorded here too and
+/// put back whenever its chunk is loaded.
+///
+/// Changes are kept in memory and written out every so often, when their
+/// chunk unloads and when the server stops, since containers can change every
+/// tick.
+#[derive(Resource)]
+pub struct ContainerStore {
+    dir: PathBuf,
+    /// The saved blocks of every loaded chunk that's been read or changed
+    chunks: HashMap<ChunkPos, Vec<SavedBlock>>,
+    /// Chunks changed since they were last written
+    dirty: HashSet<ChunkPos>,
+    /// Furnaces put back since `take_restored_furnaces` was last called
+    restored_furnaces: Vec<BlockPos>,
+}
+
+pub fn load_container_store(mut commands: Commands, settings: Res<Settings>) {
+    commands.insert_resource(ContainerStore::new(
+        storage::data_dir(&settings).join("containers"),
+    ));
+}
+
+/// Writes changed chunks out every so often and when the server stops, and
+/// forgets the ones that have been unloaded.
+pub fn save_container_store(
+    mut store: ResMut<ContainerStore>,
+    layers: Query<&ChunkLayer>,
+    mut shutdown: EventReader<ShutdownEvent>,
+    mut ticks: Local<u32>,
+) {
+    *ticks += 1;
+
+    let unloaded: Vec<_> = store
+        .chunks
+        .keys()
+        .filter(|&&chunk| !layers.iter().any(|layer| layer.chunk(chunk).is_some()))
+        .copied()
+        .collect();
+    for chunk in unloaded {
+        store.unload(chunk);
+    }
+
+    let stopping = shutdown.read().next().is_some();
+    if stopping || *ticks % SAVE_INTERVAL_TICKS == 0 {
+        store.flush();
+    }
+}
+
+impl ContainerStore {
+    fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            chunks: HashMap::new(),
+            dirty: HashSet::new(),
+            restored_furnaces: Vec::new(),
+        }
+    }
+
+    fn path(&self, chunk: ChunkPos) -> PathBuf {
+        self.dir.join(format!("{}.{}.json", chunk.x, chunk.z))
+    }
+
+    /// The saved blocks of a chunk, read from disk the first time
+    fn blocks(&mut self, chunk: ChunkPos) -> &mut Vec<SavedBlock> {
+        let path = self.path(chunk);
+        self.chunks
+            .entry(chunk)
+            .or_insert_with(|| storage::load(&path))
+    }
+
+    /// Saves the block now at `pos`, replacing whatever was saved there
+    pub fn record(&mut self, pos: BlockPos, state: BlockState, nbt: Option<&Compound>) {
+        let chunk = ChunkPos::new(pos.x.div_euclid(16), pos.z.div_euclid(16));
+
+        let blocks = self.blocks(chunk);
+        blocks.retain(|block| (block.x, block.y, block.z) != (pos.x, pos.y, pos.z));
+        blocks.push(SavedBlock {
+            x: pos.x,
+            y: pos.y,
+            z: pos.z,
+            state: state.to_raw(),
+            nbt: nbt.map(snbt::to_snbt),
+        });
+
+        self.dirty.insert(chunk);
+    }
+
+    /// Puts the saved container blocks of a chunk that was just loaded back
+    /// into the layer
+    pub fn restore(&mut self, layer: &mut ChunkLayer, chunk: ChunkPos) {
+        let mut furnaces = Vec::new();
+        for block in self.blocks(chunk).iter() {
+            let pos = BlockPos::new(block.x, block.y, block.z);
+            let Some(state) = BlockState::from_raw(block.state) else {
+                warn!("unknown block state {} saved at {pos:?}", block.state);
+                continue;
+            };
+            let nbt = match block.nbt.as_deref().map(snbt::parse_compound) {
+                Some(Ok(nbt)) => Some(nbt),
+                Some(Err(e)) => {
+                    warn!("unreadable container NBT saved at {pos:?}: {e:?}");
+                    continue;
+                }
+                None => None,
+            };
+
+            if state.to_kind() == BlockKind::Furnace {
+                furnaces.push(pos);
+            }
+            layer.set_block(pos, Block::new(state, nbt));
+        }
+        self.restored_furnaces.extend(furnaces);
+    }
+
+    /// The furnaces put back into layers since this was last called
+    pub fn take_restored_furnaces(&mut self) -> Vec<BlockPos> {
+        std::mem::take(&mut self.restored_furnaces)
+    }
+
+    /// Writes a chunk out if it changed and drops it from memory
+    fn unload(&mut self, chunk: ChunkPos) {
+        if self.dirty.remove(&chunk) {
+            self.write(chunk);
+        }
+        self.chunks.remove(&chunk);
+    }
+
+    /// Writes out every chunk changed since it was last written
+    pub fn flush(&mut self) {
+        for chunk in std::mem::take(&mut self.dirty) {
+            self.write(chunk);
+        }
+    }
+
+    fn write(&self, chunk: ChunkPos) {
+        if let Some(blocks) = self.chunks.get(&chunk) {
+            storage::save(&self.path(chunk), blocks);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use valence::nbt::{compound, List};
+
+    use super::*;
+
+    /// A fresh store whose chunks are kept in their own directory
+    fn test_store(name: &str) -> ContainerStore {
+        let dir = std::env::temp_dir().join(format!("containers-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        ContainerStore::new(dir)
+    }
+
+    /// What's saved at `pos`, as read back from disk by a new store
+    fn saved_at(store: &ContainerStore, pos: BlockPos) -> Vec<(BlockState, Option<Compound>)> {
+        let mut reopened = ContainerStore::new(store.dir.clone());
+        let chunk = ChunkPos::new(pos.x.div_euclid(16), pos.z.div_euclid(16));
+
+        reopened
+            .blocks(chunk)
+            .iter()
+            .filter(|block| (block.x, block.y, block.z) == (pos.x, pos.y, pos.z))
+            .map(|block| {
+                let nbt = block
+                    .nbt
+                    .as_deref()
+                    .map(|nbt| snbt::parse_compound(nbt).unwrap());
+                (BlockState::from_raw(block.state).unwrap(), nbt)
+            })
+            .collect()
+    }
+
+    fn chest_nbt() -> Compound {
+        compound! {
+            "Items" => List::Compound(vec![compound! {
+                "Slot" => 0_i8,
+                "id" => "minecraft:diamond",
+                "Count" => 3_i8,
+            }]),
+        }
+    }
+
+    #[test]
+    fn recorded_blocks_load_back_after_a_flush() {
+        let mut store = test_store("round_trip");
+        let pos = BlockPos::new(-3, 64, 20);
+        store.record(pos, BlockState::CHEST, Some(&chest_nbt()));
+
+        assert!(saved_at(&store, pos).is_empty());
+
+        store.flush();
+        assert_eq!(
+            saved_at(&store, pos),
+            vec![(BlockState::CHEST, Some(chest_nbt()))]
+        );
+    }
+
+    #[test]
+    fn recording_replaces_the_saved_block() {
+        let mut store = test_store("replace");
+        let pos = BlockPos::new(5, 70, 5);
+        store.record(pos, BlockState::CHEST, Some(&chest_nbt()));
+        store.record(pos, BlockState::AIR, None);
+        store.flush();
+
+        assert_eq!(saved_at(&store, pos), vec![(BlockState::AIR, None)]);
+    }
+
+    #[test]
+    fn unloading_writes_changed_chunks() {
+        let mut store = test_store("unload");
+        let pos = BlockPos::new(40, 64, -1);
+        store.record(pos, BlockState::FURNACE, None);
+
+        store.unload(ChunkPos::new(2, -1));
+
+        assert!(store.chunks.is_empty());
+        assert!(store.dirty.is_empty());
+        assert_eq!(saved_at(&store, pos), vec![(BlockState::FURNACE, None)]);
+    }
+}
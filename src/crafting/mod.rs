@@ -0,0 +1,319 @@
+pub mod recipe_book;
+pub mod recipes;
+
+use std::ops::Range;
+
+use recipes::Recipes;
+use valence::{
+    block::BlockKind,
+    entity::entity::Flags,
+    event_loop::PacketEvent,
+    interact_block::InteractBlockEvent,
+    inventory::{CursorItem, InventoryKind, OpenInventory},
+    prelude::*,
+    protocol::packets::play::{click_slot_c2s::ClickMode, ClickSlotC2s, CloseHandledScreenC2s},
+    ItemKind, ItemStack,
+};
+
+use crate::interacting::items::{drop_item, give_item, has_room, PICKUP_DELAY};
+
+/// Where the crafted item shows, in both the player's inventory and crafting
+/// tables
+const RESULT_SLOT: u16 = 0;
+/// Shift clicking the result crafts at most this many times
+const MAX_SHIFT_CRAFTS: usize = 64;
+
+/// The grid of a crafting table window a player has open.
+///
+/// Each player gets a grid of their own that only lasts while the window is
+/// open. Whatever is left in it goes back to them when they close it, or is
+/// dropped on the table if they've left.
+#[derive(Component, Debug)]
+pub struct CraftingTable {
+    viewer: Entity,
+    layer: Entity,
+    pos: BlockPos,
+}
+
+/// The grid slots of a crafting inventory and how wide the grid is
+fn grid_slots(inventory: &Inventory) -> (Range<u16>, usize) {
+    match inventory.kind() {
+        InventoryKind::Player => (1..5, 2),
+        _ => (1..10, 3),
+    }
+}
+
+/// What the grid in `inventory` currently makes
+fn crafting_result(recipes: &Recipes, inventory: &Inventory) -> ItemStack {
+    let (slots, width) = grid_slots(inventory);
+    let grid: Vec<ItemStack> = slots.map(|slot| inventory.slot(slot).clone()).collect();
+
+    recipes.crafting(&grid, width).unwrap_or(ItemStack::EMPTY)
+}
+
+/// What's left in the grid after crafting with `item`
+fn remainder(item: ItemKind) -> ItemStack {
+    match item {
+        ItemKind::MilkBucket | ItemKind::WaterBucket | ItemKind::LavaBucket => {
+            ItemStack::new(ItemKind::Bucket, 1, None)
+        }
+        ItemKind::HoneyBottle => ItemStack::new(ItemKind::GlassBottle, 1, None),
+        _ => ItemStack::EMPTY,
+    }
+}
+
+/// Uses up one item from each filled grid slot
+fn consume_grid(inventory: &mut Inventory) {
+    let (slots, _) = grid_slots(inventory);
+
+    for slot in slots {
+        let stack = inventory.slot(slot);
+        if stack.is_empty() {
+            continue;
+        }
+
+        if stack.count > 1 {
+            let count = stack.count - 1;
+            inventory.set_slot_amount(slot, count);
+        } else {
+            let left = remainder(stack.item);
+            inventory.set_slot(slot, left);
+        }
+    }
+}
+
+/// Whether `result` can be picked up onto what the cursor is holding
+fn fits_cursor(cursor: &ItemStack, result: &ItemStack) -> bool {
+    cursor.is_empty()
+        || (cursor.item == result.item
+            && cursor.nbt == result.nbt
+            && cursor.count + result.count <= result.item.max_stack())
+}
+
+/// Shows what the crafting grids of player inventories and crafting tables
+/// make in their result slot.
+pub fn update_results(
+    recipes: Res<Recipes>,
+    mut inventories: Query<
+        &mut Inventory,
+        (Or<(With<Client>, With<CraftingTable>)>, Changed<Inventory>),
+    >,
+) {
+    for mut inventory in &mut inventories {
+        let result = crafting_result(&recipes, &inventory);
+        if *inventory.slot(RESULT_SLOT) != result {
+            inventory.set_slot(RESULT_SLOT, result);
+        }
+    }
+}
+
+/// Crafts when players take from the result slot.
+///
+/// Valence turns these clicks down since items appear out of nowhere, so the
+/// packets are handled here instead. Clicking puts one craft on the cursor and
+/// shift clicking crafts as many as fit in the inventory.
+pub fn take_results(
+    mut commands: Commands,
+    mut packets: EventReader<PacketEvent>,
+    recipes: Res<Recipes>,
+    mut players: Query<
+        (
+            &mut Inventory,
+            &mut CursorItem,
+            &Position,
+            &EntityLayerId,
+            Option<&OpenInventory>,
+        ),
+        With<Client>,
+    >,
+    mut tables: Query<&mut Inventory, (With<CraftingTable>, Without<Client>)>,
+) {
+    for packet in packets.read() {
+        let Some(click) = packet.decode::<ClickSlotC2s>() else {
+            continue;
+        };
+        if click.slot_idx != RESULT_SLOT as i16 {
+            continue;
+        }
+        let shift = match click.mode {
+            ClickMode::Click => false,
+            ClickMode::ShiftClick => true,
+            _ => continue,
+        };
+
+        let Ok((mut inventory, mut cursor, pos, layer, open)) = players.get_mut(packet.client)
+        else {
+            continue;
+        };
+
+        // Either the 2x2 grid of the player's own inventory or a table's
+        let mut table = match open {
+            Some(open) if click.window_id != 0 => match tables.get_mut(open.entity) {
+                Ok(table) => Some(table),
+                Err(_) => continue,
+            },
+            None if click.window_id == 0 => None,
+            _ => continue,
+        };
+
+        let times = if shift { MAX_SHIFT_CRAFTS } else { 1 };
+        for _ in 0..times {
+            let result = match &table {
+                Some(table) => crafting_result(&recipes, table),
+                None => crafting_result(&recipes, &inventory),
+            };
+            if result.is_empty() {
+                break;
+            }
+
+            let fits = if shift {
+                has_room(&inventory, &result)
+            } else {
+                fits_cursor(&cursor.0, &result)
+            };
+            if !fits {
+                break;
+            }
+
+            match &mut table {
+                Some(table) => consume_grid(table),
+                None => consume_grid(&mut inventory),
+            }
+
+            if shift {
+                let left = give_item(&mut inventory, result);
+                if !left.is_empty() {
+                    drop_item(&mut commands, layer.0, pos.0, left, PICKUP_DELAY);
+                }
+            } else if cursor.0.is_empty() {
+                cursor.0 = result;
+            } else {
+                cursor.0.count += result.count;
+            }
+        }
+    }
+}
+
+/// Opens a crafting window when players right click a crafting table, unless
+/// they're sneaking to place a block against it.
+pub fn open_crafting_tables(
+    mut commands: Commands,
+    mut events: EventReader<InteractBlockEvent>,
+    players: Query<(&GameMode, &Flags, &EntityLayerId)>,
+    layers: Query<&ChunkLayer>,
+) {
+    for event in events.read() {
+        if event.hand != Hand::Main {
+            continue;
+        }
+        let Ok((game_mode, flags, layer_id)) = players.get(event.client) else {
+            continue;
+        };
+        if *game_mode == GameMode::Spectator || flags.sneaking() {
+            continue;
+        }
+
+        let is_table = layers
+            .get(layer_id.0)
+            .ok()
+            .and_then(|layer| layer.block(event.position))
+            .is_some_and(|block| block.state.to_kind() == BlockKind::CraftingTable);
+        if !is_table {
+            continue;
+        }
+
+        let title = Text::translate("container.crafting", []);
+        let table = commands
+            .spawn((
+                Inventory::with_title(InventoryKind::Crafting, title),
+                CraftingTable {
+                    viewer: event.client,
+                    layer: layer_id.0,
+                    pos: event.position,
+                },
+            ))
+            .id();
+
+        commands
+            .entity(event.client)
+            .insert(OpenInventory::new(table));
+    }
+}
+
+/// Takes everything out of a crafting grid
+fn empty_grid(grid: &mut Inventory) -> Vec<ItemStack> {
+    let (slots, _) = grid_slots(grid);
+
+    slots
+        .map(|slot| grid.replace_slot(slot, ItemStack::EMPTY))
+        .filter(|stack| !stack.is_empty())
+        .collect()
+}
+
+/// Puts stacks back in a player's inventory, dropping what doesn't fit
+fn give_back(
+    commands: &mut Commands,
+    inventory: &mut Inventory,
+    stacks: Vec<ItemStack>,
+    pos: &Position,
+    layer: &EntityLayerId,
+) {
+    for stack in stacks {
+        let left = give_item(inventory, stack);
+        if !left.is_empty() {
+            drop_item(commands, layer.0, pos.0, left, PICKUP_DELAY);
+        }
+    }
+}
+
+/// Gives back what's left in crafting grids when players close the window,
+/// as the client expects, or drops it on the table when they've left.
+/// Crafting table grids are removed afterwards.
+pub fn close_crafting_grids(
+    mut commands: Commands,
+    mut packets: EventReader<PacketEvent>,
+    mut players: Query<
+        (&mut Inventory, &Position, &EntityLayerId, Option<&OpenInventory>),
+        With<Client>,
+    >,
+    mut tables: Query<(Entity, &mut Inventory, &CraftingTable), Without<Client>>,
+) {
+    for packet in packets.read() {
+        // Window 0 is the player's own inventory, with its 2x2 grid
+        let Some(close) = packet.decode::<CloseHandledScreenC2s>() else {
+            continue;
+        };
+        if close.window_id != 0 {
+            continue;
+        }
+        let Ok((mut inventory, pos, layer, _)) = players.get_mut(packet.client) else {
+            continue;
+        };
+
+        let stacks = empty_grid(&mut inventory);
+        give_back(&mut commands, &mut inventory, stacks, pos, layer);
+    }
+
+    for (entity, mut grid, table) in &mut tables {
+        match players.get_mut(table.viewer) {
+            Ok((_, _, _, Some(open))) if open.entity == entity => continue,
+            Ok((mut inventory, pos, layer, _)) => {
+                let stacks = empty_grid(&mut grid);
+                give_back(&mut commands, &mut inventory, stacks, pos, layer);
+            }
+            // The viewer left, so their grid spills onto the table
+            Err(_) => {
+                let top = DVec3::new(
+                    f64::from(table.pos.x) + 0.5,
+                    f64::from(table.pos.y) + 1.0,
+                    f64::from(table.pos.z) + 0.5,
+                );
+                for stack in empty_grid(&mut grid) {
+                    drop_item(&mut commands, table.layer, top, stack, PICKUP_DELAY);
+                }
+            }
+        }
+
+        commands.entity(entity).insert(Despawned);
+    }
+}
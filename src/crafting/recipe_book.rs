@@ -0,0 +1,137 @@
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet, HashSet},
+    path::PathBuf,
+};
+
+use valence::{
+    ident::Ident,
+    prelude::*,
+    protocol::{
+        packets::play::{
+            unlock_recipes_s2c::UpdateRecipeBookAction, SynchronizeRecipesS2c, UnlockRecipesS2c,
+        },
+        RawBytes, WritePacket,
+    },
+    ItemKind,
+};
+
+use super::recipes::Recipes;
+use crate::{server::shutdown::ShutdownEvent, setup::settings::Settings, world::storage};
+
+/// The recipes each player has unlocked in their recipe book, by UUID, kept
+/// in `recipe_book.json` in the world's data directory.
+#[derive(Resource)]
+pub struct RecipeBooks {
+    path: PathBuf,
+    unlocked: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl RecipeBooks {
+    fn save(&self) {
+        storage::save(&self.path, &self.unlocked);
+    }
+}
+
+pub fn load_recipe_books(mut commands: Commands, settings: Res<Settings>) {
+    let path = storage::data_dir(&settings).join("recipe_book.json");
+
+    commands.insert_resource(RecipeBooks {
+        unlocked: storage::load(&path),
+        path,
+    });
+}
+
+/// Writes recipe books out one last time when the server stops
+pub fn save_recipe_books(books: Res<RecipeBooks>, mut shutdown: EventReader<ShutdownEvent>) {
+    if shutdown.read().next().is_some() {
+        books.save();
+    }
+}
+
+fn idents<'a>(ids: impl IntoIterator<Item = &'a String>) -> Vec<Ident<Cow<'a, str>>> {
+    ids.into_iter()
+        .filter_map(|id| Ident::new(Cow::Borrowed(id.as_str())).ok())
+        .collect()
+}
+
+/// A packet changing the recipe book, leaving its settings alone
+fn unlock_packet<'a>(
+    action: UpdateRecipeBookAction<'a>,
+    recipe_ids: Vec<Ident<Cow<'a, str>>>,
+) -> UnlockRecipesS2c<'a> {
+    UnlockRecipesS2c {
+        action,
+        crafting_recipe_book_open: false,
+        crafting_recipe_book_filter_active: false,
+        smelting_recipe_book_open: false,
+        smelting_recipe_book_filter_active: false,
+        blast_furnace_recipe_book_open: false,
+        blast_furnace_recipe_book_filter_active: false,
+        smoker_recipe_book_open: false,
+        smoker_recipe_book_filter_active: false,
+        recipe_ids,
+    }
+}
+
+/// Sends joining players every recipe the server knows and fills their recipe
+/// book with the ones they've unlocked.
+pub fn sync_recipe_books(
+    mut clients: Query<(&mut Client, &UniqueId), Added<Client>>,
+    recipes: Res<Recipes>,
+    books: Res<RecipeBooks>,
+) {
+    for (mut client, uuid) in &mut clients {
+        let encoded = recipes.encode();
+        client.write_packet(&SynchronizeRecipesS2c {
+            recipes: RawBytes(&encoded),
+        });
+
+        let unlocked = books.unlocked.get(&uuid.0.to_string());
+        client.write_packet(&unlock_packet(
+            UpdateRecipeBookAction::Init {
+                recipe_ids: Vec::new(),
+            },
+            idents(unlocked.into_iter().flatten()),
+        ));
+    }
+}
+
+/// Unlocks recipes as players get hold of something that goes into them, the
+/// way vanilla's recipe advancements do.
+pub fn unlock_recipes(
+    mut players: Query<(&mut Client, &UniqueId, &Inventory), Changed<Inventory>>,
+    recipes: Res<Recipes>,
+    mut books: ResMut<RecipeBooks>,
+) {
+    for (mut client, uuid, inventory) in &mut players {
+        let held: HashSet<ItemKind> = (0..inventory.slot_count())
+            .map(|slot| inventory.slot(slot))
+            .filter(|stack| !stack.is_empty())
+            .map(|stack| stack.item)
+            .collect();
+        if held.is_empty() {
+            continue;
+        }
+
+        let book = books.unlocked.entry(uuid.0.to_string()).or_default();
+        let new: Vec<String> = recipes
+            .iter()
+            .filter(|recipe| !book.contains(&recipe.id))
+            .filter(|recipe| {
+                recipe
+                    .ingredients()
+                    .any(|ingredient| ingredient.iter().any(|item| held.contains(item)))
+            })
+            .map(|recipe| recipe.id.clone())
+            .collect();
+        if new.is_empty() {
+            continue;
+        }
+
+        book.extend(new.iter().cloned());
+        books.save();
+
+        client.write_packet(&unlock_packet(UpdateRecipeBookAction::Add, idents(&new)));
+    }
+}
@@ -0,0 +1,744 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+use valence::{
+    log::{info, warn},
+    prelude::*,
+    protocol::{Encode, VarInt},
+    ItemKind, ItemStack,
+};
+
+use crate::{setup::settings::Settings, world::storage};
+
+/// Ticks smelting takes when a recipe doesn't say
+const DEFAULT_COOK_TICKS: i16 = 200;
+/// How deep tags may include other tags
+const MAX_TAG_DEPTH: u32 = 8;
+
+/// The items any one of which fills a slot of a recipe
+///
+/// Empty for the gaps in a shaped recipe
+pub type Ingredient = Vec<ItemKind>;
+
+#[derive(Debug)]
+pub enum RecipeKind {
+    Shaped {
+        width: usize,
+        height: usize,
+        /// Row by row
+        pattern: Vec<Ingredient>,
+        result: ItemStack,
+        show_notification: bool,
+    },
+    Shapeless {
+        ingredients: Vec<Ingredient>,
+        result: ItemStack,
+    },
+    Smelting {
+        ingredient: Ingredient,
+        result: ItemKind,
+        experience: f32,
+        cook_ticks: i16,
+    },
+}
+
+/// A recipe loaded from a data pack.
+#[derive(Debug)]
+pub struct Recipe {
+    /// Such as `minecraft:oak_planks`, taken from the file name
+    pub id: String,
+    /// Recipes in the same group share a slot in the recipe book
+    pub group: String,
+    /// The recipe book tab, as the protocol numbers it
+    pub category: i32,
+    pub kind: RecipeKind,
+}
+
+impl Recipe {
+    pub fn ingredients(&self) -> impl Iterator<Item = &Ingredient> {
+        let ingredients = match &self.kind {
+            RecipeKind::Shaped { pattern, .. } => pattern.as_slice(),
+            RecipeKind::Shapeless { ingredients, .. } => ingredients.as_slice(),
+            RecipeKind::Smelting { ingredient, .. } => std::slice::from_ref(ingredient),
+        };
+
+        ingredients.iter().filter(|ingredient| !ingredient.is_empty())
+    }
+
+    /// Whether the grid, `width` slots wide, holds exactly what this recipe
+    /// needs. Shaped recipes may sit anywhere in the grid and be mirrored.
+    fn crafts(&self, grid: &[ItemStack], width: usize) -> bool {
+        match &self.kind {
+            RecipeKind::Shaped {
+                width: recipe_width,
+                height: recipe_height,
+                pattern,
+                ..
+            } => matches_shaped(*recipe_width, *recipe_height, pattern, grid, width),
+            RecipeKind::Shapeless { ingredients, .. } => matches_shapeless(ingredients, grid),
+            RecipeKind::Smelting { .. } => false,
+        }
+    }
+
+    /// Writes the recipe as the synchronize recipes packet lays it out
+    fn encode(&self, buf: &mut Vec<u8>) {
+        match &self.kind {
+            RecipeKind::Shaped {
+                width,
+                height,
+                pattern,
+                result,
+                show_notification,
+            } => {
+                write(buf, "minecraft:crafting_shaped");
+                write(buf, self.id.as_str());
+                write(buf, VarInt(*width as i32));
+                write(buf, VarInt(*height as i32));
+                write(buf, self.group.as_str());
+                write(buf, VarInt(self.category));
+                for ingredient in pattern {
+                    encode_ingredient(buf, ingredient);
+                }
+                write(buf, result);
+                write(buf, *show_notification);
+            }
+            RecipeKind::Shapeless {
+                ingredients,
+                result,
+            } => {
+                write(buf, "minecraft:crafting_shapeless");
+                write(buf, self.id.as_str());
+                write(buf, self.group.as_str());
+                write(buf, VarInt(self.category));
+                write(buf, VarInt(ingredients.len() as i32));
+                for ingredient in ingredients {
+                    encode_ingredient(buf, ingredient);
+                }
+                write(buf, result);
+            }
+            RecipeKind::Smelting {
+                ingredient,
+                result,
+                experience,
+                cook_ticks,
+            } => {
+                write(buf, "minecraft:smelting");
+                write(buf, self.id.as_str());
+                write(buf, self.group.as_str());
+                write(buf, VarInt(self.category));
+                encode_ingredient(buf, ingredient);
+                write(buf, ItemStack::new(*result, 1, None));
+                write(buf, *experience);
+                write(buf, VarInt(i32::from(*cook_ticks)));
+            }
+        }
+    }
+}
+
+fn write(buf: &mut Vec<u8>, value: impl Encode) {
+    value
+        .encode(&mut *buf)
+        .expect("encoding into memory can't fail");
+}
+
+fn encode_ingredient(buf: &mut Vec<u8>, ingredient: &Ingredient) {
+    write(buf, VarInt(ingredient.len() as i32));
+    for item in ingredient {
+        write(buf, ItemStack::new(*item, 1, None));
+    }
+}
+
+/// Whether `stack` can fill a slot needing `ingredient`
+fn fills(ingredient: &Ingredient, stack: &ItemStack) -> bool {
+    if ingredient.is_empty() {
+        stack.is_empty()
+    } else {
+        !stack.is_empty() && ingredient.contains(&stack.item)
+    }
+}
+
+fn matches_shaped(
+    width: usize,
+    height: usize,
+    pattern: &[Ingredient],
+    grid: &[ItemStack],
+    grid_width: usize,
+) -> bool {
+    let grid_height = grid.len() / grid_width;
+    if width > grid_width || height > grid_height {
+        return false;
+    }
+
+    for top in 0..=grid_height - height {
+        for left in 0..=grid_width - width {
+            for mirrored in [false, true] {
+                let matched = (0..grid_height).all(|y| {
+                    (0..grid_width).all(|x| {
+                        let stack = &grid[y * grid_width + x];
+                        let inside = (left..left + width).contains(&x)
+                            && (top..top + height).contains(&y);
+                        if !inside {
+                            return stack.is_empty();
+                        }
+
+                        let column = if mirrored {
+                            width - 1 - (x - left)
+                        } else {
+                            x - left
+                        };
+                        fills(&pattern[(y - top) * width + column], stack)
+                    })
+                });
+
+                if matched {
+                    return true;
+                }
+            }
+        }
+    }
+
+    false
+}
+
+fn matches_shapeless(ingredients: &[Ingredient], grid: &[ItemStack]) -> bool {
+    let stacks: Vec<&ItemStack> = grid.iter().filter(|stack| !stack.is_empty()).collect();
+    if stacks.len() != ingredients.len() {
+        return false;
+    }
+
+    let mut used = vec![false; ingredients.len()];
+    assign(&stacks, ingredients, &mut used)
+}
+
+/// Tries to give each stack an ingredient of its own, backing out when one
+/// is left without
+fn assign(stacks: &[&ItemStack], ingredients: &[Ingredient], used: &mut [bool]) -> bool {
+    let Some((stack, rest)) = stacks.split_first() else {
+        return true;
+    };
+
+    for (i, ingredient) in ingredients.iter().enumerate() {
+        if used[i] || !fills(ingredient, stack) {
+            continue;
+        }
+
+        used[i] = true;
+        if assign(rest, ingredients, used) {
+            return true;
+        }
+        used[i] = false;
+    }
+
+    false
+}
+
+/// Every recipe the server knows, loaded once at startup.
+#[derive(Resource, Default, Debug)]
+pub struct Recipes {
+    recipes: Vec<Recipe>,
+}
+
+impl Recipes {
+    /// Loads the recipes of a data pack's `data/minecraft` directory,
+    /// skipping types other than shaped, shapeless and smelting
+    fn load(dir: &Path) -> Self {
+        let tags = Tags::load(&dir.join("tags").join("items"));
+
+        let recipes: Vec<Recipe> = json_files(&dir.join("recipes"))
+            .into_iter()
+            .filter_map(|path| {
+                let id = namespaced(&path.file_stem()?.to_string_lossy());
+                let json = read_json(&path)?;
+
+                parse_recipe(id, json, &tags)
+                    .map_err(|e| warn!("skipping recipe {}: {e}", path.display()))
+                    .ok()
+                    .flatten()
+            })
+            .collect();
+
+        info!("loaded {} recipes from {}", recipes.len(), dir.display());
+        Self { recipes }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Recipe> {
+        self.recipes.iter()
+    }
+
+    /// What the crafting grid, `width` slots wide, makes
+    pub fn crafting(&self, grid: &[ItemStack], width: usize) -> Option<ItemStack> {
+        self.recipes
+            .iter()
+            .find(|recipe| recipe.crafts(grid, width))
+            .and_then(|recipe| match &recipe.kind {
+                RecipeKind::Shaped { result, .. } | RecipeKind::Shapeless { result, .. } => {
+                    Some(result.clone())
+                }
+                RecipeKind::Smelting { .. } => None,
+            })
+    }
+
+    /// What smelting `item` gives, and how many ticks it takes
+    pub fn smelting(&self, item: ItemKind) -> Option<(ItemKind, i16)> {
+        self.recipes.iter().find_map(|recipe| match &recipe.kind {
+            RecipeKind::Smelting {
+                ingredient,
+                result,
+                cook_ticks,
+                ..
+            } if ingredient.contains(&item) => Some((*result, *cook_ticks)),
+            _ => None,
+        })
+    }
+
+    /// Every recipe, laid out as the synchronize recipes packet expects
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        write(&mut buf, VarInt(self.recipes.len() as i32));
+        for recipe in &self.recipes {
+            recipe.encode(&mut buf);
+        }
+
+        buf
+    }
+}
+
+/// A recipe file, as found in `data/minecraft/recipes`
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+enum RecipeJson {
+    #[serde(rename = "minecraft:crafting_shaped")]
+    Shaped {
+        #[serde(default)]
+        group: String,
+        category: Option<String>,
+        pattern: Vec<String>,
+        key: HashMap<String, IngredientJson>,
+        result: ResultJson,
+        #[serde(default = "show_notification")]
+        show_notification: bool,
+    },
+    #[serde(rename = "minecraft:crafting_shapeless")]
+    Shapeless {
+        #[serde(default)]
+        group: String,
+        category: Option<String>,
+        ingredients: Vec<IngredientJson>,
+        result: ResultJson,
+    },
+    #[serde(rename = "minecraft:smelting")]
+    Smelting {
+        #[serde(default)]
+        group: String,
+        category: Option<String>,
+        ingredient: IngredientJson,
+        result: String,
+        #[serde(default)]
+        experience: f32,
+        cookingtime: Option<i16>,
+    },
+    /// Blasting, smithing, stonecutting and the special crafting recipes
+    #[serde(other)]
+    Unsupported,
+}
+
+fn show_notification() -> bool {
+    true
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum IngredientJson {
+    One(ItemOrTag),
+    AnyOf(Vec<ItemOrTag>),
+}
+
+#[derive(Deserialize)]
+struct ItemOrTag {
+    item: Option<String>,
+    tag: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ResultJson {
+    item: String,
+    count: Option<i8>,
+}
+
+/// A tag file, as found in `data/minecraft/tags/items`
+#[derive(Deserialize)]
+struct TagJson {
+    values: Vec<TagEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TagEntry {
+    Id(String),
+    Optional { id: String },
+}
+
+/// Adds the `minecraft` namespace to ids without one
+fn namespaced(id: &str) -> String {
+    if id.contains(':') {
+        id.to_owned()
+    } else {
+        format!("minecraft:{id}")
+    }
+}
+
+fn item_kind(id: &str) -> Option<ItemKind> {
+    ItemKind::from_str(id.strip_prefix("minecraft:").unwrap_or(id))
+}
+
+/// The JSON files in a directory, ignoring anything unreadable
+fn json_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    files.sort();
+    files
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: &Path) -> Option<T> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| warn!("failed to read {}: {e}", path.display()))
+        .ok()?;
+
+    serde_json::from_str(&contents)
+        .map_err(|e| warn!("failed to parse {}: {e}", path.display()))
+        .ok()
+}
+
+/// The item tags of a data pack, by namespaced name
+struct Tags(HashMap<String, Vec<String>>);
+
+impl Tags {
+    fn load(dir: &Path) -> Self {
+        let tags = json_files(dir)
+            .into_iter()
+            .filter_map(|path| {
+                let name = namespaced(&path.file_stem()?.to_string_lossy());
+                let tag: TagJson = read_json(&path)?;
+                let values = tag
+                    .values
+                    .into_iter()
+                    .map(|entry| match entry {
+                        TagEntry::Id(id) | TagEntry::Optional { id } => id,
+                    })
+                    .collect();
+                Some((name, values))
+            })
+            .collect();
+
+        Self(tags)
+    }
+
+    /// The items in a tag, following tags it includes
+    fn items(&self, name: &str, depth: u32) -> Vec<ItemKind> {
+        let Some(values) = self.0.get(&namespaced(name)) else {
+            return Vec::new();
+        };
+        if depth >= MAX_TAG_DEPTH {
+            return Vec::new();
+        }
+
+        values
+            .iter()
+            .flat_map(|value| match value.strip_prefix('#') {
+                Some(tag) => self.items(tag, depth + 1),
+                None => item_kind(value).into_iter().collect(),
+            })
+            .collect()
+    }
+
+    fn ingredient(&self, json: &IngredientJson) -> Result<Ingredient, String> {
+        let options = match json {
+            IngredientJson::One(option) => std::slice::from_ref(option),
+            IngredientJson::AnyOf(options) => options.as_slice(),
+        };
+
+        let mut items = Vec::new();
+        for option in options {
+            match (&option.item, &option.tag) {
+                (Some(item), _) => items.extend(item_kind(item)),
+                (None, Some(tag)) => items.extend(self.items(tag, 0)),
+                (None, None) => return Err("ingredient without an item or tag".to_owned()),
+            }
+        }
+
+        if items.is_empty() {
+            return Err("ingredient matches no known items".to_owned());
+        }
+        Ok(items)
+    }
+}
+
+fn result_stack(json: &ResultJson) -> Result<ItemStack, String> {
+    let item = item_kind(&json.item).ok_or_else(|| format!("unknown item {}", json.item))?;
+    Ok(ItemStack::new(item, json.count.unwrap_or(1), None))
+}
+
+/// The recipe book tab of a crafting recipe
+fn crafting_category(category: Option<&str>) -> i32 {
+    match category {
+        Some("building") => 0,
+        Some("redstone") => 1,
+        Some("equipment") => 2,
+        _ => 3,
+    }
+}
+
+/// The recipe book tab of a smelting recipe
+fn cooking_category(category: Option<&str>) -> i32 {
+    match category {
+        Some("food") => 0,
+        Some("blocks") => 1,
+        _ => 2,
+    }
+}
+
+/// Turns a recipe file into a recipe, or `None` for unsupported types
+fn parse_recipe(id: String, json: RecipeJson, tags: &Tags) -> Result<Option<Recipe>, String> {
+    let recipe = match json {
+        RecipeJson::Shaped {
+            group,
+            category,
+            pattern,
+            key,
+            result,
+            show_notification,
+        } => {
+            let width = pattern.iter().map(|row| row.chars().count()).max().unwrap_or(0);
+            let height = pattern.len();
+            if width == 0 || width > 3 || height > 3 {
+                return Err(format!("pattern is {width}x{height}"));
+            }
+
+            let mut cells = Vec::with_capacity(width * height);
+            for row in &pattern {
+                let mut symbols: Vec<char> = row.chars().collect();
+                symbols.resize(width, ' ');
+
+                for symbol in symbols {
+                    if symbol == ' ' {
+                        cells.push(Vec::new());
+                        continue;
+                    }
+                    let ingredient = key
+                        .get(&symbol.to_string())
+                        .ok_or_else(|| format!("pattern symbol '{symbol}' has no key"))?;
+                    cells.push(tags.ingredient(ingredient)?);
+                }
+            }
+
+            Recipe {
+                id,
+                group,
+                category: crafting_category(category.as_deref()),
+                kind: RecipeKind::Shaped {
+                    width,
+                    height,
+                    pattern: cells,
+                    result: result_stack(&result)?,
+                    show_notification,
+                },
+            }
+        }
+        RecipeJson::Shapeless {
+            group,
+            category,
+            ingredients,
+            result,
+        } => {
+            if ingredients.is_empty() || ingredients.len() > 9 {
+                return Err(format!("needs {} ingredients", ingredients.len()));
+            }
+
+            Recipe {
+                id,
+                group,
+                category: crafting_category(category.as_deref()),
+                kind: RecipeKind::Shapeless {
+                    ingredients: ingredients
+                        .iter()
+                        .map(|ingredient| tags.ingredient(ingredient))
+                        .collect::<Result<_, _>>()?,
+                    result: result_stack(&result)?,
+                },
+            }
+        }
+        RecipeJson::Smelting {
+            group,
+            category,
+            ingredient,
+            result,
+            experience,
+            cookingtime,
+        } => Recipe {
+            id,
+            group,
+            category: cooking_category(category.as_deref()),
+            kind: RecipeKind::Smelting {
+                ingredient: tags.ingredient(&ingredient)?,
+                result: item_kind(&result).ok_or_else(|| format!("unknown item {result}"))?,
+                experience,
+                cook_ticks: cookingtime.unwrap_or(DEFAULT_COOK_TICKS),
+            },
+        },
+        RecipeJson::Unsupported => return Ok(None),
+    };
+
+    Ok(Some(recipe))
+}
+
+/// Finds a relative data pack path in the working directory, next to the
+/// executable or in the world's data directory, in that order
+fn resolve_data_pack(path: &Path, settings: &Settings) -> Option<PathBuf> {
+    if path.is_absolute() {
+        return path.is_dir().then(|| path.to_path_buf());
+    }
+
+    let exe_dir = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf));
+
+    [Some(PathBuf::new()), exe_dir, Some(storage::data_dir(settings))]
+        .into_iter()
+        .flatten()
+        .map(|base| base.join(path))
+        .find(|candidate| candidate.is_dir())
+}
+
+pub fn load_recipes(mut commands: Commands, settings: Res<Settings>) {
+    let recipes = match &settings.data_pack_path {
+        Some(path) => match resolve_data_pack(path, &settings) {
+            Some(dir) => Recipes::load(&dir),
+            None => {
+                warn!(
+                    "data pack directory {} not found, crafting is disabled and furnaces \
+                     only use their built-in recipes",
+                    path.display()
+                );
+                Recipes::default()
+            }
+        },
+        None => Recipes::default(),
+    };
+
+    commands.insert_resource(recipes);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stack(item: ItemKind) -> ItemStack {
+        ItemStack::new(item, 1, None)
+    }
+
+    /// A crafting grid from rows of items, `None` being an empty slot
+    fn grid(rows: &[&[Option<ItemKind>]]) -> Vec<ItemStack> {
+        rows.iter()
+            .flat_map(|row| row.iter())
+            .map(|item| item.map_or(ItemStack::EMPTY, stack))
+            .collect()
+    }
+
+    const P: Option<ItemKind> = Some(ItemKind::OakPlanks);
+    const S: Option<ItemKind> = Some(ItemKind::Stick);
+    const C: Option<ItemKind> = Some(ItemKind::Cobblestone);
+    const E: Option<ItemKind> = None;
+
+    /// A wooden hoe: two planks across the top left, then sticks below the
+    /// right one
+    fn hoe() -> (usize, usize, Vec<Ingredient>) {
+        let planks = vec![ItemKind::OakPlanks];
+        let stick = vec![ItemKind::Stick];
+        let pattern = vec![
+            planks.clone(),
+            planks,
+            vec![],
+            stick.clone(),
+            vec![],
+            stick,
+        ];
+
+        (2, 3, pattern)
+    }
+
+    #[test]
+    fn shaped_matches_exact_layout() {
+        let (width, height, pattern) = hoe();
+        let grid = grid(&[&[P, P, E], &[E, S, E], &[E, S, E]]);
+
+        assert!(matches_shaped(width, height, &pattern, &grid, 3));
+    }
+
+    #[test]
+    fn shaped_matches_mirrored() {
+        let (width, height, pattern) = hoe();
+        let grid = grid(&[&[P, P, E], &[S, E, E], &[S, E, E]]);
+
+        assert!(matches_shaped(width, height, &pattern, &grid, 3));
+    }
+
+    #[test]
+    fn shaped_matches_offset() {
+        let (width, height, pattern) = hoe();
+        let grid = grid(&[&[E, P, P], &[E, E, S], &[E, E, S]]);
+
+        assert!(matches_shaped(width, height, &pattern, &grid, 3));
+    }
+
+    #[test]
+    fn shaped_rejects_extra_items_and_small_grids() {
+        let (width, height, pattern) = hoe();
+        let extra = grid(&[&[P, P, C], &[E, S, E], &[E, S, E]]);
+        let small = grid(&[&[P, P], &[E, S]]);
+
+        assert!(!matches_shaped(width, height, &pattern, &extra, 3));
+        assert!(!matches_shaped(width, height, &pattern, &small, 2));
+    }
+
+    #[test]
+    fn shaped_fits_player_grid() {
+        let planks = vec![ItemKind::OakPlanks];
+        let pattern = vec![planks.clone(), planks];
+
+        // Sticks: two planks stacked, anywhere in the 2x2 grid
+        assert!(matches_shaped(1, 2, &pattern, &grid(&[&[E, P], &[E, P]]), 2));
+        assert!(!matches_shaped(1, 2, &pattern, &grid(&[&[P, E], &[E, P]]), 2));
+    }
+
+    #[test]
+    fn shapeless_ignores_layout() {
+        let ingredients = vec![vec![ItemKind::OakPlanks], vec![ItemKind::Stick]];
+
+        assert!(matches_shapeless(&ingredients, &grid(&[&[E, S, E], &[E, E, P]])));
+        assert!(!matches_shapeless(&ingredients, &grid(&[&[S, S, P]])));
+        assert!(!matches_shapeless(&ingredients, &grid(&[&[S, E, E]])));
+    }
+
+    #[test]
+    fn shapeless_assigns_each_stack_its_own_ingredient() {
+        // The first stack fits both ingredients but only the second one
+        // leaves something for the planks, so the first choice is undone
+        let ingredients = vec![
+            vec![ItemKind::Stick, ItemKind::OakPlanks],
+            vec![ItemKind::Stick],
+        ];
+
+        assert!(matches_shapeless(&ingredients, &grid(&[&[S, P]])));
+        assert!(!matches_shapeless(&ingredients, &grid(&[&[P, P]])));
+    }
+}
@@ -0,0 +1,209 @@
+use std::collections::BTreeMap;
+
+use valence::{
+    boss_bar::{BossBarBundle, BossBarColor, BossBarDivision, BossBarHealth, BossBarTitle},
+    command::parsers::{CommandArg, CommandArgParseError, ParseInput},
+    prelude::*,
+    protocol::packets::play::command_tree_s2c::{Parser, StringArg},
+};
+
+/// A boss bar created by id, shown to a chosen set of players.
+///
+/// Each bar lives on its own entity layer, so a player sees it while that
+/// layer is in their `VisibleEntityLayers`.
+#[derive(Component, Clone, Debug)]
+pub struct CustomBossBar {
+    pub id: String,
+    pub layer: Entity,
+    pub value: i32,
+    pub max: i32,
+    pub visible: bool,
+    pub players: Vec<Entity>,
+}
+
+impl CustomBossBar {
+    /// The fraction of the bar that is filled
+    pub fn health(&self) -> f32 {
+        if self.max <= 0 {
+            return 0.0;
+        }
+
+        (self.value as f32 / self.max as f32).clamp(0.0, 1.0)
+    }
+}
+
+/// Every custom boss bar by id, with the layer it is shown through.
+#[derive(Resource, Default)]
+pub struct BossBars(BTreeMap<String, (Entity, Entity)>);
+
+impl BossBars {
+    /// The boss bar entity with this id
+    pub fn get(&self, id: &str) -> Option<Entity> {
+        self.0.get(id).map(|(bar, _)| *bar)
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = &str> {
+        self.0.keys().map(String::as_str)
+    }
+}
+
+/// Creates a boss bar that nobody sees yet, returning its entity.
+///
+/// Returns None if a bar with the id already exists.
+pub fn spawn_boss_bar(
+    commands: &mut Commands,
+    boss_bars: &mut BossBars,
+    server: &Server,
+    id: &str,
+    title: Text,
+) -> Option<Entity> {
+    if boss_bars.0.contains_key(id) {
+        return None;
+    }
+
+    let layer = commands.spawn(EntityLayer::new(server)).id();
+    let bar = CustomBossBar {
+        id: id.to_owned(),
+        layer,
+        value: 0,
+        max: 100,
+        visible: true,
+        players: vec![],
+    };
+
+    let entity = commands
+        .spawn((
+            BossBarBundle {
+                title: BossBarTitle(title),
+                health: BossBarHealth(bar.health()),
+                layer: EntityLayerId(layer),
+                ..Default::default()
+            },
+            bar,
+        ))
+        .id();
+
+    boss_bars.0.insert(id.to_owned(), (entity, layer));
+
+    Some(entity)
+}
+
+/// Removes a boss bar and its layer, returning false if there was none
+pub fn despawn_boss_bar(commands: &mut Commands, boss_bars: &mut BossBars, id: &str) -> bool {
+    let Some((bar, layer)) = boss_bars.0.remove(id) else {
+        return false;
+    };
+
+    commands.entity(bar).insert(Despawned);
+    commands.entity(layer).insert(Despawned);
+
+    true
+}
+
+/// Keeps the fill and viewers of custom boss bars in line with their state.
+pub fn sync_boss_bars(
+    mut bars: Query<(&CustomBossBar, &mut BossBarHealth), Changed<CustomBossBar>>,
+    all_bars: Query<&CustomBossBar>,
+    mut viewers: Query<(Entity, &mut VisibleEntityLayers), With<Client>>,
+    mut removed: RemovedComponents<CustomBossBar>,
+) {
+    let any_removed = removed.read().next().is_some();
+    let mut changed = any_removed;
+
+    for (bar, mut health) in &mut bars {
+        health.0 = bar.health();
+        changed = true;
+    }
+
+    if !changed {
+        return;
+    }
+
+    for (entity, mut visible_layers) in &mut viewers {
+        for bar in &all_bars {
+            if bar.visible && bar.players.contains(&entity) {
+                visible_layers.0.insert(bar.layer);
+            } else {
+                visible_layers.0.remove(&bar.layer);
+            }
+        }
+    }
+}
+
+/// Drops players who left from every boss bar's viewers
+pub fn forget_disconnected_viewers(
+    mut removed: RemovedComponents<Client>,
+    mut bars: Query<&mut CustomBossBar>,
+) {
+    for entity in removed.read() {
+        for mut bar in &mut bars {
+            if bar.players.contains(&entity) {
+                bar.players.retain(|player| *player != entity);
+            }
+        }
+    }
+}
+
+/// A boss bar colour argument
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ColorArg(pub BossBarColor);
+
+impl CommandArg for ColorArg {
+    fn parse_arg(input: &mut ParseInput) -> Result<Self, CommandArgParseError> {
+        input.skip_whitespace();
+        let word = input.pop_word();
+
+        let color = match word {
+            "pink" => BossBarColor::Pink,
+            "blue" => BossBarColor::Blue,
+            "red" => BossBarColor::Red,
+            "green" => BossBarColor::Green,
+            "yellow" => BossBarColor::Yellow,
+            "purple" => BossBarColor::Purple,
+            "white" => BossBarColor::White,
+            _ => {
+                return Err(CommandArgParseError::InvalidArgument {
+                    expected: "boss bar color".to_owned(),
+                    got: word.to_owned(),
+                })
+            }
+        };
+
+        Ok(ColorArg(color))
+    }
+
+    fn display() -> Parser {
+        Parser::String(StringArg::SingleWord)
+    }
+}
+
+/// A boss bar style argument, using the vanilla names
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StyleArg(pub BossBarDivision);
+
+impl CommandArg for StyleArg {
+    fn parse_arg(input: &mut ParseInput) -> Result<Self, CommandArgParseError> {
+        input.skip_whitespace();
+        let word = input.pop_word();
+
+        let division = match word {
+            "progress" => BossBarDivision::NoDivision,
+            "notched_6" => BossBarDivision::SixNotches,
+            "notched_10" => BossBarDivision::TenNotches,
+            "notched_12" => BossBarDivision::TwelveNotches,
+            "notched_20" => BossBarDivision::TwentyNotches,
+            _ => {
+                return Err(CommandArgParseError::InvalidArgument {
+                    expected: "boss bar style".to_owned(),
+                    got: word.to_owned(),
+                })
+            }
+        };
+
+        Ok(StyleArg(division))
+    }
+
+    fn display() -> Parser {
+        Parser::String(StringArg::SingleWord)
+    }
+}
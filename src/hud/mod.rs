@@ -0,0 +1,60 @@
+pub mod boss_bar;
+
+use valence::{prelude::*, text::IntoText, title::SetTitle};
+
+/// How long titles fade in, stay and fade out, in ticks
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TitleTimes {
+    pub fade_in: i32,
+    pub stay: i32,
+    pub fade_out: i32,
+}
+
+impl Default for TitleTimes {
+    /// The vanilla defaults
+    fn default() -> Self {
+        Self {
+            fade_in: 10,
+            stay: 70,
+            fade_out: 20,
+        }
+    }
+}
+
+/// Shows a title, with an optional subtitle, using the given timings
+pub fn show_title<'a>(
+    client: &mut Client,
+    title: impl IntoText<'a>,
+    subtitle: Option<Text>,
+    times: Option<TitleTimes>,
+) {
+    if let Some(times) = times {
+        client.set_title_times(times.fade_in, times.stay, times.fade_out);
+    }
+
+    // The subtitle is only shown with the next title, so it goes first
+    if let Some(subtitle) = subtitle {
+        client.set_subtitle(subtitle);
+    }
+
+    client.set_title(title);
+}
+
+/// Shows a message above the hotbar
+pub fn show_action_bar<'a>(client: &mut Client, message: impl IntoText<'a>) {
+    client.set_action_bar(message);
+}
+
+/// Reads a command's text argument as a JSON text component, falling back to
+/// plain text when it isn't one
+pub fn parse_text(input: &str) -> Text {
+    let trimmed = input.trim_start();
+
+    if trimmed.starts_with(['{', '[', '"']) {
+        if let Ok(text) = serde_json::from_str::<Text>(trimmed) {
+            return text;
+        }
+    }
+
+    input.to_owned().into_text()
+}
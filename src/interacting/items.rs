@@ -0,0 +1,180 @@
+use std::ops::Range;
+
+use valence::{
+    entity::item::{ItemEntityBundle, Stack},
+    prelude::*,
+    rand::{self, Rng},
+    sound::{Sound, SoundCategory},
+    ItemStack,
+};
+
+use crate::{mobs::physics::Body, survival::Vitals};
+
+/// The main inventory and hotbar of a player, where items are given to
+pub const MAIN_SLOTS: Range<u16> = 9..45;
+/// Ticks before an item dropped by the server can be picked up
+pub const PICKUP_DELAY: u32 = 10;
+/// Dropped items are removed after five minutes
+const DESPAWN_TICKS: u32 = 6000;
+/// How far from a player's middle items get picked up
+const PICKUP_RANGE: f64 = 1.5;
+const ITEM_SIZE: f64 = 0.25;
+
+/// An item lying in the world, waiting to be picked up.
+#[derive(Component, Debug)]
+pub struct DroppedItem {
+    pickup_delay: u32,
+    age: u32,
+}
+
+/// Puts as much of `stack` into the main inventory as fits, topping up
+/// matching stacks before using empty slots. Returns what didn't fit.
+pub fn give_item(inventory: &mut Inventory, mut stack: ItemStack) -> ItemStack {
+    let max = stack.item.max_stack();
+
+    // Stacks with NBT only go into empty slots, as tags must match to merge.
+    // Likewise plain stacks skip over tagged ones of the same item.
+    if stack.nbt.is_none() {
+        for slot in MAIN_SLOTS {
+            if stack.is_empty() {
+                break;
+            }
+            let existing = inventory.slot(slot);
+            if existing.is_empty()
+                || existing.item != stack.item
+                || existing.nbt.is_some()
+                || existing.count >= max
+            {
+                continue;
+            }
+
+            let moved = (max - existing.count).min(stack.count);
+            let count = existing.count + moved;
+            inventory.set_slot_amount(slot, count);
+            stack.count -= moved;
+        }
+    }
+
+    while !stack.is_empty() {
+        let Some(slot) = inventory.first_empty_slot_in(MAIN_SLOTS) else {
+            break;
+        };
+
+        let moved = stack.count.min(max);
+        inventory.set_slot(slot, ItemStack::new(stack.item, moved, stack.nbt.clone()));
+        stack.count -= moved;
+    }
+
+    stack
+}
+
+/// Whether all of `stack` would fit in the main inventory
+pub fn has_room(inventory: &Inventory, stack: &ItemStack) -> bool {
+    let max = stack.item.max_stack();
+    let space: i32 = MAIN_SLOTS
+        .map(|slot| inventory.slot(slot))
+        .map(|existing| {
+            if existing.is_empty() {
+                i32::from(max)
+            } else if existing.item == stack.item
+                && existing.nbt.is_none()
+                && stack.nbt.is_none()
+            {
+                i32::from(max - existing.count)
+            } else {
+                0
+            }
+        })
+        .sum();
+
+    space >= i32::from(stack.count)
+}
+
+/// Spawns `stack` as an item entity at `pos`, tossed slightly upwards
+pub fn drop_item(
+    commands: &mut Commands,
+    layer: Entity,
+    pos: DVec3,
+    stack: ItemStack,
+    pickup_delay: u32,
+) -> Entity {
+    let mut rng = rand::thread_rng();
+
+    let mut body = Body::new(ITEM_SIZE, ITEM_SIZE);
+    body.velocity = DVec3::new(rng.gen_range(-0.1..0.1), 0.2, rng.gen_range(-0.1..0.1));
+
+    commands
+        .spawn((
+            ItemEntityBundle {
+                layer: EntityLayerId(layer),
+                position: Position::new(pos),
+                item_stack: Stack(stack),
+                ..Default::default()
+            },
+            body,
+            DroppedItem {
+                pickup_delay,
+                age: 0,
+            },
+        ))
+        .id()
+}
+
+/// Moves dropped items into the inventories of players standing on them,
+/// and removes items that have been lying around for too long.
+pub fn pick_up_items(
+    mut commands: Commands,
+    mut items: Query<(Entity, &mut DroppedItem, &mut Stack, &Position, &EntityLayerId)>,
+    mut players: Query<
+        (&mut Inventory, &Position, &EntityLayerId, &GameMode, Option<&Vitals>),
+        (With<Client>, Without<DroppedItem>),
+    >,
+    mut layers: Query<&mut ChunkLayer>,
+) {
+    let mut rng = rand::thread_rng();
+
+    for (entity, mut dropped, mut stack, pos, layer_id) in &mut items {
+        dropped.age += 1;
+        if dropped.age >= DESPAWN_TICKS {
+            commands.entity(entity).insert(Despawned);
+            continue;
+        }
+        if dropped.pickup_delay > 0 {
+            dropped.pickup_delay -= 1;
+            continue;
+        }
+
+        for (mut inventory, player_pos, player_layer, game_mode, vitals) in &mut players {
+            let middle = player_pos.0 + DVec3::Y * 0.9;
+            if player_layer.0 != layer_id.0
+                || *game_mode == GameMode::Spectator
+                || vitals.is_some_and(|vitals| vitals.dead)
+                || middle.distance(pos.0) > PICKUP_RANGE
+            {
+                continue;
+            }
+
+            let before = stack.0.count;
+            let left = give_item(&mut inventory, stack.0.clone());
+            if left.count == before {
+                continue;
+            }
+
+            if let Ok(mut layer) = layers.get_mut(layer_id.0) {
+                layer.play_sound(
+                    Sound::EntityItemPickup,
+                    SoundCategory::Player,
+                    pos.0,
+                    0.2,
+                    rng.gen_range(0.6..2.2),
+                );
+            }
+
+            if left.is_empty() {
+                commands.entity(entity).insert(Despawned);
+                break;
+            }
+            stack.0 = left;
+        }
+    }
+}
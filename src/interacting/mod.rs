@@ -1,71 +1,192 @@
+pub mod items;
+
 use valence::{
     action::{DiggingEvent, DiggingState},
     block::{BlockKind, PropName, PropValue},
+    entity::{entity::Flags, Look},
     interact_block::InteractBlockEvent,
     inventory::HeldItem,
-    log::debug,
-    prelude::{EventReader, Inventory, Query},
-    BlockState, ChunkLayer, Direction, GameMode, Hand, ItemStack,
+    math::DVec3,
+    op_level::OpLevel,
+    prelude::{Client, Commands, Entity, EventReader, EventWriter, Inventory, Position, Query, Res},
+    protocol::{packets::play::BlockUpdateS2c, WritePacket},
+    BlockPos, BlockState, ChunkLayer, Direction, GameMode, Hand, ItemStack,
+};
+
+use crate::{
+    anticheat::{self, Check, ViolationEvent},
+    containers::{self, store::ContainerStore, ContainerInventories, ContainerKind},
+    setup::settings::Settings,
+    world::spawn::WorldSpawn,
 };
 
+/// Whether spawn protection stops a player changing the block at `pos`.
+///
+/// Operators can always build.
+pub(crate) fn is_protected(
+    spawn: Option<&WorldSpawn>,
+    op_level: &OpLevel,
+    pos: BlockPos,
+    settings: &Settings,
+) -> bool {
+    op_level.get() == 0 && spawn.is_some_and(|spawn| spawn.protects(pos, settings.spawn_protection))
+}
+
+/// Whether a player can reach `block`, reporting them if they can't
+fn within_reach(
+    settings: &Settings,
+    player: Entity,
+    pos: &Position,
+    block: BlockPos,
+    violations: &mut EventWriter<ViolationEvent>,
+) -> bool {
+    let Some(distance) = anticheat::beyond_reach(settings, pos.0, block) else {
+        return true;
+    };
+
+    violations.send(ViolationEvent {
+        player,
+        check: Check::Reach,
+        detail: format!("reached {distance:.1} blocks"),
+    });
+
+    false
+}
+
+/// Undoes the change the client already predicted for a denied action
+fn resend_block(client: &mut Client, layer: &ChunkLayer, pos: BlockPos) {
+    let state = layer.block(pos).map_or(BlockState::AIR, |block| block.state);
+
+    client.write_packet(&BlockUpdateS2c {
+        position: pos,
+        block_id: state,
+    });
+}
 
 pub fn digging(
-    mut clients: Query<(&GameMode, &mut Inventory)>,
-    mut layers: Query<&mut ChunkLayer>,
+    mut commands: Commands,
+    mut clients: Query<(&mut Client, &GameMode, &OpLevel, &Position, &mut Inventory)>,
+    mut layers: Query<(Entity, &mut ChunkLayer, Option<&WorldSpawn>)>,
+    mut containers: ContainerInventories,
     mut events: EventReader<DiggingEvent>,
+    settings: Res<Settings>,
+    mut violations: EventWriter<ViolationEvent>,
+    mut store: ResMut<ContainerStore>,
 ) {
-    let mut layer = layers.single_mut();
+    let (layer_entity, mut layer, spawn) = layers.single_mut();
 
     for event in events.read() {
-        let Ok((game_mode, mut inventory)) = clients.get_mut(event.client) else {
+        let Ok((mut client, game_mode, op_level, pos, mut inventory)) =
+            clients.get_mut(event.client)
+        else {
             continue;
         };
 
+        if is_protected(spawn, op_level, event.position, &settings)
+            || !within_reach(&settings, event.client, pos, event.position, &mut violations)
+        {
+            resend_block(&mut client, &layer, event.position);
+            continue;
+        }
+
         if (*game_mode == GameMode::Creative && event.state == DiggingState::Start)
             || (*game_mode == GameMode::Survival && event.state == DiggingState::Stop)
         {
             let prev = layer.set_block(event.position, BlockState::AIR).unwrap();
 
+            if ContainerKind::of(prev.state.to_kind()).is_some() {
+                containers::drop_contents(
+                    &mut commands,
+                    &mut containers,
+                    layer_entity,
+                    event.position,
+                    prev.nbt.as_ref(),
+                );
+                store.record(event.position, BlockState::AIR, None);
+            }
+
             if *game_mode == GameMode::Survival {
                 let broken_block_item = prev.state.to_kind().to_item_kind();
+                let stack = ItemStack::new(broken_block_item, 1, None);
 
-                if let Some(slot) = inventory.first_slot_with_item_in(broken_block_item, 64, 9..45)
-                {
-                    let count = inventory.slot(slot).count;
-                    inventory.set_slot_amount(slot, count + 1);
-                } else {
-                    let stack = ItemStack::new(broken_block_item, 1, None);
-                    if let Some(empty_slot) = inventory.first_empty_slot_in(9..45) {
-                        inventory.set_slot(empty_slot, stack);
-                    } else if let Some(empty_slot) = inventory.first_empty_slot() {
-                        inventory.set_slot(empty_slot, stack);
-                    } else {
-                        debug!(
-                            "No empty slot to give item to player: {:?}",
-                            broken_block_item
-                        );
-                    }
+                // Whatever doesn't fit drops where the block was
+                let left = items::give_item(&mut inventory, stack);
+                if !left.is_empty() {
+                    let centre = DVec3::new(
+                        f64::from(event.position.x) + 0.5,
+                        f64::from(event.position.y) + 0.5,
+                        f64::from(event.position.z) + 0.5,
+                    );
+                    items::drop_item(
+                        &mut commands,
+                        layer_entity,
+                        centre,
+                        left,
+                        items::PICKUP_DELAY,
+                    );
                 }
             }
         }
     }
 }
 
+/// The horizontal direction facing back towards a player looking at `yaw`
+fn facing_player(yaw: f32) -> PropValue {
+    match (yaw.rem_euclid(360.0) / 90.0).round() as i32 % 4 {
+        0 => PropValue::North,
+        1 => PropValue::East,
+        2 => PropValue::South,
+        _ => PropValue::West,
+    }
+}
+
 pub fn place_blocks(
-    mut clients: Query<(&mut Inventory, &GameMode, &HeldItem)>,
-    mut layers: Query<&mut ChunkLayer>,
+    mut clients: Query<(
+        &mut Client,
+        &mut Inventory,
+        &GameMode,
+        &OpLevel,
+        &Position,
+        &HeldItem,
+        &Flags,
+        &Look,
+    )>,
+    mut layers: Query<(&mut ChunkLayer, Option<&WorldSpawn>)>,
     mut events: EventReader<InteractBlockEvent>,
+    settings: Res<Settings>,
+    mut violations: EventWriter<ViolationEvent>,
+    mut store: ResMut<ContainerStore>,
 ) {
-    let mut layer = layers.single_mut();
+    let (mut layer, spawn) = layers.single_mut();
 
     for event in events.read() {
-        let Ok((mut inventory, game_mode, held)) = clients.get_mut(event.client) else {
+        let Ok((mut client, mut inventory, game_mode, op_level, pos, held, flags, look)) =
+            clients.get_mut(event.client)
+        else {
             continue;
         };
         if event.hand != Hand::Main {
             continue;
         }
 
+        let real_pos = event.position.get_in_direction(event.face);
+        if is_protected(spawn, op_level, real_pos, &settings)
+            || !within_reach(&settings, event.client, pos, event.position, &mut violations)
+        {
+            resend_block(&mut client, &layer, real_pos);
+            continue;
+        }
+
+        // Right clicking a container or crafting table opens it instead,
+        // unless sneaking
+        let opens_window = layer.block(event.position).is_some_and(|block| {
+            let kind = block.state.to_kind();
+            ContainerKind::of(kind).is_some() || kind == BlockKind::CraftingTable
+        });
+        if opens_window && !flags.sneaking() {
+            continue;
+        }
+
         // get the held item
         let slot_id = held.slot();
         let stack = inventory.slot(slot_id);
@@ -87,8 +208,7 @@ pub fn place_blocks(
                 inventory.set_slot(slot_id, ItemStack::EMPTY);
             }
         }
-        let real_pos = event.position.get_in_direction(event.face);
-        let state = block_kind.to_state().set(
+        let mut state = block_kind.to_state().set(
             PropName::Axis,
             match event.face {
                 Direction::Down | Direction::Up => PropValue::Y,
@@ -96,6 +216,10 @@ pub fn place_blocks(
                 Direction::West | Direction::East => PropValue::X,
             },
         );
+        if ContainerKind::of(block_kind).is_some() {
+            state = state.set(PropName::Facing, facing_player(look.yaw));
+            store.record(real_pos, state, None);
+        }
 
         layer.set_block(real_pos, state);
     }
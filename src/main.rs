@@ -1,15 +1,24 @@
-use std::thread;
+use std::{net::SocketAddr, num::NonZeroU32, path::PathBuf, thread, time::Duration};
 
-use setup::settings::Settings;
+use setup::settings::{AntiCheatSettings, MobSpawnSettings, Settings};
 
+mod anticheat;
+mod chat;
 mod commands;
+mod console;
+mod containers;
+mod crafting;
+mod hud;
 mod interacting;
+mod mobs;
+mod scoreboard;
 mod server;
 mod setup;
+mod survival;
 mod world;
-use valence::{command::AddCommand, prelude::*};
+use valence::{app::AppExit, command::AddCommand, prelude::*, text::Color};
 
-fn main() {
+fn main() -> AppExit {
     let settings = Settings {
         pre_load_chunks: 4,
         world_path: None,
@@ -18,20 +27,216 @@ fn main() {
         // chunk_thread_count: Some(4),
         world_max_height: 384,
         spawn_point: DVec3::new(0.0, 81.0, 0.0),
+        spawn_protection: 16,
+        ops: vec![],
         default_gamemode: GameMode::Creative,
+        address: SocketAddr::from(([0, 0, 0, 0], 25565)),
+        compression_threshold: Some(256),
+        tick_rate: NonZeroU32::new(20).unwrap(),
+        lan_broadcast: Some("Rust Minecraft Server!".into()),
+        motd: "A Valence Server".into(),
+        max_players: 420,
+        query_port: None,
+        // query_port: Some(25565),
+        console: true,
+        rcon: None,
+        // rcon: Some(RconSettings { port: 25575, password: "changeme".into() }),
+        tpa_timeout: Duration::from_secs(60),
+        tpa_cooldown: Duration::from_secs(10),
+        back_history: 10,
+        data_path: PathBuf::from("data"),
+        home_limits: vec![("player".into(), 3), ("admin".into(), 10)],
+        chat_format: "{prefix}<{name}> {message}".into(),
+        chat_prefixes: vec![("admin".into(), "[Admin] ".into(), Color::RED)],
+        chat_filter: vec![],
+        chat_rate_limit: Some((5, Duration::from_secs(3))),
+        join_message: Some("{name} joined the game".into()),
+        first_join_message: Some("Welcome {name} to the server for the first time!".into()),
+        leave_message: Some("{name} left the game".into()),
+        motd_path: Some(PathBuf::from("motd.txt")),
+        tab_header: Some("{motd}\n{online}/{max} players online".into()),
+        tab_footer: Some("World: {world} | TPS: {tps}".into()),
+        anticheat: Some(AntiCheatSettings {
+            max_speed: 12.0,
+            max_move: 10.0,
+            max_jump_height: 1.35,
+            max_hover_ticks: 20,
+            max_reach: 6.0,
+            kick_after: Some(20),
+            violation_window: Duration::from_secs(30),
+        }),
+        passive_mobs: Some(MobSpawnSettings {
+            interval: 100,
+            per_chunk: 4,
+            per_player: 20,
+        }),
+        hostile_mobs: Some(MobSpawnSettings {
+            interval: 20,
+            per_chunk: 4,
+            per_player: 30,
+        }),
+        data_pack_path: Some(PathBuf::from("assets/data/minecraft")),
     };
 
+    if let Err(e) = settings.validate() {
+        eprintln!("invalid settings: {e}");
+        return AppExit::error();
+    }
+
     let mut server = server::McServer::new(settings);
 
     server.app
         .add_systems(Update, (
             interacting::digging, interacting::place_blocks,
-            commands::teleport::handle, commands::gamemode::handle
+            (
+                (
+                    commands::teleport::handle,
+                    commands::spawn::handle,
+                    commands::tpa::handle,
+                    commands::back::handle,
+                    commands::home::handle,
+                    commands::warp::handle,
+                    // Before teleports, so a join teleport resets the new state
+                    anticheat::init_movement_state,
+                ),
+                commands::teleport::apply::apply_teleports,
+                (
+                    commands::back::record_teleports,
+                    survival::reset_fall_on_teleport,
+                    anticheat::reset_on_teleport,
+                ),
+            ).chain(),
+            commands::gamemode::handle,
+            commands::stop::handle,
+            commands::tpa::expire_requests,
+            commands::back::init_back_history,
+            commands::warp::handle_manage,
+            commands::setworldspawn::handle,
+            (commands::summon::handle, commands::kill::handle),
+            (
+                commands::give::handle,
+                commands::clear::handle,
+                commands::item::handle,
+                interacting::items::pick_up_items,
+            ),
+            (
+                (
+                    containers::open_containers,
+                    containers::furnace::load_furnaces,
+                    containers::furnace::tick_furnaces,
+                    containers::save_contents,
+                    containers::remove_stale_containers,
+                ).chain().after(interacting::digging),
+                (
+                    crafting::recipe_book::sync_recipe_books,
+                    crafting::open_crafting_tables,
+                    crafting::take_results,
+                    crafting::close_crafting_grids,
+                    crafting::update_results,
+                    crafting::recipe_book::unlock_recipes,
+                ).chain(),
+            ),
+            world::spawn::init_world_spawns,
+            (
+                chat::handle_chat,
+                commands::msg::handle,
+                commands::msg::handle_reply,
+                commands::me::handle,
+                commands::say::handle,
+                commands::mute::handle,
+                chat::cleanup_chat_state,
+                chat::join::announce_joins,
+                chat::join::announce_leaves,
+            ),
+            (
+                commands::scoreboard::handle,
+                commands::team::handle,
+                scoreboard::sync_objectives,
+                scoreboard::teams::sync_teams,
+                scoreboard::save_scoreboard,
+            ).chain(),
+            (
+                commands::title::handle,
+                commands::bossbar::handle,
+                hud::boss_bar::sync_boss_bars,
+                hud::boss_bar::forget_disconnected_viewers,
+            ).chain(),
+            (anticheat::validate_movement, anticheat::handle_violations).chain(),
+            (
+                (mobs::spawning::spawn_passive_mobs, mobs::spawning::spawn_hostile_mobs),
+                (mobs::passive::think, mobs::hostile::think),
+                mobs::physics::apply_physics,
+                mobs::projectile::move_projectiles,
+                world::explosion::apply_explosions,
+                mobs::player_attacks,
+                mobs::hurt_mobs,
+                (mobs::despawn_unloaded, mobs::hostile::despawn_far_away),
+            ).chain().before(survival::damage::apply_damage),
+            (
+                survival::init_vitals,
+                survival::track_sprinting,
+                survival::track_movement,
+                survival::tick_vitals,
+                survival::damage::tick_hurt_cooldowns,
+                survival::damage::apply_damage,
+                survival::damage::respawn,
+                survival::sync_vitals,
+            ).chain(),
+        ))
+        .add_systems(Startup, (
+            commands::home::load_homes,
+            commands::warp::load_warps,
+            world::spawn::load_spawn_points,
+            chat::join::load_known_players,
+            scoreboard::load_scoreboard,
+            containers::store::load_container_store,
+            crafting::recipes::load_recipes,
+            crafting::recipe_book::load_recipe_books,
+        ))
+        .add_systems(Last, (
+            commands::home::save_homes,
+            commands::warp::save_warps,
+            world::spawn::save_spawn_points,
+            crafting::recipe_book::save_recipe_books,
+            containers::store::save_container_store,
         ))
+        .add_event::<commands::teleport::apply::TeleportEvent>()
+        .add_event::<commands::teleport::apply::TeleportedEvent>()
+        .add_event::<survival::damage::DamageEvent>()
+        .add_event::<survival::damage::DeathEvent>()
+        .add_event::<anticheat::ViolationEvent>()
+        .add_event::<world::explosion::ExplosionEvent>()
+        .init_resource::<commands::teleport::apply::PendingTeleports>()
+        .init_resource::<commands::tpa::TeleportRequests>()
+        .init_resource::<chat::ChatState>()
+        .init_resource::<hud::boss_bar::BossBars>()
         .add_command::<commands::teleport::Command>()
         .add_command::<commands::gamemode::Command>()
+        .add_command::<commands::stop::Command>()
+        .add_command::<commands::spawn::Command>()
+        .add_command::<commands::tpa::Command>()
+        .add_command::<commands::back::Command>()
+        .add_command::<commands::home::Command>()
+        .add_command::<commands::warp::Command>()
+        .add_command::<commands::warp::ManageCommand>()
+        .add_command::<commands::setworldspawn::Command>()
+        .add_command::<commands::msg::Command>()
+        .add_command::<commands::msg::ReplyCommand>()
+        .add_command::<commands::me::Command>()
+        .add_command::<commands::say::Command>()
+        .add_command::<commands::mute::Command>()
+        .add_command::<commands::mute::UnmuteCommand>()
+        .add_command::<commands::scoreboard::Command>()
+        .add_command::<commands::team::Command>()
+        .add_command::<commands::title::Command>()
+        .add_command::<commands::bossbar::Command>()
+        .add_command::<commands::summon::Command>()
+        .add_command::<commands::kill::Command>()
+        .add_command::<commands::give::Command>()
+        .add_command::<commands::clear::Command>()
+        .add_command::<commands::item::Command>()
         
     ;
 
-    server.run();
+    server.run()
 }
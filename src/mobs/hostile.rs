@@ -0,0 +1,344 @@
+use std::collections::VecDeque;
+
+use valence::{
+    entity::{creeper::FuseSpeed, HeadYaw, Look},
+    prelude::*,
+    rand::{self, Rng},
+    sound::{Sound, SoundCategory},
+};
+
+use super::{
+    pathfinding::find_path,
+    physics::{line_of_sight, Body},
+    projectile::shoot_arrow,
+    Mob, MobKind,
+};
+use crate::{
+    commands::teleport::coordinates::look_towards,
+    survival::{
+        damage::{DamageEvent, DamageSource},
+        has_vitals, Vitals,
+    },
+    world::explosion::ExplosionEvent,
+};
+
+/// How far away hostile mobs notice players they can see
+const FOLLOW_RANGE: f64 = 16.0;
+/// Targets further than this are forgotten
+const FORGET_RANGE: f64 = 24.0;
+/// Ticks between recalculating the path to a target
+const REPATH_TICKS: u32 = 20;
+const EYE_HEIGHT: f64 = 1.62;
+
+const MELEE_RANGE: f64 = 1.8;
+const MELEE_DAMAGE: f32 = 3.0;
+const MELEE_COOLDOWN: u32 = 20;
+
+/// How close skeletons come before shooting
+const SHOOT_RANGE: f64 = 12.0;
+const SHOOT_COOLDOWN: u32 = 40;
+const ARROW_SPEED: f64 = 1.6;
+const ARROW_DAMAGE: f32 = 3.0;
+
+/// How close creepers get before lighting their fuse
+const FUSE_RANGE: f64 = 3.0;
+/// Creepers put their fuse out when the target gets this far away
+const DEFUSE_RANGE: f64 = 7.0;
+const FUSE_TICKS: u32 = 30;
+const CREEPER_POWER: f64 = 3.0;
+
+/// Mobs further than this from every player are removed straight away
+const DESPAWN_RANGE: f64 = 128.0;
+/// Mobs further than this from every player may be removed at random
+const RANDOM_DESPAWN_RANGE: f64 = 32.0;
+/// Chance each tick of a mob in the random despawn range being removed
+const RANDOM_DESPAWN_CHANCE: f64 = 1.0 / 800.0;
+
+const WANDER_RANGE: f64 = 8.0;
+const WANDER_CHANCE: f64 = 1.0 / 120.0;
+
+/// What a hostile mob is chasing and how it's getting there.
+#[derive(Component, Debug, Default)]
+pub struct HostileAi {
+    target: Option<Entity>,
+    path: VecDeque<BlockPos>,
+    repath_ticks: u32,
+    attack_cooldown: u32,
+    /// Ticks a creeper's fuse has been lit for
+    fuse: Option<u32>,
+}
+
+impl HostileAi {
+    /// Makes the mob go after whoever hurt it
+    pub fn provoke(&mut self, attacker: Entity) {
+        if self.target != Some(attacker) {
+            self.target = Some(attacker);
+            self.path.clear();
+            self.repath_ticks = 0;
+        }
+    }
+
+    fn forget_target(&mut self) {
+        self.target = None;
+        self.path.clear();
+    }
+}
+
+/// Whether a player can be targeted: alive and in Survival or Adventure
+fn is_targetable(game_mode: GameMode, vitals: &Vitals) -> bool {
+    has_vitals(game_mode) && !vitals.dead
+}
+
+/// Walks along the current path, dropping blocks as they're reached
+fn follow_path(ai: &mut HostileAi, body: &mut Body, pos: DVec3, speed: f64) {
+    while let Some(next) = ai.path.front() {
+        let centre = DVec3::new(
+            f64::from(next.x) + 0.5,
+            f64::from(next.y),
+            f64::from(next.z) + 0.5,
+        );
+        let horizontal = DVec3::new(centre.x - pos.x, 0.0, centre.z - pos.z).length();
+
+        if horizontal < 0.4 && (centre.y - pos.y).abs() < 1.0 {
+            ai.path.pop_front();
+            continue;
+        }
+
+        body.walk_towards(pos, centre, speed);
+        return;
+    }
+
+    body.stop();
+}
+
+/// Turns a mob's head and body to `target`, only touching the components
+/// when it actually turned
+fn face(look: &mut Look, head_yaw: &mut HeadYaw, target: Look) {
+    if (look.yaw - target.yaw).abs() > 1.0 || (look.pitch - target.pitch).abs() > 1.0 {
+        *look = target;
+        head_yaw.0 = target.yaw;
+    }
+}
+
+/// Finds targets for hostile mobs and chases them down: zombies hit players
+/// in reach, skeletons shoot from a distance and creepers explode next to
+/// them.
+///
+/// Only players in Survival or Adventure are targeted.
+pub fn think(
+    mut commands: Commands,
+    mut mobs: Query<(
+        Entity,
+        &Mob,
+        &mut HostileAi,
+        &mut Body,
+        &Position,
+        &mut Look,
+        &mut HeadYaw,
+        &EntityLayerId,
+        Option<&mut FuseSpeed>,
+    )>,
+    players: Query<(Entity, &Position, &EntityLayerId, &GameMode, &Vitals), With<Client>>,
+    mut layers: Query<&mut ChunkLayer>,
+    mut damage: EventWriter<DamageEvent>,
+    mut explosions: EventWriter<ExplosionEvent>,
+) {
+    let mut rng = rand::thread_rng();
+
+    for (entity, mob, mut ai, mut body, pos, mut look, mut head_yaw, layer_id, fuse_speed) in
+        &mut mobs
+    {
+        let Ok(mut layer) = layers.get_mut(layer_id.0) else {
+            continue;
+        };
+        let pos = pos.0;
+        let eyes = pos + DVec3::Y * EYE_HEIGHT;
+        let speed = mob.kind.speed();
+
+        ai.attack_cooldown = ai.attack_cooldown.saturating_sub(1);
+
+        if let Some(target) = ai.target {
+            let keep = players
+                .get(target)
+                .is_ok_and(|(_, target_pos, target_layer, game_mode, vitals)| {
+                    target_layer.0 == layer_id.0
+                        && is_targetable(*game_mode, vitals)
+                        && target_pos.0.distance(pos) < FORGET_RANGE
+                });
+            if !keep {
+                ai.forget_target();
+            }
+        }
+
+        if ai.target.is_none() {
+            ai.target = players
+                .iter()
+                .filter(|(_, target_pos, target_layer, game_mode, vitals)| {
+                    target_layer.0 == layer_id.0
+                        && is_targetable(**game_mode, vitals)
+                        && target_pos.0.distance(pos) < FOLLOW_RANGE
+                        && line_of_sight(&layer, eyes, target_pos.0 + DVec3::Y * EYE_HEIGHT)
+                })
+                .min_by(|a, b| a.1 .0.distance(pos).total_cmp(&b.1 .0.distance(pos)))
+                .map(|(target, ..)| target);
+        }
+
+        let target = ai
+            .target
+            .and_then(|target| players.get(target).ok())
+            .map(|(target, target_pos, ..)| (target, target_pos.0));
+
+        let Some((target, target_pos)) = target else {
+            if let Some(mut fuse_speed) = fuse_speed {
+                if fuse_speed.0 != -1 {
+                    fuse_speed.0 = -1;
+                }
+            }
+            ai.fuse = None;
+
+            // Wander around while there's nobody to chase
+            if ai.path.is_empty() && rng.gen_bool(WANDER_CHANCE) {
+                let wander_to = pos
+                    + DVec3::new(
+                        rng.gen_range(-WANDER_RANGE..WANDER_RANGE),
+                        0.0,
+                        rng.gen_range(-WANDER_RANGE..WANDER_RANGE),
+                    );
+                ai.path = find_path(&layer, pos, wander_to, body.height).into();
+            }
+            follow_path(&mut ai, &mut body, pos, speed);
+            if body.walk != DVec3::ZERO {
+                face(&mut look, &mut head_yaw, look_towards(pos, pos + body.walk));
+            }
+            continue;
+        };
+
+        let target_eyes = target_pos + DVec3::Y * EYE_HEIGHT;
+        let distance = pos.distance(target_pos);
+        face(&mut look, &mut head_yaw, look_towards(eyes, target_eyes));
+
+        let in_range = match mob.kind {
+            MobKind::Zombie => distance < MELEE_RANGE,
+            MobKind::Skeleton => {
+                distance < SHOOT_RANGE && line_of_sight(&layer, eyes, target_eyes)
+            }
+            MobKind::Creeper => {
+                distance < FUSE_RANGE || (ai.fuse.is_some() && distance < DEFUSE_RANGE)
+            }
+            _ => false,
+        };
+
+        if !in_range {
+            if ai.fuse.take().is_some() {
+                if let Some(mut fuse_speed) = fuse_speed {
+                    fuse_speed.0 = -1;
+                }
+            }
+
+            ai.repath_ticks = ai.repath_ticks.saturating_sub(1);
+            if ai.path.is_empty() || ai.repath_ticks == 0 {
+                ai.path = find_path(&layer, pos, target_pos, body.height).into();
+                ai.repath_ticks = REPATH_TICKS;
+            }
+            follow_path(&mut ai, &mut body, pos, speed);
+            continue;
+        }
+
+        body.stop();
+        ai.path.clear();
+
+        match mob.kind {
+            MobKind::Zombie => {
+                if ai.attack_cooldown == 0 {
+                    ai.attack_cooldown = MELEE_COOLDOWN;
+                    damage.send(DamageEvent {
+                        entity: target,
+                        amount: MELEE_DAMAGE,
+                        source: DamageSource::Entity(entity),
+                    });
+                }
+            }
+            MobKind::Skeleton => {
+                if ai.attack_cooldown == 0 {
+                    ai.attack_cooldown = SHOOT_COOLDOWN;
+                    shoot_arrow(
+                        &mut commands,
+                        layer_id.0,
+                        entity,
+                        eyes,
+                        target_eyes - DVec3::Y * 0.5,
+                        ARROW_SPEED,
+                        ARROW_DAMAGE,
+                    );
+                    layer.play_sound(
+                        Sound::EntitySkeletonShoot,
+                        SoundCategory::Hostile,
+                        eyes,
+                        1.0,
+                        rng.gen_range(0.8..1.2),
+                    );
+                }
+            }
+            MobKind::Creeper => {
+                let lit = ai.fuse.unwrap_or(0) + 1;
+                if lit == 1 {
+                    if let Some(mut fuse_speed) = fuse_speed {
+                        fuse_speed.0 = 1;
+                    }
+                    layer.play_sound(
+                        Sound::EntityCreeperPrimed,
+                        SoundCategory::Hostile,
+                        pos,
+                        1.0,
+                        0.5,
+                    );
+                }
+
+                if lit >= FUSE_TICKS {
+                    explosions.send(ExplosionEvent {
+                        layer: layer_id.0,
+                        position: pos,
+                        power: CREEPER_POWER,
+                        source: Some(entity),
+                    });
+                    commands.entity(entity).insert(Despawned);
+                    ai.fuse = None;
+                } else {
+                    ai.fuse = Some(lit);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Removes hostile mobs far from every player: straight away beyond 128
+/// blocks, and now and then beyond 32.
+pub fn despawn_far_away(
+    mut commands: Commands,
+    mobs: Query<(Entity, &Position, &EntityLayerId), With<HostileAi>>,
+    players: Query<(&Position, &EntityLayerId), With<Client>>,
+) {
+    let mut rng = rand::thread_rng();
+
+    for (entity, pos, layer_id) in &mobs {
+        let nearest = players
+            .iter()
+            .filter(|(_, player_layer)| player_layer.0 == layer_id.0)
+            .map(|(player_pos, _)| player_pos.0.distance(pos.0))
+            .min_by(f64::total_cmp);
+
+        let despawn = match nearest {
+            None => true,
+            Some(distance) if distance > DESPAWN_RANGE => true,
+            Some(distance) if distance > RANDOM_DESPAWN_RANGE => {
+                rng.gen_bool(RANDOM_DESPAWN_CHANCE)
+            }
+            Some(_) => false,
+        };
+
+        if despawn {
+            commands.entity(entity).insert(Despawned);
+        }
+    }
+}
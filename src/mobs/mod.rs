@@ -0,0 +1,413 @@
+pub mod hostile;
+pub mod passive;
+pub mod pathfinding;
+pub mod physics;
+pub mod projectile;
+pub mod spawning;
+
+use hostile::HostileAi;
+use passive::PassiveAi;
+use physics::Body;
+use valence::{
+    entity::{
+        chicken::ChickenEntityBundle, cow::CowEntityBundle, creeper::CreeperEntityBundle,
+        pig::PigEntityBundle, sheep::SheepEntityBundle, skeleton::SkeletonEntityBundle,
+        zombie::ZombieEntityBundle, HeadYaw, Look,
+    },
+    interact_entity::{EntityInteraction, InteractEntityEvent},
+    inventory::HeldItem,
+    prelude::*,
+    sound::{Sound, SoundCategory},
+    ItemKind,
+};
+
+use crate::{
+    survival::damage::{DamageEvent, DamageSource, HurtCooldown},
+    world,
+};
+
+/// How hard hits push mobs away
+const KNOCKBACK: f64 = 0.4;
+
+/// The mobs the server can spawn and run AI for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MobKind {
+    Cow,
+    Sheep,
+    Pig,
+    Chicken,
+    Zombie,
+    Skeleton,
+    Creeper,
+}
+
+impl MobKind {
+    pub const PASSIVE: [MobKind; 4] = [
+        MobKind::Cow,
+        MobKind::Sheep,
+        MobKind::Pig,
+        MobKind::Chicken,
+    ];
+    pub const HOSTILE: [MobKind; 3] = [MobKind::Zombie, MobKind::Skeleton, MobKind::Creeper];
+
+    /// Looks up a mob by its entity id, without the namespace
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "cow" => Some(MobKind::Cow),
+            "sheep" => Some(MobKind::Sheep),
+            "pig" => Some(MobKind::Pig),
+            "chicken" => Some(MobKind::Chicken),
+            "zombie" => Some(MobKind::Zombie),
+            "skeleton" => Some(MobKind::Skeleton),
+            "creeper" => Some(MobKind::Creeper),
+            _ => None,
+        }
+    }
+
+    pub fn is_hostile(self) -> bool {
+        Self::HOSTILE.contains(&self)
+    }
+
+    /// Width and height of the hitbox
+    fn size(self) -> (f64, f64) {
+        match self {
+            MobKind::Cow => (0.9, 1.4),
+            MobKind::Sheep => (0.9, 1.3),
+            MobKind::Pig => (0.9, 0.9),
+            MobKind::Chicken => (0.4, 0.7),
+            MobKind::Zombie => (0.6, 1.95),
+            MobKind::Skeleton => (0.6, 1.99),
+            MobKind::Creeper => (0.6, 1.7),
+        }
+    }
+
+    pub fn max_health(self) -> f32 {
+        match self {
+            MobKind::Cow | MobKind::Pig => 10.0,
+            MobKind::Sheep => 8.0,
+            MobKind::Chicken => 4.0,
+            MobKind::Zombie | MobKind::Skeleton | MobKind::Creeper => 20.0,
+        }
+    }
+
+    /// Walking speed in blocks per tick
+    pub fn speed(self) -> f64 {
+        match self {
+            MobKind::Cow | MobKind::Sheep => 0.1,
+            MobKind::Pig | MobKind::Chicken => 0.12,
+            MobKind::Zombie => 0.115,
+            MobKind::Skeleton => 0.125,
+            MobKind::Creeper => 0.1,
+        }
+    }
+
+    /// Whether holding `item` makes the mob follow a player
+    pub fn is_food(self, item: ItemKind) -> bool {
+        match self {
+            MobKind::Cow | MobKind::Sheep => item == ItemKind::Wheat,
+            MobKind::Pig => matches!(
+                item,
+                ItemKind::Carrot | ItemKind::Potato | ItemKind::Beetroot
+            ),
+            MobKind::Chicken => matches!(
+                item,
+                ItemKind::WheatSeeds
+                    | ItemKind::MelonSeeds
+                    | ItemKind::PumpkinSeeds
+                    | ItemKind::BeetrootSeeds
+            ),
+            MobKind::Zombie | MobKind::Skeleton | MobKind::Creeper => false,
+        }
+    }
+
+    fn hurt_sound(self) -> Sound {
+        match self {
+            MobKind::Cow => Sound::EntityCowHurt,
+            MobKind::Sheep => Sound::EntitySheepHurt,
+            MobKind::Pig => Sound::EntityPigHurt,
+            MobKind::Chicken => Sound::EntityChickenHurt,
+            MobKind::Zombie => Sound::EntityZombieHurt,
+            MobKind::Skeleton => Sound::EntitySkeletonHurt,
+            MobKind::Creeper => Sound::EntityCreeperHurt,
+        }
+    }
+
+    fn death_sound(self) -> Sound {
+        match self {
+            MobKind::Cow => Sound::EntityCowDeath,
+            MobKind::Sheep => Sound::EntitySheepDeath,
+            MobKind::Pig => Sound::EntityPigDeath,
+            MobKind::Chicken => Sound::EntityChickenDeath,
+            MobKind::Zombie => Sound::EntityZombieDeath,
+            MobKind::Skeleton => Sound::EntitySkeletonDeath,
+            MobKind::Creeper => Sound::EntityCreeperDeath,
+        }
+    }
+
+    /// Spawns the mob with its AI on `layer`
+    pub fn spawn(self, commands: &mut Commands, layer: Entity, pos: DVec3, yaw: f32) -> Entity {
+        let layer = EntityLayerId(layer);
+        let position = Position::new(pos);
+        let look = Look::new(yaw, 0.0);
+        let head_yaw = HeadYaw(yaw);
+
+        let mut entity = match self {
+            MobKind::Cow => commands.spawn(CowEntityBundle {
+                layer,
+                position,
+                look,
+                head_yaw,
+                ..Default::default()
+            }),
+            MobKind::Sheep => commands.spawn(SheepEntityBundle {
+                layer,
+                position,
+                look,
+                head_yaw,
+                ..Default::default()
+            }),
+            MobKind::Pig => commands.spawn(PigEntityBundle {
+                layer,
+                position,
+                look,
+                head_yaw,
+                ..Default::default()
+            }),
+            MobKind::Chicken => commands.spawn(ChickenEntityBundle {
+                layer,
+                position,
+                look,
+                head_yaw,
+                ..Default::default()
+            }),
+            MobKind::Zombie => commands.spawn(ZombieEntityBundle {
+                layer,
+                position,
+                look,
+                head_yaw,
+                ..Default::default()
+            }),
+            MobKind::Skeleton => commands.spawn(SkeletonEntityBundle {
+                layer,
+                position,
+                look,
+                head_yaw,
+                ..Default::default()
+            }),
+            MobKind::Creeper => commands.spawn(CreeperEntityBundle {
+                layer,
+                position,
+                look,
+                head_yaw,
+                ..Default::default()
+            }),
+        };
+
+        let (width, height) = self.size();
+        let mut body = Body::new(width, height);
+        body.slow_fall = self == MobKind::Chicken;
+
+        entity.insert((Mob::new(self), body, HurtCooldown::default()));
+
+        if self.is_hostile() {
+            entity.insert(HostileAi::default());
+        } else {
+            entity.insert(PassiveAi::default());
+        }
+
+        entity.id()
+    }
+}
+
+/// A mob spawned by the server, with its health.
+#[derive(Component, Clone, Debug)]
+pub struct Mob {
+    pub kind: MobKind,
+    pub health: f32,
+    /// Ignores all damage
+    pub invulnerable: bool,
+}
+
+impl Mob {
+    pub fn new(kind: MobKind) -> Self {
+        Self {
+            kind,
+            health: kind.max_health(),
+            invulnerable: false,
+        }
+    }
+}
+
+/// Damage dealt by a melee hit with `item`
+pub fn attack_damage(item: ItemKind) -> f32 {
+    match item {
+        ItemKind::WoodenSword | ItemKind::GoldenSword => 4.0,
+        ItemKind::StoneSword => 5.0,
+        ItemKind::IronSword => 6.0,
+        ItemKind::DiamondSword => 7.0,
+        ItemKind::NetheriteSword => 8.0,
+        ItemKind::WoodenAxe | ItemKind::GoldenAxe => 7.0,
+        ItemKind::StoneAxe | ItemKind::IronAxe | ItemKind::DiamondAxe => 9.0,
+        ItemKind::NetheriteAxe => 10.0,
+        _ => 1.0,
+    }
+}
+
+/// Turns players hitting mobs into damage.
+pub fn player_attacks(
+    mut events: EventReader<InteractEntityEvent>,
+    players: Query<(&Inventory, &HeldItem, &GameMode)>,
+    mobs: Query<(), With<Mob>>,
+    mut damage: EventWriter<DamageEvent>,
+) {
+    for event in events.read() {
+        if !matches!(event.interact, EntityInteraction::Attack) || !mobs.contains(event.entity) {
+            continue;
+        }
+
+        let Ok((inventory, held, game_mode)) = players.get(event.client) else {
+            continue;
+        };
+        if *game_mode == GameMode::Spectator {
+            continue;
+        }
+
+        damage.send(DamageEvent {
+            entity: event.entity,
+            amount: attack_damage(inventory.slot(held.slot()).item),
+            source: DamageSource::Entity(event.client),
+        });
+    }
+}
+
+/// Applies damage to mobs, knocking them back from whatever hurt them and
+/// despawning them when their health runs out.
+///
+/// Passive mobs panic, hostile mobs turn on whoever hit them.
+pub fn hurt_mobs(
+    mut commands: Commands,
+    mut events: EventReader<DamageEvent>,
+    mut mobs: Query<(
+        &mut Mob,
+        &mut Body,
+        &mut HurtCooldown,
+        &Position,
+        &EntityLayerId,
+        Option<&mut PassiveAi>,
+        Option<&mut HostileAi>,
+    )>,
+    positions: Query<&Position>,
+    mut layers: Query<&mut ChunkLayer>,
+) {
+    for event in events.read() {
+        let Ok((mut mob, mut body, mut cooldown, pos, layer_id, passive_ai, hostile_ai)) =
+            mobs.get_mut(event.entity)
+        else {
+            continue;
+        };
+
+        let forced = event.source == DamageSource::Kill;
+        if mob.health <= 0.0 || (!forced && (mob.invulnerable || !cooldown.try_start())) {
+            continue;
+        }
+
+        mob.health -= event.amount;
+
+        let attacker = match event.source {
+            DamageSource::Entity(attacker)
+            | DamageSource::Arrow(attacker)
+            | DamageSource::Explosion(Some(attacker)) => Some(attacker),
+            _ => None,
+        };
+        let attacker_pos = attacker.and_then(|attacker| positions.get(attacker).ok());
+
+        if let Some(attacker_pos) = attacker_pos {
+            body.knock_back(pos.0, attacker_pos.0, KNOCKBACK);
+        }
+
+        if let Some(mut ai) = passive_ai {
+            ai.panic(attacker_pos.map_or(pos.0, |attacker_pos| attacker_pos.0));
+        }
+        if let (Some(mut ai), Some(attacker)) = (hostile_ai, attacker) {
+            ai.provoke(attacker);
+        }
+
+        let dead = mob.health <= 0.0;
+
+        if let Ok(mut layer) = layers.get_mut(layer_id.0) {
+            let sound = if dead {
+                mob.kind.death_sound()
+            } else {
+                mob.kind.hurt_sound()
+            };
+            let category = if mob.kind.is_hostile() {
+                SoundCategory::Hostile
+            } else {
+                SoundCategory::Neutral
+            };
+            layer.play_sound(sound, category, pos.0, 1.0, 1.0);
+        }
+
+        if dead {
+            commands.entity(event.entity).insert(Despawned);
+        }
+    }
+}
+
+/// Mobs aren't saved, so they go away with their chunk
+pub fn despawn_unloaded(
+    mut commands: Commands,
+    mobs: Query<(Entity, &Position, &EntityLayerId), With<Mob>>,
+    layers: Query<&ChunkLayer>,
+) {
+    for (entity, pos, layer_id) in &mobs {
+        let loaded = layers
+            .get(layer_id.0)
+            .is_ok_and(|layer| layer.chunk(world::chunk_pos_at(pos.0)).is_some());
+
+        if !loaded {
+            commands.entity(entity).insert(Despawned);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mobs_are_found_by_entity_id() {
+        assert_eq!(MobKind::from_name("cow"), Some(MobKind::Cow));
+        assert_eq!(MobKind::from_name("creeper"), Some(MobKind::Creeper));
+        assert_eq!(MobKind::from_name("minecraft:cow"), None);
+        assert_eq!(MobKind::from_name("ender_dragon"), None);
+    }
+
+    #[test]
+    fn passive_and_hostile_mobs_dont_overlap() {
+        assert!(MobKind::PASSIVE.iter().all(|kind| !kind.is_hostile()));
+        assert!(MobKind::HOSTILE.iter().all(|kind| kind.is_hostile()));
+    }
+
+    #[test]
+    fn only_passive_mobs_follow_food() {
+        assert!(MobKind::Cow.is_food(ItemKind::Wheat));
+        assert!(MobKind::Pig.is_food(ItemKind::Carrot));
+        assert!(MobKind::Chicken.is_food(ItemKind::WheatSeeds));
+        assert!(!MobKind::Chicken.is_food(ItemKind::Wheat));
+
+        for kind in MobKind::HOSTILE {
+            assert!(!kind.is_food(ItemKind::Wheat));
+            assert!(!kind.is_food(ItemKind::RottenFlesh));
+        }
+    }
+
+    #[test]
+    fn weapons_hit_harder_than_fists() {
+        assert_eq!(attack_damage(ItemKind::Air), 1.0);
+        assert_eq!(attack_damage(ItemKind::Stick), 1.0);
+        assert_eq!(attack_damage(ItemKind::IronSword), 6.0);
+        assert_eq!(attack_damage(ItemKind::NetheriteAxe), 10.0);
+        assert!(attack_damage(ItemKind::DiamondSword) > attack_damage(ItemKind::StoneSword));
+    }
+}
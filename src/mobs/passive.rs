@@ -0,0 +1,168 @@
+use valence::{
+    entity::{HeadYaw, Look},
+    inventory::HeldItem,
+    prelude::*,
+    rand::{self, Rng},
+};
+
+use super::{physics::Body, Mob};
+use crate::commands::teleport::coordinates::look_towards;
+
+/// Ticks a mob runs around for after being hurt
+const PANIC_TICKS: u32 = 100;
+const PANIC_SPEED_MULTIPLIER: f64 = 1.8;
+/// How far away mobs notice players holding their food
+const TEMPT_RANGE: f64 = 10.0;
+/// How close mobs come to players holding their food
+const FOLLOW_DISTANCE: f64 = 2.5;
+/// How far mobs wander from where they stand
+const WANDER_RANGE: f64 = 10.0;
+/// Chance each tick of an idle mob starting to wander
+const WANDER_CHANCE: f64 = 1.0 / 120.0;
+/// Wandering gives up after this many ticks, in case the target can't be
+/// reached
+const WANDER_TIMEOUT: u32 = 200;
+
+#[derive(Clone, Copy, Debug, Default)]
+enum Goal {
+    #[default]
+    Idle,
+    Wander(DVec3),
+    /// Running away from where the mob was hurt
+    Panic(DVec3),
+    /// Following a player holding the mob's food
+    Follow(Entity),
+}
+
+/// What a passive mob is doing, and how long it's been doing it.
+#[derive(Component, Debug, Default)]
+pub struct PassiveAi {
+    goal: Goal,
+    ticks: u32,
+}
+
+impl PassiveAi {
+    /// Makes the mob run away from `from` for a while
+    pub fn panic(&mut self, from: DVec3) {
+        self.goal = Goal::Panic(from);
+        self.ticks = 0;
+    }
+
+    fn set_goal(&mut self, goal: Goal) {
+        self.goal = goal;
+        self.ticks = 0;
+    }
+}
+
+fn horizontal_distance(a: DVec3, b: DVec3) -> f64 {
+    DVec3::new(a.x - b.x, 0.0, a.z - b.z).length()
+}
+
+/// Turns a mob's head and body to `look`, only touching the components when
+/// it actually turned
+fn face(look: &mut Look, head_yaw: &mut HeadYaw, target: Look) {
+    if (look.yaw - target.yaw).abs() > 1.0 || (look.pitch - target.pitch).abs() > 1.0 {
+        *look = target;
+        head_yaw.0 = target.yaw;
+    }
+}
+
+/// Picks what each passive mob does this tick: panicking after being hurt,
+/// following players holding their food, or wandering around.
+pub fn think(
+    mut mobs: Query<(
+        &Mob,
+        &mut PassiveAi,
+        &mut Body,
+        &Position,
+        &mut Look,
+        &mut HeadYaw,
+        &EntityLayerId,
+    )>,
+    players: Query<
+        (Entity, &Position, &EntityLayerId, &GameMode, &Inventory, &HeldItem),
+        With<Client>,
+    >,
+) {
+    let mut rng = rand::thread_rng();
+
+    for (mob, mut ai, mut body, pos, mut look, mut head_yaw, layer) in &mut mobs {
+        let pos = pos.0;
+        let speed = mob.kind.speed();
+        ai.ticks += 1;
+
+        if let Goal::Panic(from) = ai.goal {
+            if ai.ticks > PANIC_TICKS {
+                ai.set_goal(Goal::Idle);
+            } else {
+                // Bumping into a wall sends the mob off in another direction
+                let away = if body.blocked || pos == from {
+                    let angle = rng.gen_range(0.0..std::f64::consts::TAU);
+                    DVec3::new(angle.cos(), 0.0, angle.sin())
+                } else {
+                    pos - from
+                };
+
+                body.walk_towards(pos, pos + away, speed * PANIC_SPEED_MULTIPLIER);
+                if body.blocked {
+                    ai.goal = Goal::Panic(pos - away);
+                }
+                face(&mut look, &mut head_yaw, look_towards(pos, pos + body.walk));
+                continue;
+            }
+        }
+
+        let tempter = players
+            .iter()
+            .filter(|(_, player_pos, player_layer, game_mode, inventory, held)| {
+                player_layer.0 == layer.0
+                    && **game_mode != GameMode::Spectator
+                    && mob.kind.is_food(inventory.slot(held.slot()).item)
+                    && player_pos.0.distance(pos) < TEMPT_RANGE
+            })
+            .min_by(|a, b| a.1 .0.distance(pos).total_cmp(&b.1 .0.distance(pos)))
+            .map(|(player, player_pos, ..)| (player, player_pos.0));
+
+        if let Some((player, player_pos)) = tempter {
+            if !matches!(ai.goal, Goal::Follow(following) if following == player) {
+                ai.set_goal(Goal::Follow(player));
+            }
+
+            if horizontal_distance(pos, player_pos) > FOLLOW_DISTANCE {
+                body.walk_towards(pos, player_pos, speed);
+            } else {
+                body.stop();
+            }
+            face(&mut look, &mut head_yaw, look_towards(pos, player_pos));
+            continue;
+        }
+
+        match ai.goal {
+            Goal::Wander(target) => {
+                if horizontal_distance(pos, target) < 1.0 || ai.ticks > WANDER_TIMEOUT {
+                    body.stop();
+                    ai.set_goal(Goal::Idle);
+                } else {
+                    body.walk_towards(pos, target, speed);
+                    face(&mut look, &mut head_yaw, look_towards(pos, pos + body.walk));
+                }
+            }
+            // Followed players put their food away
+            Goal::Follow(_) | Goal::Panic(_) => {
+                body.stop();
+                ai.set_goal(Goal::Idle);
+            }
+            Goal::Idle => {
+                if rng.gen_bool(WANDER_CHANCE) {
+                    let target = pos
+                        + DVec3::new(
+                            rng.gen_range(-WANDER_RANGE..WANDER_RANGE),
+                            0.0,
+                            rng.gen_range(-WANDER_RANGE..WANDER_RANGE),
+                        );
+                    ai.set_goal(Goal::Wander(target));
+                }
+            }
+        }
+    }
+}
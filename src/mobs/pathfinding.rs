@@ -0,0 +1,146 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+};
+
+use valence::{block::BlockKind, prelude::*};
+
+/// Most blocks searched for one path, so unreachable targets give up quickly
+const MAX_NODES: usize = 1000;
+/// How far a mob will drop down in one step
+const MAX_DROP: i32 = 3;
+const DIRECTIONS: [(i32, i32); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+
+fn is_passable(layer: &ChunkLayer, pos: BlockPos) -> bool {
+    layer.block(pos).is_some_and(|block| {
+        block.state.collision_shapes().len() == 0
+            && !matches!(
+                block.state.to_kind(),
+                BlockKind::Lava | BlockKind::Fire | BlockKind::SoulFire | BlockKind::Cobweb
+            )
+    })
+}
+
+/// A mob `height` blocks tall can stand with its feet in `pos`
+fn can_stand(layer: &ChunkLayer, pos: BlockPos, height: i32) -> bool {
+    let floor = BlockPos::new(pos.x, pos.y - 1, pos.z);
+    let solid_floor = layer
+        .block(floor)
+        .is_some_and(|block| block.state.collision_shapes().len() > 0);
+
+    solid_floor && (0..height).all(|dy| is_passable(layer, BlockPos::new(pos.x, pos.y + dy, pos.z)))
+}
+
+fn distance(a: BlockPos, b: BlockPos) -> u32 {
+    a.x.abs_diff(b.x) + a.y.abs_diff(b.y) + a.z.abs_diff(b.z)
+}
+
+/// The blocks a mob can move to from `pos` in one step, with their cost
+fn neighbours(layer: &ChunkLayer, pos: BlockPos, height: i32) -> Vec<(BlockPos, u32)> {
+    let mut found = vec![];
+
+    for (dx, dz) in DIRECTIONS {
+        let next = BlockPos::new(pos.x + dx, pos.y, pos.z + dz);
+
+        if can_stand(layer, next, height) {
+            found.push((next, 1));
+            continue;
+        }
+
+        // Jumping up a block needs headroom above the mob
+        let up = BlockPos::new(next.x, next.y + 1, next.z);
+        let headroom = is_passable(layer, BlockPos::new(pos.x, pos.y + height, pos.z));
+        if headroom && can_stand(layer, up, height) {
+            found.push((up, 2));
+            continue;
+        }
+
+        // Walking off an edge
+        if !(0..height).all(|dy| is_passable(layer, BlockPos::new(next.x, next.y + dy, next.z))) {
+            continue;
+        }
+        for drop in 1..=MAX_DROP {
+            let down = BlockPos::new(next.x, next.y - drop, next.z);
+            if can_stand(layer, down, height) {
+                found.push((down, 1 + drop as u32));
+                break;
+            }
+            if !is_passable(layer, down) {
+                break;
+            }
+        }
+    }
+
+    found
+}
+
+/// Finds the blocks to walk through to get from `from` to `to` with A*.
+///
+/// If the target can't be reached within the search limit, the path leads
+/// to the closest block found instead, so mobs still move towards it. The
+/// start block isn't included.
+pub fn find_path(layer: &ChunkLayer, from: DVec3, to: DVec3, height: f64) -> Vec<BlockPos> {
+    let height = height.ceil() as i32;
+    let start = BlockPos::new(
+        from.x.floor() as i32,
+        (from.y + 0.01).floor() as i32,
+        from.z.floor() as i32,
+    );
+    let goal = BlockPos::new(
+        to.x.floor() as i32,
+        (to.y + 0.01).floor() as i32,
+        to.z.floor() as i32,
+    );
+
+    // The heap holds indices into `queued`, as block positions can't be
+    // ordered
+    let mut queued = vec![start];
+    let mut open = BinaryHeap::new();
+    let mut came_from: HashMap<BlockPos, BlockPos> = HashMap::new();
+    let mut cost: HashMap<BlockPos, u32> = HashMap::new();
+
+    open.push(Reverse((distance(start, goal), 0)));
+    cost.insert(start, 0);
+
+    let mut closest = (distance(start, goal), start);
+    let mut searched = 0;
+
+    while let Some(Reverse((_, index))) = open.pop() {
+        let pos = queued[index];
+        let remaining = distance(pos, goal);
+        if remaining < closest.0 {
+            closest = (remaining, pos);
+        }
+        if remaining == 0 {
+            break;
+        }
+
+        searched += 1;
+        if searched > MAX_NODES {
+            break;
+        }
+
+        let current = cost[&pos];
+        for (next, step) in neighbours(layer, pos, height) {
+            let next_cost = current + step;
+            if cost.get(&next).is_some_and(|known| *known <= next_cost) {
+                continue;
+            }
+
+            cost.insert(next, next_cost);
+            came_from.insert(next, pos);
+            open.push(Reverse((next_cost + distance(next, goal), queued.len())));
+            queued.push(next);
+        }
+    }
+
+    let mut path = vec![closest.1];
+    while let Some(previous) = came_from.get(path.last().unwrap()) {
+        path.push(*previous);
+    }
+
+    // Drop the start block, the mob is already there
+    path.pop();
+    path.reverse();
+    path
+}
@@ -0,0 +1,265 @@
+use valence::{entity::OnGround, prelude::*};
+
+use crate::world;
+
+/// Downwards acceleration per tick
+const GRAVITY: f64 = 0.08;
+/// Vertical velocity kept each tick
+const DRAG: f64 = 0.98;
+/// Horizontal velocity kept each tick on the ground and in the air
+const GROUND_FRICTION: f64 = 0.546;
+const AIR_FRICTION: f64 = 0.91;
+/// Velocity kept each tick in water or lava
+const LIQUID_DRAG: f64 = 0.8;
+/// Upwards push from liquids, slightly stronger than gravity so mobs float
+const BUOYANCY: f64 = 0.1;
+const JUMP_VELOCITY: f64 = 0.42;
+
+/// A mob's size and motion, moved by `apply_physics` every tick.
+#[derive(Component, Clone, Debug)]
+pub struct Body {
+    pub width: f64,
+    pub height: f64,
+    pub velocity: DVec3,
+    /// The horizontal velocity the mob is trying to walk at
+    pub walk: DVec3,
+    pub on_ground: bool,
+    /// Whether a block stopped the mob moving horizontally last tick
+    pub blocked: bool,
+    /// Falls slowly, like chickens flapping their wings
+    pub slow_fall: bool,
+}
+
+impl Body {
+    pub fn new(width: f64, height: f64) -> Self {
+        Self {
+            width,
+            height,
+            velocity: DVec3::ZERO,
+            walk: DVec3::ZERO,
+            on_ground: false,
+            blocked: false,
+            slow_fall: false,
+        }
+    }
+
+    /// Walks towards `target` at `speed` blocks per tick
+    pub fn walk_towards(&mut self, pos: DVec3, target: DVec3, speed: f64) {
+        let direction = DVec3::new(target.x - pos.x, 0.0, target.z - pos.z);
+        self.walk = direction.normalize_or_zero() * speed;
+    }
+
+    pub fn stop(&mut self) {
+        self.walk = DVec3::ZERO;
+    }
+
+    /// Pushes the mob away from `from`, as when hit
+    pub fn knock_back(&mut self, pos: DVec3, from: DVec3, strength: f64) {
+        let away = DVec3::new(pos.x - from.x, 0.0, pos.z - from.z).normalize_or_zero();
+        self.velocity.x = self.velocity.x / 2.0 + away.x * strength;
+        self.velocity.z = self.velocity.z / 2.0 + away.z * strength;
+        if self.on_ground {
+            self.velocity.y = self.velocity.y.max(strength);
+        }
+    }
+}
+
+/// An axis aligned box in world space
+#[derive(Clone, Copy, Debug)]
+pub struct Bounds {
+    pub min: DVec3,
+    pub max: DVec3,
+}
+
+impl Bounds {
+    /// The box of a body standing at `pos`
+    pub fn of(pos: DVec3, width: f64, height: f64) -> Self {
+        let half = width / 2.0;
+        Self {
+            min: DVec3::new(pos.x - half, pos.y, pos.z - half),
+            max: DVec3::new(pos.x + half, pos.y + height, pos.z + half),
+        }
+    }
+
+    pub fn offset(self, by: DVec3) -> Self {
+        Self {
+            min: self.min + by,
+            max: self.max + by,
+        }
+    }
+
+    /// Grows the box to cover everything it passes through moving by `by`
+    pub fn expand_towards(self, by: DVec3) -> Self {
+        Self {
+            min: self.min + by.min(DVec3::ZERO),
+            max: self.max + by.max(DVec3::ZERO),
+        }
+    }
+}
+
+/// The collision boxes of every block touching `area`
+pub fn block_boxes(layer: &ChunkLayer, area: Bounds) -> Vec<Bounds> {
+    let mut boxes = vec![];
+
+    // Fences and walls stick out above their block
+    for y in (area.min.y.floor() as i32 - 1)..=(area.max.y.floor() as i32) {
+        for z in (area.min.z.floor() as i32)..=(area.max.z.floor() as i32) {
+            for x in (area.min.x.floor() as i32)..=(area.max.x.floor() as i32) {
+                let Some(block) = layer.block(BlockPos::new(x, y, z)) else {
+                    continue;
+                };
+
+                let origin = DVec3::new(f64::from(x), f64::from(y), f64::from(z));
+                boxes.extend(block.state.collision_shapes().map(|shape| Bounds {
+                    min: shape.min() + origin,
+                    max: shape.max() + origin,
+                }));
+            }
+        }
+    }
+
+    boxes
+}
+
+/// How far `body` can move along `axis` before hitting one of `boxes`
+fn clip(boxes: &[Bounds], body: Bounds, axis: usize, mut offset: f64) -> f64 {
+    let others = [(axis + 1) % 3, (axis + 2) % 3];
+
+    for block in boxes {
+        let overlaps = others
+            .iter()
+            .all(|&other| body.min[other] < block.max[other] && body.max[other] > block.min[other]);
+        if !overlaps {
+            continue;
+        }
+
+        if offset > 0.0 && body.max[axis] <= block.min[axis] {
+            offset = offset.min(block.min[axis] - body.max[axis]);
+        } else if offset < 0.0 && body.min[axis] >= block.max[axis] {
+            offset = offset.max(block.max[axis] - body.min[axis]);
+        }
+    }
+
+    offset
+}
+
+/// Moves a box by `delta`, stopping at blocks, and returns how far it
+/// actually moved.
+///
+/// Vertical movement is resolved first, so mobs slide along walls as they
+/// fall.
+pub fn move_and_collide(layer: &ChunkLayer, body: Bounds, delta: DVec3) -> DVec3 {
+    let boxes = block_boxes(layer, body.expand_towards(delta));
+    let mut body = body;
+    let mut moved = DVec3::ZERO;
+
+    for axis in [1, 0, 2] {
+        moved[axis] = clip(&boxes, body, axis, delta[axis]);
+
+        let mut step = DVec3::ZERO;
+        step[axis] = moved[axis];
+        body = body.offset(step);
+    }
+
+    moved
+}
+
+/// Whether `point` is inside the collision box of a block
+pub fn solid_at(layer: &ChunkLayer, point: DVec3) -> bool {
+    let block_pos = BlockPos::new(
+        point.x.floor() as i32,
+        point.y.floor() as i32,
+        point.z.floor() as i32,
+    );
+    let Some(block) = layer.block(block_pos) else {
+        return false;
+    };
+
+    let local = point - point.floor();
+    block.state.collision_shapes().any(|shape| {
+        (0..3).all(|axis| local[axis] >= shape.min()[axis] && local[axis] < shape.max()[axis])
+    })
+}
+
+/// Whether nothing solid is between `from` and `to`
+pub fn line_of_sight(layer: &ChunkLayer, from: DVec3, to: DVec3) -> bool {
+    let steps = (from.distance(to) * 4.0).ceil() as u32;
+
+    (1..steps).all(|step| !solid_at(layer, from.lerp(to, f64::from(step) / f64::from(steps))))
+}
+
+fn in_liquid(layer: &ChunkLayer, pos: DVec3) -> bool {
+    let feet = BlockPos::new(
+        pos.x.floor() as i32,
+        (pos.y + 0.1).floor() as i32,
+        pos.z.floor() as i32,
+    );
+
+    layer.block(feet).is_some_and(|block| block.state.is_liquid())
+}
+
+/// Applies gravity, friction and walking, and moves bodies out of blocks.
+///
+/// Mobs in unloaded chunks are frozen. Walking mobs jump when they bump into
+/// a block.
+pub fn apply_physics(
+    mut bodies: Query<(&mut Body, &mut Position, &mut OnGround, &EntityLayerId)>,
+    layers: Query<&ChunkLayer>,
+) {
+    for (mut body, mut pos, mut on_ground, layer_id) in &mut bodies {
+        let Ok(layer) = layers.get(layer_id.0) else {
+            continue;
+        };
+        if layer.chunk(world::chunk_pos_at(pos.0)).is_none() {
+            continue;
+        }
+
+        let mut velocity = body.velocity;
+
+        if in_liquid(layer, pos.0) {
+            velocity *= LIQUID_DRAG;
+            velocity.y += BUOYANCY;
+        }
+        velocity.y -= GRAVITY;
+
+        if body.slow_fall && !body.on_ground && velocity.y < 0.0 {
+            velocity.y *= 0.6;
+        }
+
+        // Walking pulls the velocity towards the walk speed, knockback fades
+        let friction = if body.on_ground {
+            GROUND_FRICTION
+        } else {
+            AIR_FRICTION
+        };
+        velocity.x = velocity.x * friction + body.walk.x * (1.0 - friction);
+        velocity.z = velocity.z * friction + body.walk.z * (1.0 - friction);
+
+        let moved = move_and_collide(layer, Bounds::of(pos.0, body.width, body.height), velocity);
+
+        let landed = velocity.y < 0.0 && moved.y > velocity.y;
+        let blocked = moved.x != velocity.x || moved.z != velocity.z;
+
+        for axis in 0..3 {
+            if moved[axis] != velocity[axis] {
+                velocity[axis] = 0.0;
+            }
+        }
+        velocity.y *= DRAG;
+
+        if blocked && landed && body.walk != DVec3::ZERO {
+            velocity.y = JUMP_VELOCITY;
+        }
+
+        body.velocity = velocity;
+        body.on_ground = landed;
+        body.blocked = blocked;
+
+        if on_ground.0 != landed {
+            on_ground.0 = landed;
+        }
+        if moved.length_squared() > 1e-8 {
+            pos.set(pos.0 + moved);
+        }
+    }
+}
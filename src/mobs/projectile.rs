@@ -0,0 +1,138 @@
+use valence::{
+    entity::{arrow::ArrowEntityBundle, Look},
+    prelude::*,
+};
+
+use super::physics::{solid_at, Bounds};
+use crate::survival::{
+    damage::{DamageEvent, DamageSource},
+    Vitals,
+};
+
+const ARROW_GRAVITY: f64 = 0.05;
+const ARROW_DRAG: f64 = 0.99;
+/// Arrows that haven't hit anything by now are removed
+const MAX_FLIGHT_TICKS: u32 = 200;
+/// Points checked for hits along each tick of flight
+const HIT_SAMPLES: u32 = 4;
+const PLAYER_WIDTH: f64 = 0.6;
+const PLAYER_HEIGHT: f64 = 1.8;
+
+/// An arrow in flight, moved by `move_projectiles`.
+#[derive(Component, Debug)]
+pub struct Projectile {
+    velocity: DVec3,
+    shooter: Entity,
+    damage: f32,
+    ticks: u32,
+}
+
+/// The look an arrow flying along `velocity` is drawn with
+fn arrow_look(velocity: DVec3) -> Look {
+    let horizontal = (velocity.x * velocity.x + velocity.z * velocity.z).sqrt();
+
+    Look::new(
+        velocity.x.atan2(velocity.z).to_degrees() as f32,
+        velocity.y.atan2(horizontal).to_degrees() as f32,
+    )
+}
+
+/// Fires an arrow from `from` at `target`, aiming a little high to make up
+/// for the drop
+pub fn shoot_arrow(
+    commands: &mut Commands,
+    layer: Entity,
+    shooter: Entity,
+    from: DVec3,
+    target: DVec3,
+    speed: f64,
+    damage: f32,
+) {
+    let delta = target - from;
+    let horizontal = (delta.x * delta.x + delta.z * delta.z).sqrt();
+    let velocity =
+        DVec3::new(delta.x, delta.y + horizontal * 0.2, delta.z).normalize_or_zero() * speed;
+
+    commands.spawn((
+        ArrowEntityBundle {
+            layer: EntityLayerId(layer),
+            position: Position::new(from),
+            look: arrow_look(velocity),
+            ..Default::default()
+        },
+        Projectile {
+            velocity,
+            shooter,
+            damage,
+            ticks: 0,
+        },
+    ));
+}
+
+/// Moves arrows along their arc, hurting the first player they pass through
+/// and removing them when they hit a block.
+pub fn move_projectiles(
+    mut commands: Commands,
+    mut projectiles: Query<(Entity, &mut Projectile, &mut Position, &mut Look, &EntityLayerId)>,
+    players: Query<(Entity, &Position, &EntityLayerId), (With<Vitals>, Without<Projectile>)>,
+    layers: Query<&ChunkLayer>,
+    mut damage: EventWriter<DamageEvent>,
+) {
+    for (entity, mut projectile, mut pos, mut look, layer_id) in &mut projectiles {
+        let Ok(layer) = layers.get(layer_id.0) else {
+            continue;
+        };
+
+        projectile.ticks += 1;
+        if projectile.ticks > MAX_FLIGHT_TICKS {
+            commands.entity(entity).insert(Despawned);
+            continue;
+        }
+
+        let start = pos.0;
+        let end = start + projectile.velocity;
+        let mut hit_block = false;
+        let mut hit_player = None;
+
+        for sample in 1..=HIT_SAMPLES {
+            let point = start.lerp(end, f64::from(sample) / f64::from(HIT_SAMPLES));
+
+            hit_player = players
+                .iter()
+                .find(|(player, player_pos, player_layer)| {
+                    let bounds = Bounds::of(player_pos.0, PLAYER_WIDTH, PLAYER_HEIGHT);
+                    *player != projectile.shooter
+                        && player_layer.0 == layer_id.0
+                        && (0..3).all(|axis| {
+                            point[axis] >= bounds.min[axis] && point[axis] <= bounds.max[axis]
+                        })
+                })
+                .map(|(player, ..)| player);
+
+            if hit_player.is_some() {
+                break;
+            }
+            if solid_at(layer, point) {
+                hit_block = true;
+                break;
+            }
+        }
+
+        if let Some(player) = hit_player {
+            damage.send(DamageEvent {
+                entity: player,
+                amount: projectile.damage,
+                source: DamageSource::Arrow(projectile.shooter),
+            });
+        }
+
+        if hit_player.is_some() || hit_block {
+            commands.entity(entity).insert(Despawned);
+            continue;
+        }
+
+        pos.set(end);
+        projectile.velocity = projectile.velocity * ARROW_DRAG - DVec3::Y * ARROW_GRAVITY;
+        *look = arrow_look(projectile.velocity);
+    }
+}
@@ -0,0 +1,285 @@
+use std::f64::consts::TAU;
+
+use valence::{
+    block::BlockKind,
+    prelude::*,
+    rand::{self, rngs::ThreadRng, Rng},
+};
+
+use super::{Mob, MobKind};
+use crate::{
+    setup::settings::{MobSpawnSettings, Settings},
+    world::{self, BlockView},
+};
+
+/// How far from players mobs spawn, horizontally
+const MIN_SPAWN_DISTANCE: f64 = 24.0;
+const MAX_SPAWN_DISTANCE: f64 = 64.0;
+/// Mobs within this distance of a player count towards their cap
+const PLAYER_CAP_RADIUS: f64 = 128.0;
+/// How far apart the mobs of one group spawn
+const GROUP_SPREAD: f64 = 4.0;
+const MAX_GROUP_SIZE: usize = 4;
+
+fn horizontal_distance(a: DVec3, b: DVec3) -> f64 {
+    DVec3::new(a.x - b.x, 0.0, a.z - b.z).length()
+}
+
+/// Finds where in the column at `pos` a mob can spawn
+type SpawnPoint = fn(&ChunkLayer, DVec3, &mut ThreadRng) -> Option<DVec3>;
+
+/// Where a passive mob can spawn in the column at `pos`: on grass open to
+/// the sky, with room above it
+fn passive_spawn_point(layer: &ChunkLayer, pos: DVec3, _: &mut ThreadRng) -> Option<DVec3> {
+    let surface = world::safe_surface(layer, pos)?;
+
+    let floor = BlockPos::new(
+        surface.x.floor() as i32,
+        surface.y as i32 - 1,
+        surface.z.floor() as i32,
+    );
+    let on_grass = layer
+        .block(floor)
+        .is_some_and(|block| block.state.to_kind() == BlockKind::GrassBlock);
+
+    on_grass.then_some(DVec3::new(pos.x, surface.y, pos.z))
+}
+
+/// Where a hostile mob can spawn in the column at `pos`: on a solid block
+/// at a random height, with room above it and something overhead
+fn hostile_spawn_point(layer: &impl BlockView, pos: DVec3, rng: &mut ThreadRng) -> Option<DVec3> {
+    let x = pos.x.floor() as i32;
+    let z = pos.z.floor() as i32;
+    let min_y = layer.min_y();
+    let max_y = min_y + layer.height() as i32 - 1;
+
+    // The column's chunk isn't loaded
+    layer.state_at(BlockPos::new(x, min_y, z))?;
+
+    let state_at = |y: i32| {
+        layer
+            .state_at(BlockPos::new(x, y, z))
+            .unwrap_or(BlockState::AIR)
+    };
+    let is_open = |state: BlockState| !state.blocks_motion() && !state.is_liquid();
+
+    let start = rng.gen_range(min_y + 1..max_y - 2);
+
+    for y in (min_y + 1..=start).rev() {
+        if !state_at(y - 1).blocks_motion() || !is_open(state_at(y)) || !is_open(state_at(y + 1)) {
+            continue;
+        }
+
+        // Out in the open counts as daylight
+        let covered = (y + 2..=max_y).any(|above| state_at(above).blocks_motion());
+        return covered.then_some(DVec3::new(pos.x, f64::from(y), pos.z));
+    }
+
+    None
+}
+
+/// Tries to spawn one group of mobs around each player, keeping within the
+/// per chunk and per player caps of the mobs' category
+fn spawn_round(
+    commands: &mut Commands,
+    players: &Query<(&Position, &EntityLayerId, &GameMode), With<Client>>,
+    mobs: &Query<(&Mob, &Position, &EntityLayerId)>,
+    layers: &Query<&ChunkLayer>,
+    spawning: &MobSpawnSettings,
+    kinds: &[MobKind],
+    spawn_point: SpawnPoint,
+) {
+    let mut rng = rand::thread_rng();
+
+    // Mobs spawned this round count towards the caps straight away
+    let mut existing: Vec<(DVec3, Entity)> = mobs
+        .iter()
+        .filter(|(mob, ..)| kinds.contains(&mob.kind))
+        .map(|(_, pos, layer)| (pos.0, layer.0))
+        .collect();
+
+    for (player_pos, layer_id, game_mode) in players {
+        if *game_mode == GameMode::Spectator {
+            continue;
+        }
+        let Ok(layer) = layers.get(layer_id.0) else {
+            continue;
+        };
+
+        let near_player = existing
+            .iter()
+            .filter(|(pos, layer)| {
+                *layer == layer_id.0 && horizontal_distance(*pos, player_pos.0) < PLAYER_CAP_RADIUS
+            })
+            .count();
+        if near_player >= spawning.per_player {
+            continue;
+        }
+
+        let angle = rng.gen_range(0.0..TAU);
+        let distance = rng.gen_range(MIN_SPAWN_DISTANCE..MAX_SPAWN_DISTANCE);
+        let centre = player_pos.0 + DVec3::new(angle.cos(), 0.0, angle.sin()) * distance;
+        let chunk = world::chunk_pos_at(centre);
+
+        if layer.chunk(chunk).is_none() {
+            continue;
+        }
+
+        let too_close = players.iter().any(|(other, other_layer, _)| {
+            other_layer.0 == layer_id.0
+                && horizontal_distance(other.0, centre) < MIN_SPAWN_DISTANCE
+        });
+        if too_close {
+            continue;
+        }
+
+        let in_chunk = existing
+            .iter()
+            .filter(|(pos, layer)| *layer == layer_id.0 && world::chunk_pos_at(*pos) == chunk)
+            .count();
+        if in_chunk >= spawning.per_chunk {
+            continue;
+        }
+
+        let kind = kinds[rng.gen_range(0..kinds.len())];
+        let group = rng
+            .gen_range(1..=MAX_GROUP_SIZE)
+            .min(spawning.per_chunk - in_chunk)
+            .min(spawning.per_player - near_player);
+
+        for _ in 0..group {
+            let offset = DVec3::new(
+                rng.gen_range(-GROUP_SPREAD..GROUP_SPREAD),
+                0.0,
+                rng.gen_range(-GROUP_SPREAD..GROUP_SPREAD),
+            );
+
+            // Staying in the chunk keeps the per chunk cap honest
+            let pos = centre + offset;
+            if world::chunk_pos_at(pos) != chunk {
+                continue;
+            }
+
+            let Some(pos) = spawn_point(layer, pos, &mut rng) else {
+                continue;
+            };
+
+            kind.spawn(commands, layer_id.0, pos, rng.gen_range(0.0..360.0));
+            existing.push((pos, layer_id.0));
+        }
+    }
+}
+
+/// Spawns groups of passive mobs on grass around players every few seconds.
+///
+/// There's no light engine, so being the highest block in the column stands
+/// in for being lit.
+pub fn spawn_passive_mobs(
+    mut commands: Commands,
+    mut ticks: Local<u32>,
+    players: Query<(&Position, &EntityLayerId, &GameMode), With<Client>>,
+    mobs: Query<(&Mob, &Position, &EntityLayerId)>,
+    layers: Query<&ChunkLayer>,
+    settings: Res<Settings>,
+) {
+    let Some(spawning) = &settings.passive_mobs else {
+        return;
+    };
+
+    *ticks += 1;
+    if *ticks < spawning.interval {
+        return;
+    }
+    *ticks = 0;
+
+    spawn_round(
+        &mut commands,
+        &players,
+        &mobs,
+        &layers,
+        spawning,
+        &MobKind::PASSIVE,
+        passive_spawn_point,
+    );
+}
+
+/// Spawns groups of zombies, skeletons and creepers in the dark around
+/// players every few seconds.
+///
+/// Anywhere with a block overhead counts as dark, so they spawn in caves and
+/// under overhangs.
+pub fn spawn_hostile_mobs(
+    mut commands: Commands,
+    mut ticks: Local<u32>,
+    players: Query<(&Position, &EntityLayerId, &GameMode), With<Client>>,
+    mobs: Query<(&Mob, &Position, &EntityLayerId)>,
+    layers: Query<&ChunkLayer>,
+    settings: Res<Settings>,
+) {
+    let Some(spawning) = &settings.hostile_mobs else {
+        return;
+    };
+
+    *ticks += 1;
+    if *ticks < spawning.interval {
+        return;
+    }
+    *ticks = 0;
+
+    spawn_round(
+        &mut commands,
+        &players,
+        &mobs,
+        &layers,
+        spawning,
+        &MobKind::HOSTILE,
+        hostile_spawn_point,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::test_blocks::TestBlocks;
+
+    const COLUMN: DVec3 = DVec3::new(0.5, 0.0, 0.5);
+
+    fn spawn_heights(layer: &TestBlocks) -> Vec<Option<f64>> {
+        let mut rng = rand::thread_rng();
+        (0..2000)
+            .map(|_| hostile_spawn_point(layer, COLUMN, &mut rng).map(|pos| pos.y))
+            .collect()
+    }
+
+    #[test]
+    fn hostile_mobs_spawn_under_cover() {
+        let mut layer = TestBlocks::default();
+        layer
+            .set([0, 0, 0], BlockState::STONE)
+            .set([0, 10, 0], BlockState::STONE);
+
+        let heights = spawn_heights(&layer);
+
+        // Only the cave floor is covered, the roof is open to the sky
+        assert!(heights.iter().flatten().all(|y| *y == 1.0));
+        assert!(heights.contains(&Some(1.0)));
+    }
+
+    #[test]
+    fn hostile_mobs_need_room_to_stand() {
+        let mut layer = TestBlocks::default();
+        layer
+            .set([0, 0, 0], BlockState::STONE)
+            .set([0, 2, 0], BlockState::STONE);
+
+        assert!(spawn_heights(&layer).iter().all(|y| y.is_none()));
+    }
+
+    #[test]
+    fn hostile_mobs_dont_spawn_in_the_open() {
+        let mut layer = TestBlocks::default();
+        layer.set([0, 0, 0], BlockState::STONE);
+
+        assert!(spawn_heights(&layer).iter().all(|y| y.is_none()));
+    }
+}
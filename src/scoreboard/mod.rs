@@ -0,0 +1,382 @@
+pub mod teams;
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+};
+
+use serde::{Deserialize, Serialize};
+use valence::{
+    command::parsers::{CommandArg, CommandArgParseError, ParseInput},
+    prelude::*,
+    protocol::packets::play::command_tree_s2c::Parser,
+    scoreboard::{Objective, ObjectiveBundle, ObjectiveDisplay, ObjectiveScores, ScoreboardPosition},
+    text::{Color, IntoText},
+};
+
+use teams::TeamData;
+
+use crate::{server::shutdown::ShutdownEvent, setup::settings::Settings, world::storage};
+
+/// The only criterion supported, scores change only through commands and the
+/// API
+pub const DUMMY_CRITERION: &str = "dummy";
+
+/// How often changed scores are written to disk
+const SAVE_INTERVAL_TICKS: u32 = 20 * 30;
+
+/// Where an objective can be shown on the client.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DisplaySlot {
+    Sidebar,
+    List,
+    BelowName,
+}
+
+impl DisplaySlot {
+    pub const ALL: [DisplaySlot; 3] = [
+        DisplaySlot::Sidebar,
+        DisplaySlot::List,
+        DisplaySlot::BelowName,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DisplaySlot::Sidebar => "sidebar",
+            DisplaySlot::List => "list",
+            DisplaySlot::BelowName => "belowName",
+        }
+    }
+
+    fn position(self) -> ScoreboardPosition {
+        match self {
+            DisplaySlot::Sidebar => ScoreboardPosition::Sidebar,
+            DisplaySlot::List => ScoreboardPosition::List,
+            DisplaySlot::BelowName => ScoreboardPosition::BelowName,
+        }
+    }
+}
+
+impl CommandArg for DisplaySlot {
+    fn parse_arg(input: &mut ParseInput) -> Result<Self, CommandArgParseError> {
+        input.skip_whitespace();
+        let word = input.pop_word();
+
+        DisplaySlot::ALL
+            .into_iter()
+            .find(|slot| slot.name() == word)
+            .ok_or_else(|| CommandArgParseError::InvalidArgument {
+                expected: "sidebar, list or belowName".to_owned(),
+                got: word.to_owned(),
+            })
+    }
+
+    fn display() -> Parser {
+        Parser::ScoreboardSlot
+    }
+}
+
+/// Why a scoreboard change was refused.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ScoreboardError {
+    UnknownObjective(String),
+    DuplicateObjective,
+    UnknownCriterion(String),
+    UnknownTeam(String),
+    DuplicateTeam,
+    UnknownColor(String),
+}
+
+impl ScoreboardError {
+    pub fn to_text(&self) -> Text {
+        let (key, arg) = match self {
+            Self::UnknownObjective(name) => ("arguments.objective.notFound", Some(name)),
+            Self::DuplicateObjective => ("commands.scoreboard.objectives.add.duplicate", None),
+            Self::UnknownCriterion(name) => ("argument.criteria.invalid", Some(name)),
+            Self::UnknownTeam(name) => ("team.notFound", Some(name)),
+            Self::DuplicateTeam => ("commands.team.add.duplicate", None),
+            Self::UnknownColor(name) => ("argument.color.invalid", Some(name)),
+        };
+
+        let with: Vec<Text> = arg.into_iter().map(|arg| arg.clone().into_text()).collect();
+        Text::translate(key, with).color(Color::RED)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ObjectiveData {
+    pub display_name: String,
+    pub criterion: String,
+    /// Scores by holder, a player name or any other string
+    pub scores: BTreeMap<String, i32>,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug)]
+struct ScoreboardData {
+    objectives: BTreeMap<String, ObjectiveData>,
+    display: BTreeMap<DisplaySlot, String>,
+    teams: BTreeMap<String, TeamData>,
+}
+
+/// Every objective, score and team, saved to `scoreboard.json` in the world's
+/// data directory.
+///
+/// Other systems can read and change scores through this resource; changes
+/// are sent to clients and saved on their own.
+#[derive(Resource)]
+pub struct Scoreboard {
+    path: PathBuf,
+    data: ScoreboardData,
+    dirty: bool,
+    /// Bumped whenever a team changes, so clients are only sent teams again
+    /// when there's something new
+    teams_version: u64,
+}
+
+impl Scoreboard {
+    pub fn objective(&self, name: &str) -> Option<&ObjectiveData> {
+        self.data.objectives.get(name)
+    }
+
+    pub fn objectives(&self) -> impl Iterator<Item = (&String, &ObjectiveData)> {
+        self.data.objectives.iter()
+    }
+
+    /// An objective about to be changed, marking the scoreboard for saving
+    fn objective_mut(&mut self, name: &str) -> Result<&mut ObjectiveData, ScoreboardError> {
+        let objective = self
+            .data
+            .objectives
+            .get_mut(name)
+            .ok_or_else(|| ScoreboardError::UnknownObjective(name.to_owned()))?;
+        self.dirty = true;
+
+        Ok(objective)
+    }
+
+    pub fn add_objective(
+        &mut self,
+        name: &str,
+        criterion: &str,
+        display_name: Option<String>,
+    ) -> Result<(), ScoreboardError> {
+        if criterion != DUMMY_CRITERION {
+            return Err(ScoreboardError::UnknownCriterion(criterion.to_owned()));
+        }
+
+        if self.data.objectives.contains_key(name) {
+            return Err(ScoreboardError::DuplicateObjective);
+        }
+
+        self.data.objectives.insert(
+            name.to_owned(),
+            ObjectiveData {
+                display_name: display_name.unwrap_or_else(|| name.to_owned()),
+                criterion: criterion.to_owned(),
+                scores: BTreeMap::new(),
+            },
+        );
+        self.dirty = true;
+
+        Ok(())
+    }
+
+    /// Removes an objective, clearing any display slots showing it
+    pub fn remove_objective(&mut self, name: &str) -> Result<(), ScoreboardError> {
+        self.data
+            .objectives
+            .remove(name)
+            .ok_or_else(|| ScoreboardError::UnknownObjective(name.to_owned()))?;
+        self.data.display.retain(|_, shown| shown != name);
+        self.dirty = true;
+
+        Ok(())
+    }
+
+    /// Shows an objective in a slot, or clears the slot when None
+    pub fn set_display(
+        &mut self,
+        slot: DisplaySlot,
+        name: Option<&str>,
+    ) -> Result<(), ScoreboardError> {
+        match name {
+            Some(name) => {
+                self.objective_mut(name)?;
+                self.data.display.insert(slot, name.to_owned());
+            }
+            None => {
+                self.data.display.remove(&slot);
+            }
+        }
+        self.dirty = true;
+
+        Ok(())
+    }
+
+    pub fn displayed(&self, slot: DisplaySlot) -> Option<&str> {
+        self.data.display.get(&slot).map(String::as_str)
+    }
+
+    pub fn score(&self, objective: &str, holder: &str) -> Option<i32> {
+        self.objective(objective)?.scores.get(holder).copied()
+    }
+
+    pub fn set_score(
+        &mut self,
+        objective: &str,
+        holder: &str,
+        value: i32,
+    ) -> Result<(), ScoreboardError> {
+        self.objective_mut(objective)?
+            .scores
+            .insert(holder.to_owned(), value);
+
+        Ok(())
+    }
+
+    /// Adds to a score, starting from 0, and returns the new value
+    pub fn add_score(
+        &mut self,
+        objective: &str,
+        holder: &str,
+        amount: i32,
+    ) -> Result<i32, ScoreboardError> {
+        let score = self
+            .objective_mut(objective)?
+            .scores
+            .entry(holder.to_owned())
+            .or_insert(0);
+        *score = score.wrapping_add(amount);
+
+        Ok(*score)
+    }
+
+    /// Removes a holder's score from one objective, or from all of them
+    pub fn reset_score(
+        &mut self,
+        objective: Option<&str>,
+        holder: &str,
+    ) -> Result<(), ScoreboardError> {
+        match objective {
+            Some(objective) => {
+                self.objective_mut(objective)?.scores.remove(holder);
+            }
+            None => {
+                for objective in self.data.objectives.values_mut() {
+                    objective.scores.remove(holder);
+                }
+                self.dirty = true;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Everyone with a score in any objective
+    pub fn holders(&self) -> Vec<String> {
+        let mut holders: Vec<_> = self
+            .data
+            .objectives
+            .values()
+            .flat_map(|objective| objective.scores.keys().cloned())
+            .collect();
+        holders.sort();
+        holders.dedup();
+        holders
+    }
+
+    fn save(&mut self) {
+        storage::save(&self.path, &self.data);
+        self.dirty = false;
+    }
+}
+
+pub fn load_scoreboard(mut commands: Commands, settings: Res<Settings>) {
+    let path = storage::data_dir(&settings).join("scoreboard.json");
+
+    commands.insert_resource(Scoreboard {
+        data: storage::load(&path),
+        path,
+        dirty: false,
+        teams_version: 0,
+    });
+}
+
+/// Saves changed scores every so often and when the server stops, since
+/// scores can change every tick.
+pub fn save_scoreboard(
+    mut scoreboard: ResMut<Scoreboard>,
+    mut shutdown: EventReader<ShutdownEvent>,
+    mut ticks: Local<u32>,
+) {
+    *ticks += 1;
+
+    let stopping = shutdown.read().next().is_some();
+    // Saving changes nothing clients see, so it mustn't trigger a resync
+    if scoreboard.dirty && (stopping || *ticks % SAVE_INTERVAL_TICKS == 0) {
+        scoreboard.bypass_change_detection().save();
+    }
+}
+
+/// The client-side objective showing whatever is in a display slot.
+///
+/// Objectives are only sent to clients while displayed, one per slot, so the
+/// same objective can be shown in several slots at once.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct SlotObjective(DisplaySlot);
+
+pub fn sync_objectives(
+    mut commands: Commands,
+    scoreboard: Res<Scoreboard>,
+    mut shown: Query<(Entity, &SlotObjective, &mut ObjectiveDisplay, &mut ObjectiveScores)>,
+    layers: Query<Entity, With<ChunkLayer>>,
+) {
+    if !scoreboard.is_changed() {
+        return;
+    }
+
+    for slot in DisplaySlot::ALL {
+        let objective = scoreboard
+            .displayed(slot)
+            .and_then(|name| scoreboard.objective(name));
+        let existing = shown
+            .iter_mut()
+            .find(|(_, slot_objective, ..)| slot_objective.0 == slot);
+
+        match (objective, existing) {
+            (Some(objective), Some((_, _, mut display, mut scores))) => {
+                display.0 = objective.display_name.clone().into_text();
+                *scores = ObjectiveScores::with_map(to_map(objective));
+            }
+            (Some(objective), None) => {
+                let Some(layer) = layers.iter().next() else {
+                    continue;
+                };
+
+                commands.spawn((
+                    SlotObjective(slot),
+                    ObjectiveBundle {
+                        name: Objective::new(slot.name()),
+                        display: ObjectiveDisplay(objective.display_name.clone().into_text()),
+                        scores: ObjectiveScores::with_map(to_map(objective)),
+                        position: slot.position(),
+                        layer: EntityLayerId(layer),
+                        ..Default::default()
+                    },
+                ));
+            }
+            (None, Some((entity, ..))) => {
+                commands.entity(entity).insert(Despawned);
+            }
+            (None, None) => {}
+        }
+    }
+}
+
+fn to_map(objective: &ObjectiveData) -> HashMap<String, i32> {
+    objective
+        .scores
+        .iter()
+        .map(|(holder, score)| (holder.clone(), *score))
+        .collect()
+}
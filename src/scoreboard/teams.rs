@@ -0,0 +1,342 @@
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet},
+};
+
+use serde::{Deserialize, Serialize};
+use valence::{
+    prelude::*,
+    protocol::{
+        packets::play::{
+            team_s2c::{CollisionRule, Mode, NameTagVisibility, TeamColor, TeamFlags},
+            TeamS2c,
+        },
+        WritePacket,
+    },
+    text::IntoText,
+};
+
+use super::{Scoreboard, ScoreboardError};
+
+/// The colors a team can have, by the names commands use
+pub const COLORS: [&str; 17] = [
+    "black",
+    "dark_blue",
+    "dark_green",
+    "dark_aqua",
+    "dark_red",
+    "dark_purple",
+    "gold",
+    "gray",
+    "dark_gray",
+    "blue",
+    "green",
+    "aqua",
+    "red",
+    "light_purple",
+    "yellow",
+    "white",
+    "reset",
+];
+
+fn team_color(name: &str) -> TeamColor {
+    match name {
+        "black" => TeamColor::Black,
+        "dark_blue" => TeamColor::DarkBlue,
+        "dark_green" => TeamColor::DarkGreen,
+        "dark_aqua" => TeamColor::DarkCyan,
+        "dark_red" => TeamColor::DarkRed,
+        "dark_purple" => TeamColor::Purple,
+        "gold" => TeamColor::Gold,
+        "gray" => TeamColor::Gray,
+        "dark_gray" => TeamColor::DarkGray,
+        "blue" => TeamColor::Blue,
+        "green" => TeamColor::BrightGreen,
+        "aqua" => TeamColor::Cyan,
+        "red" => TeamColor::Red,
+        "light_purple" => TeamColor::Pink,
+        "yellow" => TeamColor::Yellow,
+        "white" => TeamColor::White,
+        _ => TeamColor::Reset,
+    }
+}
+
+/// How a team's members are shown, and who they are.
+///
+/// Members are score holder names, so players are kept by name whether or
+/// not they're online.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TeamData {
+    pub display_name: String,
+    /// One of `COLORS`
+    pub color: String,
+    pub prefix: String,
+    pub suffix: String,
+    pub members: BTreeSet<String>,
+}
+
+/// A team setting `/team modify` can change.
+#[derive(Clone, Debug)]
+pub enum TeamOption {
+    DisplayName(String),
+    Color(String),
+    Prefix(String),
+    Suffix(String),
+}
+
+impl Scoreboard {
+    pub fn team(&self, name: &str) -> Option<&TeamData> {
+        self.data.teams.get(name)
+    }
+
+    pub fn teams(&self) -> impl Iterator<Item = (&String, &TeamData)> {
+        self.data.teams.iter()
+    }
+
+    /// The team `member` is on, if any
+    pub fn team_of(&self, member: &str) -> Option<&str> {
+        self.data
+            .teams
+            .iter()
+            .find(|(_, team)| team.members.contains(member))
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// A team about to be changed, marking the scoreboard for saving
+    fn team_mut(&mut self, name: &str) -> Result<&mut TeamData, ScoreboardError> {
+        let team = self
+            .data
+            .teams
+            .get_mut(name)
+            .ok_or_else(|| ScoreboardError::UnknownTeam(name.to_owned()))?;
+        self.dirty = true;
+        self.teams_version += 1;
+
+        Ok(team)
+    }
+
+    pub fn add_team(
+        &mut self,
+        name: &str,
+        display_name: Option<String>,
+    ) -> Result<(), ScoreboardError> {
+        if self.data.teams.contains_key(name) {
+            return Err(ScoreboardError::DuplicateTeam);
+        }
+
+        self.data.teams.insert(
+            name.to_owned(),
+            TeamData {
+                display_name: display_name.unwrap_or_else(|| name.to_owned()),
+                color: "reset".to_owned(),
+                prefix: String::new(),
+                suffix: String::new(),
+                members: BTreeSet::new(),
+            },
+        );
+        self.dirty = true;
+        self.teams_version += 1;
+
+        Ok(())
+    }
+
+    pub fn remove_team(&mut self, name: &str) -> Result<(), ScoreboardError> {
+        self.data
+            .teams
+            .remove(name)
+            .ok_or_else(|| ScoreboardError::UnknownTeam(name.to_owned()))?;
+        self.dirty = true;
+        self.teams_version += 1;
+
+        Ok(())
+    }
+
+    /// Puts members on a team, taking them off any other team they were on
+    pub fn join_team(&mut self, name: &str, members: &[String]) -> Result<(), ScoreboardError> {
+        self.team_mut(name)?;
+
+        for team in self.data.teams.values_mut() {
+            for member in members {
+                team.members.remove(member);
+            }
+        }
+        self.team_mut(name)?.members.extend(members.iter().cloned());
+
+        Ok(())
+    }
+
+    /// Takes members off whatever team they're on, returning how many were
+    /// on one
+    pub fn leave_team(&mut self, members: &[String]) -> usize {
+        let mut left = 0;
+        for team in self.data.teams.values_mut() {
+            for member in members {
+                if team.members.remove(member) {
+                    left += 1;
+                }
+            }
+        }
+
+        if left > 0 {
+            self.dirty = true;
+            self.teams_version += 1;
+        }
+        left
+    }
+
+    /// Takes everyone off a team, returning how many were on it
+    pub fn empty_team(&mut self, name: &str) -> Result<usize, ScoreboardError> {
+        let team = self.team_mut(name)?;
+        let count = team.members.len();
+        team.members.clear();
+
+        Ok(count)
+    }
+
+    pub fn modify_team(&mut self, name: &str, option: TeamOption) -> Result<(), ScoreboardError> {
+        if let TeamOption::Color(color) = &option {
+            if !COLORS.contains(&color.as_str()) {
+                return Err(ScoreboardError::UnknownColor(color.clone()));
+            }
+        }
+
+        let team = self.team_mut(name)?;
+        match option {
+            TeamOption::DisplayName(display_name) => team.display_name = display_name,
+            TeamOption::Color(color) => team.color = color,
+            TeamOption::Prefix(prefix) => team.prefix = prefix,
+            TeamOption::Suffix(suffix) => team.suffix = suffix,
+        }
+
+        Ok(())
+    }
+}
+
+/// Friendly fire and seeing invisible teammates are always on, as vanilla
+/// teams start out
+fn team_flags() -> TeamFlags {
+    TeamFlags::new()
+        .with_friendly_fire(true)
+        .with_see_invisible_teammates(true)
+}
+
+fn create_packet<'a>(name: &'a str, team: &'a TeamData) -> TeamS2c<'a> {
+    TeamS2c {
+        team_name: name,
+        mode: Mode::CreateTeam {
+            team_display_name: Cow::Owned(team.display_name.clone().into_text()),
+            friendly_flags: team_flags(),
+            name_tag_visibility: NameTagVisibility::Always,
+            collision_rule: CollisionRule::Always,
+            team_color: team_color(&team.color),
+            team_prefix: Cow::Owned(team.prefix.clone().into_text()),
+            team_suffix: Cow::Owned(team.suffix.clone().into_text()),
+            entities: team.members.iter().map(String::as_str).collect(),
+        },
+    }
+}
+
+fn update_packet<'a>(name: &'a str, team: &'a TeamData) -> TeamS2c<'a> {
+    TeamS2c {
+        team_name: name,
+        mode: Mode::UpdateTeamInfo {
+            team_display_name: Cow::Owned(team.display_name.clone().into_text()),
+            friendly_flags: team_flags(),
+            name_tag_visibility: NameTagVisibility::Always,
+            collision_rule: CollisionRule::Always,
+            team_color: team_color(&team.color),
+            team_prefix: Cow::Owned(team.prefix.clone().into_text()),
+            team_suffix: Cow::Owned(team.suffix.clone().into_text()),
+        },
+    }
+}
+
+/// The packets turning the teams clients were sent into the current ones
+fn diff_packets<'a>(
+    old: &'a BTreeMap<String, TeamData>,
+    new: &'a BTreeMap<String, TeamData>,
+) -> Vec<TeamS2c<'a>> {
+    let mut packets = Vec::new();
+
+    for name in old.keys().filter(|name| !new.contains_key(*name)) {
+        packets.push(TeamS2c {
+            team_name: name,
+            mode: Mode::RemoveTeam,
+        });
+    }
+
+    for (name, team) in new {
+        let Some(before) = old.get(name) else {
+            packets.push(create_packet(name, team));
+            continue;
+        };
+
+        let info_changed = before.display_name != team.display_name
+            || before.color != team.color
+            || before.prefix != team.prefix
+            || before.suffix != team.suffix;
+        if info_changed {
+            packets.push(update_packet(name, team));
+        }
+
+        let removed: Vec<&str> = before
+            .members
+            .difference(&team.members)
+            .map(String::as_str)
+            .collect();
+        if !removed.is_empty() {
+            packets.push(TeamS2c {
+                team_name: name,
+                mode: Mode::RemoveEntities { entities: removed },
+            });
+        }
+
+        let added: Vec<&str> = team
+            .members
+            .difference(&before.members)
+            .map(String::as_str)
+            .collect();
+        if !added.is_empty() {
+            packets.push(TeamS2c {
+                team_name: name,
+                mode: Mode::AddEntities { entities: added },
+            });
+        }
+    }
+
+    packets
+}
+
+/// Sends every team to players as they join and keeps everyone up to date
+/// as teams change.
+pub fn sync_teams(
+    scoreboard: Res<Scoreboard>,
+    mut clients: Query<&mut Client>,
+    mut synced: Local<(u64, BTreeMap<String, TeamData>)>,
+) {
+    let teams = &scoreboard.data.teams;
+    let (synced_version, synced) = &mut *synced;
+    let changed = scoreboard.teams_version != *synced_version;
+    let changes = if changed {
+        diff_packets(synced, teams)
+    } else {
+        Vec::new()
+    };
+
+    for mut client in &mut clients {
+        if client.is_added() {
+            for (name, team) in teams {
+                client.write_packet(&create_packet(name, team));
+            }
+        } else {
+            for packet in &changes {
+                client.write_packet(packet);
+            }
+        }
+    }
+
+    if changed {
+        *synced_version = scoreboard.teams_version;
+        *synced = teams.clone();
+    }
+}
@@ -1,9 +1,18 @@
 
+pub mod query;
+pub mod rcon;
+pub mod shutdown;
+pub mod tab_list;
+
+use std::net::SocketAddr;
+
 use valence::{
-    app::{App, AppExit, Startup, Update}, client::despawn_disconnected_clients, prelude::*, weather::{Rain, Thunder}, ChunkLayer
+    app::{App, AppExit, Last, PostStartup, Startup, Update}, client::despawn_disconnected_clients, log::{error, LogPlugin}, prelude::*, protocol::CompressionThreshold, weather::{Rain, Thunder}, ChunkLayer, ServerSettings
 };
 
 use crate::{
+    commands::feedback,
+    console,
     setup::{self, settings::Settings},
     world::{self},
 };
@@ -27,16 +36,93 @@ impl McServer {
             app: App::new(),
         };
 
+        let compression_threshold =
+            CompressionThreshold(sself.settings.compression_threshold.unwrap_or(-1));
+
+        sself.app.insert_resource(ServerSettings {
+            tick_rate: sself.settings.tick_rate,
+            compression_threshold,
+        });
+
         sself.app.insert_resource(NetworkSettings {
+            address: sself.settings.address,
+            max_players: sself.settings.max_players,
             connection_mode: ConnectionMode::Online {
                 prevent_proxy_connections: true,
             },
-            callbacks: setup::login::MyCallbacks.into(),
+            callbacks: setup::login::MyCallbacks {
+                lan_broadcast: sself.settings.lan_broadcast.clone(),
+                motd: sself.settings.motd.clone(),
+            }
+            .into(),
             ..Default::default()
         });
         
         sself.app.insert_resource(sself.settings.to_owned());
-        sself.app.add_plugins(DefaultPlugins);
+
+        if sself.settings.console {
+            let completions = console::completion::ConsoleCompletions::default();
+            let (input, output) = console::start(completions.clone());
+            console::init_logging(output.clone());
+
+            sself
+                .app
+                .add_plugins(DefaultPlugins.build().disable::<LogPlugin>())
+                .insert_resource(completions)
+                .insert_resource(input)
+                .insert_resource(output)
+                .add_systems(Startup, console::spawn_console)
+                .add_systems(PostStartup, console::completion::build_completions)
+                .add_systems(
+                    Update,
+                    (
+                        console::read_console,
+                        console::print_feedback,
+                        console::completion::update_player_names,
+                    ),
+                );
+        } else {
+            sself.app.add_plugins(DefaultPlugins);
+        }
+
+        sself
+            .app
+            .add_event::<feedback::CommandFeedback>()
+            .add_systems(Update, feedback::deliver_feedback);
+
+        if let Some(rcon_settings) = sself.settings.rcon.clone() {
+            let address = SocketAddr::new(sself.settings.address.ip(), rcon_settings.port);
+
+            match rcon::start(address, rcon_settings.password) {
+                Ok(requests) => {
+                    sself.app.insert_resource(requests).add_systems(
+                        Update,
+                        (
+                            rcon::receive_requests,
+                            rcon::collect_feedback,
+                            rcon::send_replies,
+                        )
+                            .chain(),
+                    );
+                }
+                Err(e) => error!("failed to start RCON on {address}: {e}"),
+            }
+        }
+
+        if let Some(query_port) = sself.settings.query_port {
+            let address = SocketAddr::new(sself.settings.address.ip(), query_port);
+            let status = query::QueryStatus::new(&sself.settings);
+
+            match query::start(address, status.clone()) {
+                Ok(()) => {
+                    sself
+                        .app
+                        .insert_resource(status)
+                        .add_systems(Update, query::update_query_players);
+                }
+                Err(e) => error!("failed to start query on {address}: {e}"),
+            }
+        }
         
         if sself.settings.world_path.clone().is_some() {
             sself
@@ -62,18 +148,35 @@ impl McServer {
                     setup::init_clients,
                     ).chain(),
                 change_weather,
-
+                shutdown::begin_shutdown,
+                tab_list::record_tick,
+                tab_list::update_display_names,
+                tab_list::update_header_footer,
             ),
         );
 
+        sself.app.init_resource::<tab_list::TickTimes>();
+
+        sself
+            .app
+            .add_event::<shutdown::ShutdownEvent>()
+            .insert_resource(shutdown::Shutdown::new())
+            .add_systems(Last, shutdown::finish_shutdown);
+
         
 
         sself
     }
 
-    /// Runs the server
+    /// Runs the server until it is stopped
+    ///
+    /// SIGINT and SIGTERM trigger the same graceful shutdown as `/stop`
     pub fn run(&mut self) -> AppExit {
         println!("Starting...");
+        self.app
+            .world()
+            .resource::<shutdown::Shutdown>()
+            .install_signal_handler();
         self.app.run()
     }
 }
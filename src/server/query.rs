@@ -0,0 +1,188 @@
+use std::{
+    collections::HashMap,
+    io,
+    net::{SocketAddr, UdpSocket},
+    sync::{Arc, RwLock},
+    thread,
+    time::{Duration, Instant},
+};
+
+use valence::{
+    log::{info, warn},
+    prelude::*,
+    rand, MINECRAFT_VERSION,
+};
+
+use crate::setup::settings::Settings;
+
+const MAGIC: [u8; 2] = [0xFE, 0xFD];
+const HANDSHAKE: u8 = 9;
+const STAT: u8 = 0;
+
+/// Challenge tokens are only accepted for this long after the handshake
+const TOKEN_LIFETIME: Duration = Duration::from_secs(30);
+
+const SPLITNUM_PADDING: &[u8] = b"splitnum\x00\x80\x00";
+const PLAYER_PADDING: &[u8] = b"\x01player_\x00\x00";
+
+/// The server details reported over query, shared with the listener thread.
+///
+/// The static parts come from the same settings as the server list ping, the
+/// player list is refreshed by `update_query_players`.
+#[derive(Resource, Clone)]
+pub struct QueryStatus(Arc<RwLock<Status>>);
+
+pub struct Status {
+    motd: String,
+    map: String,
+    max_players: usize,
+    host_ip: String,
+    host_port: u16,
+    players: Vec<String>,
+}
+
+impl QueryStatus {
+    pub fn new(settings: &Settings) -> Self {
+        Self(Arc::new(RwLock::new(Status {
+            motd: settings.motd.clone(),
+            map: settings.world_name(),
+            max_players: settings.max_players,
+            host_ip: settings.address.ip().to_string(),
+            host_port: settings.address.port(),
+            players: vec![],
+        })))
+    }
+}
+
+/// Binds the query socket and answers requests on a background thread
+pub fn start(address: SocketAddr, status: QueryStatus) -> io::Result<()> {
+    let socket = UdpSocket::bind(address)?;
+
+    info!("Query listening on {address}");
+
+    thread::spawn(move || {
+        let mut tokens: HashMap<SocketAddr, (i32, Instant)> = HashMap::new();
+        let mut buf = [0; 1500];
+
+        loop {
+            let (len, peer) = match socket.recv_from(&mut buf) {
+                Ok(received) => received,
+                Err(e) => {
+                    warn!("query socket error: {e}");
+                    continue;
+                }
+            };
+
+            tokens.retain(|_, (_, issued)| issued.elapsed() < TOKEN_LIFETIME);
+
+            if let Some(response) = handle_request(&buf[..len], peer, &mut tokens, &status) {
+                let _ = socket.send_to(&response, peer);
+            }
+        }
+    });
+
+    Ok(())
+}
+
+fn handle_request(
+    request: &[u8],
+    peer: SocketAddr,
+    tokens: &mut HashMap<SocketAddr, (i32, Instant)>,
+    status: &QueryStatus,
+) -> Option<Vec<u8>> {
+    if request.len() < 7 || request[0..2] != MAGIC {
+        return None;
+    }
+
+    let kind = request[2];
+    let session = i32::from_be_bytes(request[3..7].try_into().unwrap()) & 0x0F0F_0F0F;
+
+    let mut response = vec![kind];
+    response.extend_from_slice(&session.to_be_bytes());
+
+    match kind {
+        HANDSHAKE => {
+            let token = rand::random::<i32>() & 0x7FFF_FFFF;
+            tokens.insert(peer, (token, Instant::now()));
+
+            push_str(&mut response, &token.to_string());
+        }
+        STAT if request.len() >= 11 => {
+            let token = i32::from_be_bytes(request[7..11].try_into().unwrap());
+            if tokens.get(&peer).map(|(t, _)| *t) != Some(token) {
+                return None;
+            }
+
+            let status = status.0.read().unwrap();
+
+            // Full stat requests are padded with four extra bytes
+            if request.len() >= 15 {
+                write_full_stat(&mut response, &status);
+            } else {
+                write_basic_stat(&mut response, &status);
+            }
+        }
+        _ => return None,
+    }
+
+    Some(response)
+}
+
+fn write_basic_stat(response: &mut Vec<u8>, status: &Status) {
+    push_str(response, &status.motd);
+    push_str(response, "SMP");
+    push_str(response, &status.map);
+    push_str(response, &status.players.len().to_string());
+    push_str(response, &status.max_players.to_string());
+    response.extend_from_slice(&status.host_port.to_le_bytes());
+    push_str(response, &status.host_ip);
+}
+
+fn write_full_stat(response: &mut Vec<u8>, status: &Status) {
+    response.extend_from_slice(SPLITNUM_PADDING);
+
+    let pairs = [
+        ("hostname", status.motd.clone()),
+        ("gametype", "SMP".to_owned()),
+        ("game_id", "MINECRAFT".to_owned()),
+        ("version", MINECRAFT_VERSION.to_owned()),
+        // `<server>: <plugin>; <plugin>` on modded servers, empty like vanilla
+        // as there are no plugins
+        ("plugins", String::new()),
+        ("map", status.map.clone()),
+        ("numplayers", status.players.len().to_string()),
+        ("maxplayers", status.max_players.to_string()),
+        ("hostport", status.host_port.to_string()),
+        ("hostip", status.host_ip.clone()),
+    ];
+
+    for (key, value) in pairs {
+        push_str(response, key);
+        push_str(response, &value);
+    }
+    response.push(0);
+
+    response.extend_from_slice(PLAYER_PADDING);
+    for player in &status.players {
+        push_str(response, player);
+    }
+    response.push(0);
+}
+
+fn push_str(buf: &mut Vec<u8>, s: &str) {
+    buf.extend_from_slice(s.as_bytes());
+    buf.push(0);
+}
+
+pub fn update_query_players(
+    added: Query<(), Added<Client>>,
+    mut removed: RemovedComponents<Client>,
+    usernames: Query<&Username, With<Client>>,
+    status: Res<QueryStatus>,
+) {
+    if added.is_empty() && removed.read().next().is_none() {
+        return;
+    }
+
+    status.0.write().unwrap().players = usernames.iter().map(|name| name.0.clone()).collect();
+}
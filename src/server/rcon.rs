@@ -0,0 +1,289 @@
+use std::{
+    io::{self, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use flume::{Receiver, Sender};
+use valence::{
+    command::{scopes::CommandScopes, CommandExecutionEvent},
+    log::{info, warn},
+    prelude::*,
+};
+
+use crate::commands::feedback::{plain_text, CommandFeedback};
+
+const LOGIN: i32 = 3;
+const EXEC_COMMAND: i32 = 2;
+const AUTH_RESPONSE: i32 = 2;
+const RESPONSE_VALUE: i32 = 0;
+
+/// Clients must not send packets larger than this
+const MAX_REQUEST_LEN: i32 = 1460;
+/// Responses longer than this are split into several packets
+const MAX_RESPONSE_LEN: usize = 4096;
+
+/// Connections beyond this many at once are closed straight away
+const MAX_CONNECTIONS: usize = 8;
+/// How long a client that sent the wrong password waits before it's
+/// disconnected, to slow down guessing
+const AUTH_FAILURE_DELAY: Duration = Duration::from_secs(2);
+/// Connections that send nothing for this long are closed, so idle clients
+/// don't hold on to a slot
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// A command is answered once this many ticks pass without more feedback
+const SETTLE_TICKS: u32 = 2;
+/// Commands that never send feedback are answered with nothing after this
+/// many ticks
+const REPLY_TIMEOUT_TICKS: u32 = 20 * 5;
+
+/// A command received over RCON, waiting to be executed.
+pub struct RconRequest {
+    command: String,
+    reply: Sender<String>,
+}
+
+#[derive(Resource)]
+pub struct RconRequests(Receiver<RconRequest>);
+
+/// The executor for a single RCON command. Feedback sent to it is collected
+/// and returned as the response, then the entity is despawned.
+#[derive(Component)]
+pub struct RconExecutor {
+    reply: Sender<String>,
+    output: Vec<String>,
+    ticks: u32,
+    /// The tick the latest feedback arrived on
+    last_feedback: Option<u32>,
+}
+
+/// Counts a connection as open until it's dropped
+struct ConnectionSlot(Arc<AtomicUsize>);
+
+impl ConnectionSlot {
+    fn take(open: &Arc<AtomicUsize>) -> Option<Self> {
+        open.fetch_update(Ordering::AcqRel, Ordering::Acquire, |count| {
+            (count < MAX_CONNECTIONS).then_some(count + 1)
+        })
+        .ok()
+        .map(|_| Self(open.clone()))
+    }
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Binds the RCON listener and accepts connections on a background thread,
+/// each handled on a thread of its own up to `MAX_CONNECTIONS`
+pub fn start(address: SocketAddr, password: String) -> io::Result<RconRequests> {
+    let listener = TcpListener::bind(address)?;
+    let (sender, receiver) = flume::unbounded();
+    let open = Arc::new(AtomicUsize::new(0));
+
+    info!("RCON listening on {address}");
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else {
+                continue;
+            };
+            let peer = stream.peer_addr().ok();
+
+            let Some(slot) = ConnectionSlot::take(&open) else {
+                warn!("RCON connection {peer:?} refused, too many connections");
+                continue;
+            };
+
+            let sender = sender.clone();
+            let password = password.clone();
+
+            thread::spawn(move || {
+                let _slot = slot;
+                if let Err(e) = handle_connection(stream, &password, &sender) {
+                    if e.kind() != io::ErrorKind::UnexpectedEof {
+                        warn!("RCON connection {peer:?} closed: {e}");
+                    }
+                }
+            });
+        }
+    });
+
+    Ok(RconRequests(receiver))
+}
+
+fn handle_connection(
+    mut stream: TcpStream,
+    password: &str,
+    requests: &Sender<RconRequest>,
+) -> io::Result<()> {
+    stream.set_read_timeout(Some(IDLE_TIMEOUT))?;
+    let mut authenticated = false;
+
+    loop {
+        let (id, kind, body) = read_packet(&mut stream)?;
+
+        match kind {
+            LOGIN => {
+                if !password.is_empty() && body == password {
+                    authenticated = true;
+                    write_packet(&mut stream, id, AUTH_RESPONSE, "")?;
+                } else {
+                    write_packet(&mut stream, -1, AUTH_RESPONSE, "")?;
+                    thread::sleep(AUTH_FAILURE_DELAY);
+                    return Ok(());
+                }
+            }
+            EXEC_COMMAND if authenticated => {
+                let (reply, response) = flume::bounded(1);
+
+                requests
+                    .send(RconRequest {
+                        command: body,
+                        reply,
+                    })
+                    .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "server stopped"))?;
+
+                let output = response
+                    .recv_timeout(Duration::from_secs(10))
+                    .unwrap_or_default();
+
+                for chunk in split_response(&output) {
+                    write_packet(&mut stream, id, RESPONSE_VALUE, chunk)?;
+                }
+            }
+            _ => write_packet(&mut stream, -1, AUTH_RESPONSE, "")?,
+        }
+    }
+}
+
+fn read_packet(stream: &mut TcpStream) -> io::Result<(i32, i32, String)> {
+    let mut int = [0; 4];
+
+    stream.read_exact(&mut int)?;
+    let len = i32::from_le_bytes(int);
+
+    if !(10..=MAX_REQUEST_LEN).contains(&len) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid packet length {len}"),
+        ));
+    }
+
+    let mut payload = vec![0; len as usize];
+    stream.read_exact(&mut payload)?;
+
+    let id = i32::from_le_bytes(payload[0..4].try_into().unwrap());
+    let kind = i32::from_le_bytes(payload[4..8].try_into().unwrap());
+
+    // The body is terminated by two null bytes
+    let body = &payload[8..payload.len() - 2];
+    let body = String::from_utf8_lossy(body).into_owned();
+
+    Ok((id, kind, body))
+}
+
+fn write_packet(stream: &mut TcpStream, id: i32, kind: i32, body: &str) -> io::Result<()> {
+    let len = 4 + 4 + body.len() + 2;
+
+    let mut packet = Vec::with_capacity(4 + len);
+    packet.extend_from_slice(&(len as i32).to_le_bytes());
+    packet.extend_from_slice(&id.to_le_bytes());
+    packet.extend_from_slice(&kind.to_le_bytes());
+    packet.extend_from_slice(body.as_bytes());
+    packet.extend_from_slice(&[0, 0]);
+
+    stream.write_all(&packet)
+}
+
+fn split_response(output: &str) -> Vec<&str> {
+    if output.is_empty() {
+        return vec![""];
+    }
+
+    let mut chunks = vec![];
+    let mut rest = output;
+
+    while !rest.is_empty() {
+        let mut end = rest.len().min(MAX_RESPONSE_LEN);
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+
+        let (chunk, tail) = rest.split_at(end);
+        chunks.push(chunk);
+        rest = tail;
+    }
+
+    chunks
+}
+
+/// Spawns an executor for each received command and executes it.
+pub fn receive_requests(
+    mut commands: Commands,
+    requests: Res<RconRequests>,
+    mut events: EventWriter<CommandExecutionEvent>,
+) {
+    for request in requests.0.try_iter() {
+        let command = request.command.trim().trim_start_matches('/');
+
+        info!("RCON executed: {command}");
+
+        let mut scopes = CommandScopes::default();
+        scopes.add("admin");
+
+        let executor = commands
+            .spawn((
+                RconExecutor {
+                    reply: request.reply,
+                    output: vec![],
+                    ticks: 0,
+                    last_feedback: None,
+                },
+                scopes,
+            ))
+            .id();
+
+        events.send(CommandExecutionEvent {
+            command: command.to_owned(),
+            executor,
+        });
+    }
+}
+
+pub fn collect_feedback(
+    mut events: EventReader<CommandFeedback>,
+    mut executors: Query<&mut RconExecutor>,
+) {
+    for event in events.read() {
+        if let Ok(mut executor) = executors.get_mut(event.executor) {
+            executor.output.push(plain_text(&event.message));
+            executor.last_feedback = Some(executor.ticks);
+        }
+    }
+}
+
+/// Answers commands once their feedback stops arriving, or once they've
+/// timed out without any.
+pub fn send_replies(mut commands: Commands, mut executors: Query<(Entity, &mut RconExecutor)>) {
+    for (entity, mut executor) in &mut executors {
+        executor.ticks += 1;
+
+        let done = match executor.last_feedback {
+            Some(tick) => executor.ticks - tick >= SETTLE_TICKS,
+            None => executor.ticks >= REPLY_TIMEOUT_TICKS,
+        };
+        if done {
+            let _ = executor.reply.send(executor.output.join("\n"));
+            commands.entity(entity).despawn();
+        }
+    }
+}
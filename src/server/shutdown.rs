@@ -0,0 +1,175 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use valence::{
+    app::AppExit,
+    log::info,
+    prelude::*,
+    protocol::{packets::play::DisconnectS2c, WritePacket},
+    text::{Color, IntoText},
+};
+
+use crate::world::chunks::{ChunkWorkers, GameState};
+
+/// The number of ticks to wait after disconnecting clients before exiting,
+/// giving the network tasks time to flush the disconnect packets.
+const DRAIN_TICKS: u32 = 10;
+
+/// Sent once when the server begins shutting down.
+///
+/// Systems that need to persist state should save it when they receive this.
+#[derive(Event, Clone, Debug)]
+pub struct ShutdownEvent {
+    pub reason: String,
+}
+
+enum ShutdownState {
+    Running,
+    Requested { reason: String },
+    Stopping { reason: String, ticks_left: u32 },
+}
+
+/// Tracks whether the server has been asked to stop, either by the `/stop`
+/// command or by a SIGINT/SIGTERM.
+#[derive(Resource)]
+pub struct Shutdown {
+    signal: Arc<AtomicBool>,
+    state: ShutdownState,
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        Self {
+            signal: Arc::new(AtomicBool::new(false)),
+            state: ShutdownState::Running,
+        }
+    }
+
+    /// Asks the server to stop at the start of the next tick
+    ///
+    /// Does nothing if a shutdown is already in progress
+    pub fn request(&mut self, reason: impl Into<String>) {
+        if let ShutdownState::Running = self.state {
+            self.state = ShutdownState::Requested {
+                reason: reason.into(),
+            };
+        }
+    }
+
+    pub fn is_stopping(&self) -> bool {
+        !matches!(self.state, ShutdownState::Running)
+    }
+
+    /// Installs a SIGINT/SIGTERM handler that requests a shutdown
+    ///
+    /// A second signal while already stopping exits immediately.
+    pub fn install_signal_handler(&self) {
+        let signal = self.signal.clone();
+
+        let result = ctrlc::set_handler(move || {
+            if signal.swap(true, Ordering::SeqCst) {
+                eprintln!("Forcing exit");
+                std::process::exit(130);
+            }
+        });
+
+        if let Err(e) = result {
+            eprintln!("failed to install signal handler: {e}");
+        }
+    }
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Turns a pending shutdown into a `ShutdownEvent` and keeps disconnecting
+/// clients (including ones that join mid-shutdown) until the drain is over.
+pub fn begin_shutdown(
+    mut commands: Commands,
+    mut shutdown: ResMut<Shutdown>,
+    mut clients: Query<(Entity, &mut Client)>,
+    mut events: EventWriter<ShutdownEvent>,
+) {
+    if shutdown.signal.load(Ordering::SeqCst) {
+        shutdown.request("Server closed");
+    }
+
+    let state = std::mem::replace(&mut shutdown.state, ShutdownState::Running);
+
+    shutdown.state = match state {
+        ShutdownState::Running => ShutdownState::Running,
+        ShutdownState::Requested { reason } => {
+            info!("Stopping the server: {reason}");
+
+            for (entity, mut client) in &mut clients {
+                client.send_chat_message(format!("Server stopping: {reason}").color(Color::RED));
+                disconnect(&mut commands, entity, &mut client, reason.clone());
+            }
+
+            events.send(ShutdownEvent {
+                reason: reason.clone(),
+            });
+
+            ShutdownState::Stopping {
+                reason,
+                ticks_left: DRAIN_TICKS,
+            }
+        }
+        ShutdownState::Stopping { reason, ticks_left } => {
+            for (entity, mut client) in &mut clients {
+                disconnect(&mut commands, entity, &mut client, reason.clone());
+            }
+
+            ShutdownState::Stopping {
+                reason,
+                ticks_left: ticks_left.saturating_sub(1),
+            }
+        }
+    };
+}
+
+/// Disconnects a client, showing them the reason.
+///
+/// The packet is flushed straight away, as removing the `Client` component
+/// closes the connection before the next regular flush.
+pub fn disconnect<'a>(
+    commands: &mut Commands,
+    entity: Entity,
+    client: &mut Client,
+    reason: impl IntoText<'a>,
+) {
+    client.write_packet(&DisconnectS2c {
+        reason: reason.into_cow_text(),
+    });
+    let _ = client.flush_packets();
+    commands.entity(entity).remove::<Client>();
+}
+
+/// Stops the chunk workers and exits the app once the drain is over.
+pub fn finish_shutdown(world: &mut World) {
+    let done = matches!(
+        world.resource::<Shutdown>().state,
+        ShutdownState::Stopping { ticks_left: 0, .. }
+    );
+
+    if !done {
+        return;
+    }
+
+    // Dropping the game state drops the only sender of the pending queue,
+    // which lets the workers fall out of their receive loop.
+    world.remove_resource::<GameState>();
+
+    if let Some(workers) = world.remove_resource::<ChunkWorkers>() {
+        let count = workers.join();
+        info!("Stopped {count} chunk workers");
+    }
+
+    info!("Server stopped");
+    world.send_event(AppExit::Success);
+}
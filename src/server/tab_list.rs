@@ -0,0 +1,110 @@
+use std::{collections::VecDeque, time::Instant};
+
+use valence::{
+    command::scopes::CommandScopes,
+    player_list::{DisplayName, PlayerList},
+    prelude::*,
+    text::IntoText,
+};
+
+use crate::{chat, setup::settings::Settings};
+
+/// How many ticks the TPS is averaged over, and how often the header and
+/// footer are refreshed
+const SAMPLE_TICKS: usize = 20;
+
+/// When recent ticks started, to measure the real tick rate.
+#[derive(Resource, Default)]
+pub struct TickTimes(VecDeque<Instant>);
+
+impl TickTimes {
+    /// Ticks per second over the last second or so, capped at the target
+    /// rate
+    pub fn tps(&self, settings: &Settings) -> f64 {
+        let target = f64::from(settings.tick_rate.get());
+
+        let (Some(first), Some(last)) = (self.0.front(), self.0.back()) else {
+            return target;
+        };
+
+        let elapsed = last.duration_since(*first).as_secs_f64();
+        if elapsed == 0.0 {
+            return target;
+        }
+
+        ((self.0.len() - 1) as f64 / elapsed).min(target)
+    }
+}
+
+pub fn record_tick(mut ticks: ResMut<TickTimes>) {
+    ticks.0.push_back(Instant::now());
+
+    if ticks.0.len() > SAMPLE_TICKS + 1 {
+        ticks.0.pop_front();
+    }
+}
+
+/// Shows players in the tab list with their chat prefix.
+///
+/// Valence keeps the game mode and latency of each entry up to date on its
+/// own.
+pub fn update_display_names(
+    mut players: Query<
+        (&Username, &CommandScopes, &mut DisplayName),
+        Or<(Added<Client>, Changed<CommandScopes>)>,
+    >,
+    settings: Res<Settings>,
+) {
+    for (username, scopes, mut display_name) in &mut players {
+        let name = chat::prefix(&settings, scopes).add_child(username.0.clone());
+        display_name.0 = Some(name);
+    }
+}
+
+/// Fills in `{tps}`, `{online}`, `{max}`, `{world}` and `{motd}`
+fn fill_placeholders(format: &str, tps: f64, online: usize, settings: &Settings) -> String {
+    format
+        .replace("{tps}", &format!("{tps:.1}"))
+        .replace("{online}", &online.to_string())
+        .replace("{max}", &settings.max_players.to_string())
+        .replace("{world}", &settings.world_name())
+        .replace("{motd}", &settings.motd)
+}
+
+/// Refreshes the header and footer once a second, so the live placeholders
+/// stay current without resending them every tick.
+pub fn update_header_footer(
+    mut player_list: ResMut<PlayerList>,
+    ticks: Res<TickTimes>,
+    clients: Query<(), With<Client>>,
+    settings: Res<Settings>,
+    mut tick: Local<usize>,
+    mut last: Local<(String, String)>,
+) {
+    *tick += 1;
+    if *tick % SAMPLE_TICKS != 1 {
+        return;
+    }
+
+    let tps = ticks.tps(&settings);
+    let online = clients.iter().len();
+    let fill = |format: &Option<String>| {
+        format
+            .as_deref()
+            .map(|format| fill_placeholders(format, tps, online, &settings))
+            .unwrap_or_default()
+    };
+
+    let header = fill(&settings.tab_header);
+    let footer = fill(&settings.tab_footer);
+
+    if last.0 != header {
+        player_list.set_header(header.clone().into_text());
+        last.0 = header;
+    }
+
+    if last.1 != footer {
+        player_list.set_footer(footer.clone().into_text());
+        last.1 = footer;
+    }
+}
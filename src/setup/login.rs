@@ -9,7 +9,12 @@ use valence::{
     MINECRAFT_VERSION,
 };
 
-pub struct MyCallbacks;
+pub struct MyCallbacks {
+    /// The message broadcast on the LAN, or None to stay silent
+    pub lan_broadcast: Option<String>,
+    /// The description shown in the server list
+    pub motd: String,
+}
 
 #[async_trait]
 impl NetworkCallbacks for MyCallbacks {
@@ -23,9 +28,9 @@ impl NetworkCallbacks for MyCallbacks {
 
         ServerListPing::Respond {
             online_players: shared.player_count().load(Ordering::Relaxed) as i32,
-            max_players: 420 as i32,
+            max_players: shared.max_players() as i32,
             player_sample: vec![],
-            description: "A Valence Server".into_text(),
+            description: self.motd.clone().into_text(),
             favicon_png: &[],
             version_name: MINECRAFT_VERSION.to_owned(),
             protocol: PROTOCOL_VERSION,
@@ -33,7 +38,10 @@ impl NetworkCallbacks for MyCallbacks {
     }
 
     async fn broadcast_to_lan(&self, _shared: &SharedNetworkState) -> BroadcastToLan {
-        BroadcastToLan::Enabled("Rust Minecraft Server!".into())
+        match &self.lan_broadcast {
+            Some(motd) => BroadcastToLan::Enabled(motd.clone().into()),
+            None => BroadcastToLan::Disabled,
+        }
     }
     
     async fn login(
@@ -6,19 +6,27 @@ use std::{collections::HashMap, sync::Arc, thread, time::SystemTime};
 use noise::SuperSimplex;
 use settings::Settings;
 use valence::{
-    anvil::AnvilLevel, command::{scopes::CommandScopes, CommandScopeRegistry}, log::info, op_level::OpLevel, prelude::*, spawn::IsFlat
+    anvil::AnvilLevel, command::{scopes::CommandScopes, CommandScopeRegistry}, entity::Look, log::info, op_level::OpLevel, prelude::*, spawn::{IsFlat, RespawnPosition}
 };
 
-use crate::world::{self, chunks::{ChunkWorkerState, GameState}};
+use crate::{
+    commands::teleport::apply::{TeleportCause, TeleportEvent},
+    world::{self, chunks::{ChunkWorkerState, ChunkWorkers, GameState}, spawn::{LayerName, WorldSpawn}},
+};
 
 pub fn init_clients(
     mut clients: Query<
         (
+            Entity,
+            &Username,
+            &UniqueId,
             &mut Client,
             &mut EntityLayerId,
             &mut VisibleChunkLayer,
             &mut VisibleEntityLayers,
             &mut Position,
+            &mut Look,
+            &mut RespawnPosition,
             &mut GameMode,
             &mut OpLevel,
             &mut CommandScopes,
@@ -26,39 +34,67 @@ pub fn init_clients(
         ),
         Added<Client>,
     >,
-    layers: Query<Entity, With<ChunkLayer>>,
+    layers: Query<(Entity, Option<&WorldSpawn>), With<ChunkLayer>>,
     settings: Res<Settings>,
+    mut teleports: EventWriter<TeleportEvent>,
 ) {
     for (
+        client_entity,
+        username,
+        uuid,
         mut client,
         mut layer_id,
         mut visible_chunk_layer,
         mut visible_entity_layers,
         mut pos,
+        mut look,
+        mut respawn_position,
         mut game_mode,
         mut op_level,
         mut permissions,
         mut is_flat,
     ) in &mut clients
     {
-        let layer = layers.single();
+        let (layer, spawn) = layers.single();
+        let (spawn_point, spawn_look) =
+            spawn.map_or((settings.spawn_point, Look::default()), |spawn| (spawn.position, spawn.look));
 
         layer_id.0 = layer;
         visible_chunk_layer.0 = layer;
         visible_entity_layers.0.insert(layer);
-        pos.set(settings.spawn_point);
+        pos.set(spawn_point);
+        *look = spawn_look;
+        respawn_position.pos = BlockPos::new(
+            spawn_point.x.floor() as i32,
+            spawn_point.y.floor() as i32,
+            spawn_point.z.floor() as i32,
+        );
+        respawn_position.yaw = spawn_look.yaw;
+
+        // Lands the player on the surface once the spawn chunk has loaded
+        teleports.send(TeleportEvent {
+            safe: true,
+            cause: TeleportCause::Join,
+            ..TeleportEvent::new(client_entity, spawn_point)
+        });
         *game_mode = settings.default_gamemode;
-        op_level.set(4);
-        
+
         if settings.world_path.clone().is_some() {
             is_flat.0 = false;
         } else {
             is_flat.0 = true;
         }
 
-        permissions.add("admin");
-
-        client.send_chat_message("Welcome to a Minecraft Server written in Rust!".italic());
+        let is_op = settings.ops.iter().any(|op| {
+            op.eq_ignore_ascii_case(&username.0) || op.eq_ignore_ascii_case(&uuid.0.to_string())
+        });
+        if is_op {
+            op_level.set(4);
+            permissions.add("admin");
+        } else {
+            op_level.set(0);
+            permissions.add("player");
+        }
     }
 }
 
@@ -107,7 +143,7 @@ pub fn setup(
             elapsed_chunk.as_millis()
         );
 
-        commands.spawn((layer, level));
+        commands.spawn((layer, level, LayerName("overworld".into())));
     } else {
         info!("Default World generation starting!");
         let seconds_per_day = 86_400;
@@ -133,10 +169,13 @@ pub fn setup(
         });
     
         let current_time = std::time::SystemTime::now();
+        let mut handles = vec![];
         for _ in 0..settings.chunk_thread_count.unwrap_or(thread::available_parallelism().unwrap().get() / 2) {
             let state = state.clone();
-            thread::spawn(move || world::chunks::chunk_worker(state));
+            handles.push(thread::spawn(move || world::chunks::chunk_worker(state)));
         }
+
+        commands.insert_resource(ChunkWorkers { state, handles });
     
         commands.insert_resource(GameState {
             pending: HashMap::new(),
@@ -144,13 +183,34 @@ pub fn setup(
             receiver: finished_receiver,
         });
     
-        commands.spawn(layer);
+        commands.spawn((layer, LayerName("overworld".into())));
         let elapsed = current_time.elapsed().unwrap();
         info!("Chunk state up in {:.2?}ms", elapsed.as_millis());
     }
 
     command_scopes.link("admin", "command.teleport");
     command_scopes.link("admin", "command.gamemode");
+    command_scopes.link("admin", "command.stop");
+    command_scopes.link("admin", "command.setwarp");
+    command_scopes.link("admin", "command.setworldspawn");
+    command_scopes.link("admin", "command.say");
+    command_scopes.link("admin", "command.mute");
+    command_scopes.link("admin", "command.scoreboard");
+    command_scopes.link("admin", "command.title");
+    command_scopes.link("admin", "command.bossbar");
+    command_scopes.link("admin", "command.summon");
+    command_scopes.link("admin", "command.kill");
+    command_scopes.link("admin", "command.give");
+    command_scopes.link("admin", "command.clear");
+    command_scopes.link("admin", "command.item");
+    command_scopes.link("admin", "player");
+    command_scopes.link("player", "command.spawn");
+    command_scopes.link("player", "command.tpa");
+    command_scopes.link("player", "command.back");
+    command_scopes.link("player", "command.home");
+    command_scopes.link("player", "command.warp");
+    command_scopes.link("player", "command.msg");
+    command_scopes.link("player", "command.me");
 
     let elapsed = current_time.elapsed().unwrap();
     info!("Server up in {:.2?}ms", elapsed.as_millis());
@@ -1,6 +1,6 @@
-use std::path::PathBuf;
+use std::{fmt, net::SocketAddr, num::NonZeroU32, path::PathBuf, time::Duration};
 
-use valence::{math::DVec3, prelude::Resource, GameMode};
+use valence::{math::DVec3, prelude::Resource, text::Color, GameMode};
 
 
 #[derive(Clone, Debug)]
@@ -29,10 +29,213 @@ pub struct Settings {
     ///
     /// Generated worlds will always be 384 high
     pub world_max_height: u32,
-    /// The default spawn point for every player
+    /// The spawn point of worlds that have never had one set
+    ///
+    /// `/setworldspawn` overrides it per layer
     pub spawn_point: DVec3,
+    /// The radius around the world spawn where only operators can break and
+    /// place blocks
+    ///
+    /// 0 disables spawn protection
+    pub spawn_protection: u32,
+    /// The players made operators when they join, by username or UUID
+    ///
+    /// Operators get op level 4 and the `admin` scope. Everyone else joins
+    /// with op level 0 and the `player` scope
+    pub ops: Vec<String>,
     /// The default gamemode for every player
     pub default_gamemode: GameMode,
+    /// The address and port the server listens on
+    ///
+    /// Use a different port per instance to run several servers on one host
+    pub address: SocketAddr,
+    /// Packets at least this many bytes long are compressed
+    ///
+    /// None disables compression entirely. Negative thresholds are rejected
+    pub compression_threshold: Option<i32>,
+    /// The number of game ticks per second
+    ///
+    /// Vanilla runs at 20
+    pub tick_rate: NonZeroU32,
+    /// The message shown to clients on the local network
+    ///
+    /// None disables LAN broadcasting
+    pub lan_broadcast: Option<String>,
+    /// The description shown in the server list and query responses
+    pub motd: String,
+    /// The maximum number of players allowed online at once
+    pub max_players: usize,
+    /// The UDP port to answer query (GameSpy4) requests on
+    ///
+    /// None disables the query listener
+    pub query_port: Option<u16>,
+    /// Whether to read commands from stdin
+    ///
+    /// Logs are printed above the prompt while the console is enabled
+    pub console: bool,
+    /// Remote console access
+    ///
+    /// None disables RCON
+    pub rcon: Option<RconSettings>,
+    /// How long a teleport request stays open before it expires
+    pub tpa_timeout: Duration,
+    /// How long a player must wait between sending teleport requests
+    pub tpa_cooldown: Duration,
+    /// How many previous locations `/back` remembers per player
+    pub back_history: usize,
+    /// The directory homes, warps and other server data are stored in
+    ///
+    /// Each world gets its own subdirectory
+    pub data_path: PathBuf,
+    /// How many homes players in each scope group may set
+    ///
+    /// A player gets the highest limit of the groups they are in
+    pub home_limits: Vec<(String, usize)>,
+    /// How chat messages are laid out
+    ///
+    /// `{prefix}`, `{name}` and `{message}` are filled in
+    pub chat_format: String,
+    /// The prefix and its colour shown before players in each scope group
+    ///
+    /// A player gets the prefix of the first group they are in
+    pub chat_prefixes: Vec<(String, String, Color)>,
+    /// Words replaced with asterisks in chat and private messages
+    ///
+    /// Matched as whole words, ignoring case
+    pub chat_filter: Vec<String>,
+    /// How many messages a player may send within a window of time
+    ///
+    /// None disables the rate limit
+    pub chat_rate_limit: Option<(usize, Duration)>,
+    /// Broadcast when a player joins
+    ///
+    /// `{name}`, `{online}` and `{max}` are filled in. None stays silent
+    pub join_message: Option<String>,
+    /// Broadcast instead of the join message the first time a player joins
+    pub first_join_message: Option<String>,
+    /// Broadcast when a player leaves, with the same placeholders
+    pub leave_message: Option<String>,
+    /// A text file whose lines are shown to each player when they join
+    ///
+    /// Supports the same placeholders as the join message
+    pub motd_path: Option<PathBuf>,
+    /// Shown above the tab list
+    ///
+    /// `{tps}`, `{online}`, `{max}`, `{world}` and `{motd}` are filled in
+    /// and kept up to date. None leaves it empty
+    pub tab_header: Option<String>,
+    /// Shown below the tab list, with the same placeholders as the header
+    pub tab_footer: Option<String>,
+    /// Movement and reach checks
+    ///
+    /// None trusts every move the clients make
+    pub anticheat: Option<AntiCheatSettings>,
+    /// Natural spawning of cows, sheep, pigs and chickens
+    ///
+    /// None disables it
+    pub passive_mobs: Option<MobSpawnSettings>,
+    /// Natural spawning of zombies, skeletons and creepers
+    ///
+    /// None disables it
+    pub hostile_mobs: Option<MobSpawnSettings>,
+    /// The `data/minecraft` directory of a data pack, such as the one in the
+    /// vanilla server jar
+    ///
+    /// Recipes are read from its `recipes` directory and the item tags they
+    /// use from `tags/items`. A relative path is looked for in the working
+    /// directory, then next to the executable, then in the world's data
+    /// directory. None disables crafting, and furnaces only smelt the
+    /// built-in recipes
+    ///
+    /// `assets/data/minecraft` only has a handful of recipes to start with.
+    /// For all of vanilla's, extract `data/minecraft` from the 1.20.1 server
+    /// jar (it's inside `META-INF/versions/1.20.1/server-1.20.1.jar`) and
+    /// point this at it
+    pub data_pack_path: Option<PathBuf>,
+}
+
+#[derive(Clone, Debug)]
+pub struct RconSettings {
+    /// The port RCON listens on, on the same interface as the server
+    pub port: u16,
+    /// The password clients must log in with
+    ///
+    /// An empty password rejects every login
+    pub password: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct AntiCheatSettings {
+    /// Blocks per second a player may move horizontally, averaged over a
+    /// second
+    ///
+    /// Sprint jumping is about 7, ice and speed effects go faster
+    pub max_speed: f64,
+    /// Blocks a player may move in a single packet
+    pub max_move: f64,
+    /// How far a player may rise above where they last stood without
+    /// something to climb or swim in
+    pub max_jump_height: f64,
+    /// Ticks a player may stay in the air without falling
+    pub max_hover_ticks: u32,
+    /// How far from their eyes players may break and place blocks
+    pub max_reach: f64,
+    /// How many violations within the window get a player kicked
+    ///
+    /// None only logs them
+    pub kick_after: Option<usize>,
+    pub violation_window: Duration,
+}
+
+#[derive(Clone, Debug)]
+pub struct MobSpawnSettings {
+    /// Ticks between spawn attempts around each player
+    pub interval: u32,
+    /// The most mobs of the category in one chunk
+    pub per_chunk: usize,
+    /// The most mobs of the category near one player
+    pub per_player: usize,
+}
+
+/// A setting with a value the server can't run with
+#[derive(Clone, Debug, PartialEq)]
+pub enum SettingsError {
+    NegativeCompressionThreshold(i32),
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SettingsError::NegativeCompressionThreshold(threshold) => write!(
+                f,
+                "compression_threshold can't be negative, got {threshold}; use None to disable"
+            ),
+        }
+    }
+}
+
+impl Settings {
+    /// Checks the settings the types alone don't rule out
+    pub fn validate(&self) -> Result<(), SettingsError> {
+        if let Some(threshold) = self.compression_threshold {
+            if threshold < 0 {
+                return Err(SettingsError::NegativeCompressionThreshold(threshold));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The name of the world, taken from its directory
+    ///
+    /// Generated worlds are called "world"
+    pub fn world_name(&self) -> String {
+        self.world_path
+            .as_ref()
+            .and_then(|path| path.file_name())
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "world".to_owned())
+    }
 }
 
 impl Resource for Settings {}
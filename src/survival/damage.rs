@@ -0,0 +1,295 @@
+use valence::{
+    entity::Look,
+    log::info,
+    prelude::*,
+    sound::{Sound, SoundCategory},
+    status::RequestRespawnEvent,
+    text::IntoText,
+};
+
+use super::{has_vitals, Vitals};
+use crate::{
+    commands::{
+        back::{BackHistory, BackLocation},
+        feedback::plain_text,
+        selector::entity_kind_name,
+        teleport::apply::{TeleportCause, TeleportEvent},
+    },
+    setup::settings::Settings,
+    world::spawn::WorldSpawn,
+};
+
+/// Ticks after taking damage during which further damage is ignored
+const INVULNERABLE_TICKS: u32 = 10;
+
+/// What hurt an entity, which decides the death message.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DamageSource {
+    Fall,
+    Drown,
+    Void,
+    Starve,
+    /// Attacked by another entity
+    Entity(Entity),
+    /// Shot by an entity
+    Arrow(Entity),
+    /// Caught in an explosion, set off by an entity if known
+    Explosion(Option<Entity>),
+    Generic,
+    /// `/kill`, which ignores game modes, cooldowns and invulnerability
+    Kill,
+}
+
+impl DamageSource {
+    /// The vanilla death message key, and the entity to name in it
+    fn death_message(self) -> (&'static str, Option<Entity>) {
+        match self {
+            DamageSource::Fall => ("death.fell.accident.generic", None),
+            DamageSource::Drown => ("death.attack.drown", None),
+            DamageSource::Void => ("death.attack.outOfWorld", None),
+            DamageSource::Starve => ("death.attack.starve", None),
+            DamageSource::Entity(attacker) => ("death.attack.mob", Some(attacker)),
+            DamageSource::Arrow(shooter) => ("death.attack.arrow", Some(shooter)),
+            DamageSource::Explosion(None) => ("death.attack.explosion", None),
+            DamageSource::Explosion(Some(attacker)) => {
+                ("death.attack.explosion.player", Some(attacker))
+            }
+            DamageSource::Generic => ("death.attack.generic", None),
+            DamageSource::Kill => ("death.attack.genericKill", None),
+        }
+    }
+}
+
+/// Hurts a player or a mob. Players are only hurt in Survival and Adventure,
+/// unless killed with `/kill`.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct DamageEvent {
+    pub entity: Entity,
+    pub amount: f32,
+    pub source: DamageSource,
+}
+
+/// Sent when a player dies, after the death screen is shown.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct DeathEvent {
+    pub entity: Entity,
+    pub source: DamageSource,
+}
+
+/// Ticks left before a player or mob can be hurt again
+#[derive(Component, Default)]
+pub struct HurtCooldown(u32);
+
+impl HurtCooldown {
+    /// Starts the cooldown, or returns false if it's still running
+    pub fn try_start(&mut self) -> bool {
+        if self.0 > 0 {
+            return false;
+        }
+
+        self.0 = INVULNERABLE_TICKS;
+        true
+    }
+}
+
+pub fn tick_hurt_cooldowns(mut cooldowns: Query<&mut HurtCooldown>) {
+    for mut cooldown in &mut cooldowns {
+        if cooldown.0 > 0 {
+            cooldown.0 -= 1;
+        }
+    }
+}
+
+/// Applies damage, and kills players whose health runs out.
+///
+/// Death messages are broadcast to everyone and the death location is
+/// remembered for `/back`.
+pub fn apply_damage(
+    mut commands: Commands,
+    mut events: EventReader<DamageEvent>,
+    mut players: Query<(
+        &mut Client,
+        &mut Vitals,
+        &GameMode,
+        &Username,
+        &Position,
+        &Look,
+        &EntityLayerId,
+        Option<&mut HurtCooldown>,
+        Option<&mut BackHistory>,
+    )>,
+    names: Query<(Option<&Username>, &EntityKind)>,
+    settings: Res<Settings>,
+    mut deaths: EventWriter<DeathEvent>,
+) {
+    let mut death_messages = vec![];
+
+    for event in events.read() {
+        let Ok((
+            mut client,
+            mut vitals,
+            game_mode,
+            username,
+            pos,
+            look,
+            layer,
+            cooldown,
+            history,
+        )) = players.get_mut(event.entity)
+        else {
+            continue;
+        };
+
+        let forced = event.source == DamageSource::Kill;
+        if (!forced && !has_vitals(*game_mode)) || vitals.dead || event.amount <= 0.0 {
+            continue;
+        }
+
+        match cooldown {
+            Some(mut cooldown) => {
+                if !cooldown.try_start() && !forced {
+                    continue;
+                }
+            }
+            None => {
+                commands
+                    .entity(event.entity)
+                    .insert(HurtCooldown(INVULNERABLE_TICKS));
+            }
+        }
+
+        vitals.health = (vitals.health - event.amount).max(0.0);
+        vitals.add_exhaustion(0.1);
+
+        client.play_sound(
+            Sound::EntityPlayerHurt,
+            SoundCategory::Player,
+            pos.0,
+            1.0,
+            1.0,
+        );
+
+        if vitals.health > 0.0 {
+            continue;
+        }
+
+        vitals.dead = true;
+
+        let (key, attacker) = event.source.death_message();
+        let mut with = vec![username.0.clone().into_text()];
+        if let Some(attacker) = attacker {
+            let name = match names.get(attacker) {
+                Ok((Some(username), _)) => username.0.clone(),
+                Ok((None, kind)) => entity_kind_name(*kind)
+                    .unwrap_or("something")
+                    .to_owned(),
+                Err(_) => "something".to_owned(),
+            };
+            with.push(name.into_text());
+        }
+        let message = Text::translate(key, with);
+
+        client.kill(message.clone());
+
+        if let Some(mut history) = history {
+            history.push(
+                BackLocation {
+                    position: pos.0,
+                    look: *look,
+                    layer: layer.0,
+                },
+                settings.back_history,
+            );
+        }
+
+        death_messages.push(message);
+
+        deaths.send(DeathEvent {
+            entity: event.entity,
+            source: event.source,
+        });
+    }
+
+    for message in death_messages {
+        info!("{}", plain_text(&message));
+
+        for (mut client, ..) in &mut players {
+            client.send_chat_message(message.clone());
+        }
+    }
+}
+
+/// Respawns dead players at their layer's spawn when they press respawn.
+///
+/// Marking the chunk layer as changed makes Valence send the respawn packet,
+/// even though the player stays on the same layer.
+pub fn respawn(
+    mut events: EventReader<RequestRespawnEvent>,
+    mut players: Query<(&mut Vitals, &EntityLayerId, &mut VisibleChunkLayer)>,
+    spawns: Query<&WorldSpawn>,
+    settings: Res<Settings>,
+    mut teleports: EventWriter<TeleportEvent>,
+) {
+    for event in events.read() {
+        let Ok((mut vitals, layer, mut visible_chunk_layer)) = players.get_mut(event.client)
+        else {
+            continue;
+        };
+
+        if !vitals.dead {
+            continue;
+        }
+
+        *vitals = Vitals::default();
+        visible_chunk_layer.set_changed();
+
+        let (position, look) = match spawns.get(layer.0) {
+            Ok(spawn) => (spawn.position, spawn.look),
+            Err(_) => (settings.spawn_point, Look::default()),
+        };
+
+        teleports.send(TeleportEvent {
+            look: Some(look),
+            safe: true,
+            cause: TeleportCause::Respawn,
+            ..TeleportEvent::new(event.client, position)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hurt_cooldown_blocks_until_it_runs_out() {
+        let mut cooldown = HurtCooldown::default();
+        assert!(cooldown.try_start());
+        assert!(!cooldown.try_start());
+
+        cooldown.0 = 0;
+        assert!(cooldown.try_start());
+    }
+
+    #[test]
+    fn death_messages_name_the_attacker() {
+        let attacker = Entity::from_raw(7);
+
+        assert_eq!(
+            DamageSource::Fall.death_message(),
+            ("death.fell.accident.generic", None)
+        );
+        assert_eq!(
+            DamageSource::Arrow(attacker).death_message(),
+            ("death.attack.arrow", Some(attacker))
+        );
+        assert_eq!(
+            DamageSource::Explosion(None).death_message(),
+            ("death.attack.explosion", None)
+        );
+        assert_eq!(
+            DamageSource::Explosion(Some(attacker)).death_message(),
+            ("death.attack.explosion.player", Some(attacker))
+        );
+    }
+}
@@ -0,0 +1,346 @@
+pub mod damage;
+
+use damage::{DamageEvent, DamageSource};
+use valence::{
+    block::{BlockKind, PropName, PropValue},
+    client_command::{SprintEvent, SprintState},
+    entity::entity::Air,
+    movement::MovementEvent,
+    prelude::*,
+    protocol::{packets::play::HealthUpdateS2c, VarInt, WritePacket},
+};
+
+use crate::{anticheat, commands::teleport::apply::TeleportedEvent};
+
+pub const MAX_HEALTH: f32 = 20.0;
+pub const MAX_FOOD: i32 = 20;
+/// Air in ticks, 15 seconds underwater
+pub const MAX_AIR: i32 = 300;
+
+/// Exhaustion that costs one point of saturation or food
+const EXHAUSTION_PER_FOOD: f32 = 4.0;
+/// Ticks between natural regeneration or starvation damage
+const FOOD_TICK_INTERVAL: u32 = 80;
+/// Blocks a player can fall before taking damage
+const SAFE_FALL_DISTANCE: f64 = 3.0;
+/// Ticks between void damage
+const VOID_DAMAGE_INTERVAL: u32 = 10;
+/// How far below the bottom of the world the void starts hurting
+const VOID_DEPTH: f64 = 64.0;
+const EYE_HEIGHT: f64 = 1.62;
+
+/// Health, hunger and air of a player, only used in Survival and Adventure.
+#[derive(Component, Clone, Debug)]
+pub struct Vitals {
+    pub health: f32,
+    pub food: i32,
+    pub saturation: f32,
+    pub exhaustion: f32,
+    pub air: i32,
+    /// How far the player has fallen since they last stood on the ground
+    pub fall_distance: f64,
+    pub sprinting: bool,
+    /// Set from death until the player respawns
+    pub dead: bool,
+    food_ticks: u32,
+    void_ticks: u32,
+}
+
+impl Default for Vitals {
+    fn default() -> Self {
+        Self {
+            health: MAX_HEALTH,
+            food: MAX_FOOD,
+            saturation: 5.0,
+            exhaustion: 0.0,
+            air: MAX_AIR,
+            fall_distance: 0.0,
+            sprinting: false,
+            dead: false,
+            food_ticks: 0,
+            void_ticks: 0,
+        }
+    }
+}
+
+impl Vitals {
+    pub fn add_exhaustion(&mut self, amount: f32) {
+        self.exhaustion = (self.exhaustion + amount).min(40.0);
+    }
+
+    pub fn heal(&mut self, amount: f32) {
+        if !self.dead {
+            self.health = (self.health + amount).min(MAX_HEALTH);
+        }
+    }
+}
+
+/// Whether vitals matter in a game mode
+pub fn has_vitals(game_mode: GameMode) -> bool {
+    matches!(game_mode, GameMode::Survival | GameMode::Adventure)
+}
+
+pub fn init_vitals(mut commands: Commands, clients: Query<Entity, Added<Client>>) {
+    for entity in &clients {
+        commands.entity(entity).insert(Vitals::default());
+    }
+}
+
+pub fn track_sprinting(mut events: EventReader<SprintEvent>, mut players: Query<&mut Vitals>) {
+    for event in events.read() {
+        if let Ok(mut vitals) = players.get_mut(event.client) {
+            vitals.sprinting = event.state == SprintState::Start;
+        }
+    }
+}
+
+/// The damage for landing after falling `distance` blocks, if it hurts
+fn fall_damage(distance: f64) -> Option<f32> {
+    (distance > SAFE_FALL_DISTANCE).then(|| (distance - SAFE_FALL_DISTANCE).ceil() as f32)
+}
+
+/// Tracks falls and exhaustion from moving, and deals fall damage on landing.
+///
+/// Whether the player is on the ground comes from the blocks under them, not
+/// their client. Liquids and climbable blocks break a fall without damage.
+pub fn track_movement(
+    mut events: EventReader<MovementEvent>,
+    mut players: Query<(&mut Vitals, &GameMode, &EntityLayerId)>,
+    layers: Query<&ChunkLayer>,
+    mut damage: EventWriter<DamageEvent>,
+) {
+    for event in events.read() {
+        let Ok((mut vitals, game_mode, layer_id)) = players.get_mut(event.client) else {
+            continue;
+        };
+
+        // Only written when it changes, as `sync_vitals` runs on `Changed<Vitals>`
+        if !has_vitals(*game_mode) || vitals.dead {
+            if vitals.fall_distance != 0.0 {
+                vitals.fall_distance = 0.0;
+            }
+            continue;
+        }
+
+        let Ok(layer) = layers.get(layer_id.0) else {
+            continue;
+        };
+
+        let delta = event.position - event.old_position;
+        let on_ground = anticheat::on_ground(layer, event.position);
+
+        if anticheat::can_climb(layer, event.position) {
+            if vitals.fall_distance != 0.0 {
+                vitals.fall_distance = 0.0;
+            }
+        } else if on_ground {
+            if let Some(amount) = fall_damage(vitals.fall_distance) {
+                damage.send(DamageEvent {
+                    entity: event.client,
+                    amount,
+                    source: DamageSource::Fall,
+                });
+            }
+            if vitals.fall_distance != 0.0 {
+                vitals.fall_distance = 0.0;
+            }
+        } else if delta.y < 0.0 {
+            vitals.fall_distance -= delta.y;
+        }
+
+        // Leaving the ground upwards is a jump
+        if !on_ground && delta.y > 0.0 && anticheat::on_ground(layer, event.old_position) {
+            vitals.add_exhaustion(if vitals.sprinting { 0.2 } else { 0.05 });
+        }
+
+        if vitals.sprinting {
+            let horizontal = (delta.x * delta.x + delta.z * delta.z).sqrt();
+            vitals.add_exhaustion(0.1 * horizontal as f32);
+        }
+    }
+}
+
+/// A teleport shouldn't count as falling
+pub fn reset_fall_on_teleport(
+    mut events: EventReader<TeleportedEvent>,
+    mut players: Query<&mut Vitals>,
+) {
+    for event in events.read() {
+        let Ok(mut vitals) = players.get_mut(event.entity) else {
+            continue;
+        };
+        if vitals.fall_distance != 0.0 {
+            vitals.fall_distance = 0.0;
+        }
+    }
+}
+
+fn is_water(state: BlockState) -> bool {
+    state.to_kind() == BlockKind::Water
+        || state.to_kind() == BlockKind::BubbleColumn
+        || state.get(PropName::Waterlogged) == Some(PropValue::True)
+}
+
+/// Runs hunger, regeneration, starvation, drowning and the void every tick.
+///
+/// The timers are updated without change detection, so vitals are only sent
+/// to the client when something visible changed.
+pub fn tick_vitals(
+    mut players: Query<(Entity, &mut Vitals, &GameMode, &Position, &EntityLayerId)>,
+    layers: Query<&ChunkLayer>,
+    mut damage: EventWriter<DamageEvent>,
+) {
+    for (entity, mut vitals, game_mode, pos, layer_id) in &mut players {
+        if !has_vitals(*game_mode) || vitals.dead {
+            if vitals.air != MAX_AIR {
+                vitals.air = MAX_AIR;
+            }
+            continue;
+        }
+
+        let Ok(layer) = layers.get(layer_id.0) else {
+            continue;
+        };
+
+        // Air
+        let eyes = BlockPos::new(
+            pos.0.x.floor() as i32,
+            (pos.0.y + EYE_HEIGHT).floor() as i32,
+            pos.0.z.floor() as i32,
+        );
+        let underwater = layer.block(eyes).is_some_and(|block| is_water(block.state));
+
+        if underwater {
+            vitals.air -= 1;
+            if vitals.air <= -20 {
+                vitals.air = 0;
+                damage.send(DamageEvent {
+                    entity,
+                    amount: 2.0,
+                    source: DamageSource::Drown,
+                });
+            }
+        } else if vitals.air < MAX_AIR {
+            vitals.air = (vitals.air.max(0) + 4).min(MAX_AIR);
+        }
+
+        // The void
+        if pos.0.y < f64::from(layer.min_y()) - VOID_DEPTH {
+            vitals.bypass_change_detection().void_ticks += 1;
+            if vitals.void_ticks >= VOID_DAMAGE_INTERVAL {
+                vitals.bypass_change_detection().void_ticks = 0;
+                damage.send(DamageEvent {
+                    entity,
+                    amount: 4.0,
+                    source: DamageSource::Void,
+                });
+            }
+        } else {
+            vitals.bypass_change_detection().void_ticks = 0;
+        }
+
+        // Hunger
+        if vitals.exhaustion >= EXHAUSTION_PER_FOOD {
+            vitals.exhaustion -= EXHAUSTION_PER_FOOD;
+            if vitals.saturation > 0.0 {
+                vitals.saturation = (vitals.saturation - 1.0).max(0.0);
+            } else {
+                vitals.food = (vitals.food - 1).max(0);
+            }
+        }
+
+        // Natural regeneration and starvation, as on normal difficulty
+        vitals.bypass_change_detection().food_ticks += 1;
+        if vitals.food_ticks >= FOOD_TICK_INTERVAL {
+            vitals.bypass_change_detection().food_ticks = 0;
+
+            if vitals.food >= 18 && vitals.health < MAX_HEALTH {
+                vitals.heal(1.0);
+                vitals.add_exhaustion(6.0);
+            } else if vitals.food == 0 && vitals.health > 1.0 {
+                damage.send(DamageEvent {
+                    entity,
+                    amount: 1.0,
+                    source: DamageSource::Starve,
+                });
+            }
+        }
+    }
+}
+
+/// Sends changed vitals to the player's HUD.
+pub fn sync_vitals(mut players: Query<(&mut Client, &Vitals, &mut Air), Changed<Vitals>>) {
+    for (mut client, vitals, mut air) in &mut players {
+        client.write_packet(&HealthUpdateS2c {
+            health: vitals.health,
+            food: VarInt(vitals.food),
+            food_saturation: vitals.saturation,
+        });
+
+        if air.0 != vitals.air {
+            air.0 = vitals.air;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_falls_are_safe() {
+        assert_eq!(fall_damage(0.0), None);
+        assert_eq!(fall_damage(SAFE_FALL_DISTANCE), None);
+    }
+
+    #[test]
+    fn fall_damage_rounds_up_per_block() {
+        assert_eq!(fall_damage(3.2), Some(1.0));
+        assert_eq!(fall_damage(4.0), Some(1.0));
+        assert_eq!(fall_damage(23.0), Some(20.0));
+    }
+
+    #[test]
+    fn healing_stops_at_max_health_and_death() {
+        let mut vitals = Vitals {
+            health: 15.0,
+            ..Vitals::default()
+        };
+        vitals.heal(10.0);
+        assert_eq!(vitals.health, MAX_HEALTH);
+
+        vitals.health = 0.0;
+        vitals.dead = true;
+        vitals.heal(5.0);
+        assert_eq!(vitals.health, 0.0);
+    }
+
+    #[test]
+    fn exhaustion_is_capped() {
+        let mut vitals = Vitals::default();
+        vitals.add_exhaustion(0.1);
+        assert!((vitals.exhaustion - 0.1).abs() < f32::EPSILON);
+
+        vitals.add_exhaustion(100.0);
+        assert_eq!(vitals.exhaustion, 40.0);
+    }
+
+    #[test]
+    fn only_survival_and_adventure_have_vitals() {
+        assert!(has_vitals(GameMode::Survival));
+        assert!(has_vitals(GameMode::Adventure));
+        assert!(!has_vitals(GameMode::Creative));
+        assert!(!has_vitals(GameMode::Spectator));
+    }
+
+    #[test]
+    fn waterlogged_blocks_are_water() {
+        assert!(is_water(BlockState::WATER));
+        assert!(is_water(BlockState::BUBBLE_COLUMN));
+        assert!(is_water(
+            BlockState::OAK_SLAB.set(PropName::Waterlogged, PropValue::True)
+        ));
+        assert!(!is_water(BlockState::OAK_SLAB));
+        assert!(!is_water(BlockState::LAVA));
+    }
+}
@@ -1,9 +1,11 @@
-use std::{collections::{hash_map::Entry, HashMap}, sync::Arc};
+use std::{collections::{hash_map::Entry, HashMap}, sync::Arc, thread::JoinHandle};
 
 use flume::{Receiver, Sender};
 use noise::{NoiseFn, SuperSimplex};
 use valence::{log::info, prelude::*};
 
+use crate::{commands::teleport::apply::PendingTeleports, containers::store::ContainerStore};
+
 
 /// FROM VALENCE EXAMPLE
 /// https://github.com/valence-rs/valence/blob/main/examples/terrain.rs
@@ -32,11 +34,40 @@ pub struct GameState {
     pub receiver: Receiver<(ChunkPos, UnloadedChunk)>,
 }
 
+/// Handles to the chunk generation threads, kept so they can be joined on
+/// shutdown.
+#[derive(Resource)]
+pub struct ChunkWorkers {
+    pub state: Arc<ChunkWorkerState>,
+    pub handles: Vec<JoinHandle<()>>,
+}
+
+impl ChunkWorkers {
+    /// Discards queued chunks and waits for every worker to exit
+    ///
+    /// The workers only exit once the `GameState` sender has been dropped.
+    /// Returns the number of workers joined
+    pub fn join(self) -> usize {
+        self.state.receiver.drain().for_each(drop);
+
+        let count = self.handles.len();
+        for handle in self.handles {
+            let _ = handle.join();
+        }
+
+        count
+    }
+}
 
-pub fn remove_unviewed_chunks(mut layers: Query<&mut ChunkLayer>) {
-    layers
-        .single_mut()
-        .retain_chunks(|_, chunk| chunk.viewer_count_mut() > 0);
+/// Unloads chunks nobody can see, keeping the ones teleports are waiting for
+/// so they're still there when the teleport is applied.
+pub fn remove_unviewed_chunks(
+    mut layers: Query<&mut ChunkLayer>,
+    teleports: Res<PendingTeleports>,
+) {
+    layers.single_mut().retain_chunks(|pos, chunk| {
+        chunk.viewer_count_mut() > 0 || teleports.is_waiting_for(pos)
+    });
 }
 
 pub fn update_client_views(
@@ -77,13 +108,18 @@ pub fn update_client_views(
     }
 }
 
-pub fn send_recv_chunks(mut layers: Query<&mut ChunkLayer>, state: ResMut<GameState>) {
+pub fn send_recv_chunks(
+    mut layers: Query<&mut ChunkLayer>,
+    state: ResMut<GameState>,
+    mut store: ResMut<ContainerStore>,
+) {
     let mut layer = layers.single_mut();
     let state = state.into_inner();
 
     // Insert the chunks that are finished generating into the instance.
     for (pos, chunk) in state.receiver.drain() {
         layer.insert_chunk(pos, chunk);
+        store.restore(&mut layer, pos);
         assert!(state.pending.remove(&pos).is_some());
     }
 
@@ -0,0 +1,191 @@
+use valence::{
+    block::BlockKind,
+    particle::Particle,
+    prelude::*,
+    rand::{self, Rng},
+    sound::{Sound, SoundCategory},
+};
+
+use crate::{
+    containers::{self, store::ContainerStore, ContainerInventories, ContainerKind},
+    mobs::Mob,
+    setup::settings::Settings,
+    survival::{
+        damage::{DamageEvent, DamageSource},
+        Vitals,
+    },
+    world::spawn::WorldSpawn,
+};
+
+/// Blocks explosions can't destroy
+const BLAST_PROOF: &[BlockKind] = &[
+    BlockKind::Bedrock,
+    BlockKind::Obsidian,
+    BlockKind::CryingObsidian,
+    BlockKind::RespawnAnchor,
+    BlockKind::EnchantingTable,
+    BlockKind::Anvil,
+    BlockKind::ChippedAnvil,
+    BlockKind::DamagedAnvil,
+    BlockKind::NetheriteBlock,
+    BlockKind::AncientDebris,
+    BlockKind::ReinforcedDeepslate,
+    BlockKind::EndPortalFrame,
+    BlockKind::EndPortal,
+    BlockKind::EndGateway,
+    BlockKind::Barrier,
+    BlockKind::CommandBlock,
+    BlockKind::ChainCommandBlock,
+    BlockKind::RepeatingCommandBlock,
+    BlockKind::StructureBlock,
+    BlockKind::Jigsaw,
+    BlockKind::Water,
+    BlockKind::Lava,
+];
+
+/// Blows up blocks and hurts entities around a point.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct ExplosionEvent {
+    pub layer: Entity,
+    pub position: DVec3,
+    /// 3 for a creeper, 4 for TNT
+    pub power: f64,
+    /// What set the explosion off, named in death messages
+    pub source: Option<Entity>,
+}
+
+/// Damage at `distance` from an explosion, as in vanilla without blocks
+/// shielding the entity
+fn damage_at(power: f64, distance: f64) -> f32 {
+    let impact = 1.0 - distance / (power * 2.0);
+    if impact <= 0.0 {
+        return 0.0;
+    }
+
+    ((impact * impact + impact) / 2.0 * 7.0 * power * 2.0 + 1.0) as f32
+}
+
+/// Destroys the blocks in a roughly spherical area and damages players and
+/// mobs nearby.
+///
+/// Blocks inside spawn protection are left alone. Containers that are blown
+/// up spill their contents.
+pub fn apply_explosions(
+    mut commands: Commands,
+    mut events: EventReader<ExplosionEvent>,
+    mut layers: Query<(&mut ChunkLayer, Option<&WorldSpawn>)>,
+    mut containers: ContainerInventories,
+    entities: Query<(Entity, &Position, &EntityLayerId), Or<(With<Vitals>, With<Mob>)>>,
+    settings: Res<Settings>,
+    mut damage: EventWriter<DamageEvent>,
+    mut store: ResMut<ContainerStore>,
+) {
+    let mut rng = rand::thread_rng();
+
+    for event in events.read() {
+        let Ok((mut layer, spawn)) = layers.get_mut(event.layer) else {
+            continue;
+        };
+
+        let radius = event.power * rng.gen_range(0.7..1.3);
+        let reach = radius.ceil() as i32;
+        let centre = BlockPos::new(
+            event.position.x.floor() as i32,
+            event.position.y.floor() as i32,
+            event.position.z.floor() as i32,
+        );
+
+        for y in -reach..=reach {
+            for z in -reach..=reach {
+                for x in -reach..=reach {
+                    let pos = BlockPos::new(centre.x + x, centre.y + y, centre.z + z);
+                    let offset = DVec3::new(f64::from(x), f64::from(y), f64::from(z));
+
+                    // Ragged edges, like vanilla's rays losing strength
+                    if offset.length() > radius - rng.gen_range(0.0..1.0) {
+                        continue;
+                    }
+                    if spawn.is_some_and(|spawn| spawn.protects(pos, settings.spawn_protection)) {
+                        continue;
+                    }
+
+                    let breakable = layer.block(pos).is_some_and(|block| {
+                        !block.state.is_air() && !BLAST_PROOF.contains(&block.state.to_kind())
+                    });
+                    if !breakable {
+                        continue;
+                    }
+
+                    let Some(prev) = layer.set_block(pos, BlockState::AIR) else {
+                        continue;
+                    };
+                    if ContainerKind::of(prev.state.to_kind()).is_some() {
+                        containers::drop_contents(
+                            &mut commands,
+                            &mut containers,
+                            event.layer,
+                            pos,
+                            prev.nbt.as_ref(),
+                        );
+                        store.record(pos, BlockState::AIR, None);
+                    }
+                }
+            }
+        }
+
+        layer.play_sound(
+            Sound::EntityGenericExplode,
+            SoundCategory::Block,
+            event.position,
+            4.0,
+            rng.gen_range(0.7..0.9),
+        );
+        layer.play_particle(
+            &Particle::ExplosionEmitter,
+            true,
+            event.position,
+            [0.0, 0.0, 0.0],
+            0.0,
+            1,
+        );
+
+        for (entity, pos, layer_id) in &entities {
+            if layer_id.0 != event.layer || Some(entity) == event.source {
+                continue;
+            }
+
+            let amount = damage_at(event.power, pos.0.distance(event.position));
+            if amount > 0.0 {
+                damage.send(DamageEvent {
+                    entity,
+                    amount,
+                    source: DamageSource::Explosion(event.source),
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn damage_is_highest_at_the_centre() {
+        assert_eq!(damage_at(3.0, 0.0), 43.0);
+        assert_eq!(damage_at(3.0, 3.0), 16.75);
+    }
+
+    #[test]
+    fn damage_falls_off_with_distance() {
+        let damage: Vec<f32> = (0..6).map(|d| damage_at(3.0, f64::from(d))).collect();
+        assert!(damage.windows(2).all(|pair| pair[0] > pair[1]));
+    }
+
+    #[test]
+    fn nothing_is_hurt_beyond_twice_the_power() {
+        assert_eq!(damage_at(3.0, 6.0), 0.0);
+        assert_eq!(damage_at(3.0, 20.0), 0.0);
+        assert!(damage_at(4.0, 6.0) > 0.0);
+    }
+}
@@ -2,26 +2,133 @@ use valence::{
     anvil::{AnvilLevel, ChunkLoadEvent, ChunkLoadStatus}, message::SendMessage, prelude::*, text::{Color, IntoText}, ChunkLayer
 };
 
-use crate::setup::settings::Settings;
+use crate::{containers::store::ContainerStore, setup::settings::Settings};
 pub mod chunks;
+pub mod explosion;
+pub mod spawn;
+pub mod storage;
 
 
+/// The chunk containing a position in world space
+pub fn chunk_pos_at(pos: DVec3) -> ChunkPos {
+    ChunkPos::new(
+        (pos.x / 16.0).floor() as i32,
+        (pos.z / 16.0).floor() as i32,
+    )
+}
+
+/// Read access to the blocks of a layer, for code that only looks at blocks
+/// and can be tested without a server
+pub trait BlockView {
+    /// The block at `pos`, or None if its chunk isn't loaded
+    fn state_at(&self, pos: BlockPos) -> Option<BlockState>;
+    fn min_y(&self) -> i32;
+    fn height(&self) -> u32;
+}
+
+impl BlockView for ChunkLayer {
+    fn state_at(&self, pos: BlockPos) -> Option<BlockState> {
+        self.block(pos).map(|block| block.state)
+    }
+
+    fn min_y(&self) -> i32 {
+        ChunkLayer::min_y(self)
+    }
+
+    fn height(&self) -> u32 {
+        ChunkLayer::height(self)
+    }
+}
+
+#[cfg(test)]
+pub mod test_blocks {
+    use std::collections::HashMap;
+
+    use valence::prelude::*;
+
+    use super::BlockView;
+
+    /// An overworld sized layer with every chunk loaded, air wherever
+    /// nothing was set
+    #[derive(Default)]
+    pub struct TestBlocks(HashMap<BlockPos, BlockState>);
+
+    impl TestBlocks {
+        pub fn set(&mut self, pos: impl Into<BlockPos>, state: BlockState) -> &mut Self {
+            self.0.insert(pos.into(), state);
+            self
+        }
+    }
+
+    impl BlockView for TestBlocks {
+        fn state_at(&self, pos: BlockPos) -> Option<BlockState> {
+            let in_height = pos.y >= self.min_y() && pos.y < self.min_y() + self.height() as i32;
+            in_height.then(|| self.0.get(&pos).copied().unwrap_or(BlockState::AIR))
+        }
+
+        fn min_y(&self) -> i32 {
+            -64
+        }
+
+        fn height(&self) -> u32 {
+            384
+        }
+    }
+}
+
+/// Finds the highest spot in the column at `pos` where a player can stand
+/// with room for their head.
+///
+/// Returns the centre of the block above the floor, or None if the chunk
+/// isn't loaded. If the surface is a liquid the player is put on top of it.
+pub fn safe_surface(layer: &ChunkLayer, pos: DVec3) -> Option<DVec3> {
+    layer.chunk(chunk_pos_at(pos))?;
+
+    let x = pos.x.floor() as i32;
+    let z = pos.z.floor() as i32;
+    let min_y = layer.min_y();
+    let max_y = min_y + layer.height() as i32 - 1;
+
+    let state_at = |y: i32| {
+        layer
+            .block(BlockPos::new(x, y, z))
+            .map_or(BlockState::AIR, |block| block.state)
+    };
+    let is_open = |state: BlockState| !state.blocks_motion() && !state.is_liquid();
+
+    for y in (min_y..max_y - 1).rev() {
+        let floor = state_at(y);
+        if !(floor.blocks_motion() || floor.is_liquid()) {
+            continue;
+        }
+
+        if is_open(state_at(y + 1)) && is_open(state_at(y + 2)) {
+            return Some(DVec3::new(f64::from(x) + 0.5, f64::from(y + 1), f64::from(z) + 0.5));
+        }
+    }
+
+    None
+}
+
 pub fn handle_chunk_loads_anvil(
     mut events: EventReader<ChunkLoadEvent>,
     mut layers: Query<&mut ChunkLayer, With<AnvilLevel>>,
     settings: Res<Settings>,
+    mut store: ResMut<ContainerStore>,
 ) {
     let mut layer = layers.single_mut();
 
     for event in events.read() {
         match &event.status {
-            ChunkLoadStatus::Success { .. } => {}
+            // Containers changed since the world was saved are put back
+            ChunkLoadStatus::Success { .. } => store.restore(&mut layer, event.pos),
             ChunkLoadStatus::Empty => {
                 // There's no chunk here so let's insert an empty chunk. If we were doing
                 // terrain generation we would prepare that here.
                 let mut chunk = UnloadedChunk::new();
                 chunk.set_height(settings.world_max_height);
                 layer.insert_chunk(event.pos, chunk);
+                store.restore(&mut layer, event.pos);
             }
             ChunkLoadStatus::Failed(e) => {
                 let errmsg = format!(
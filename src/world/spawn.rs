@@ -0,0 +1,98 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use valence::{entity::Look, prelude::*};
+
+use crate::{
+    server::shutdown::ShutdownEvent,
+    setup::settings::Settings,
+    world::storage::{self, SavedLocation},
+};
+
+/// The name a layer's data is saved under, e.g. "overworld"
+#[derive(Component, Clone, Debug)]
+pub struct LayerName(pub String);
+
+/// Where players spawn on a layer, set with `/setworldspawn`.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct WorldSpawn {
+    pub position: DVec3,
+    pub look: Look,
+}
+
+impl WorldSpawn {
+    /// Whether a block is within `radius` blocks of the spawn horizontally,
+    /// measured like vanilla spawn protection
+    pub fn protects(&self, pos: BlockPos, radius: u32) -> bool {
+        if radius == 0 {
+            return false;
+        }
+
+        let dx = (pos.x - self.position.x.floor() as i32).unsigned_abs();
+        let dz = (pos.z - self.position.z.floor() as i32).unsigned_abs();
+
+        dx.max(dz) <= radius
+    }
+}
+
+/// The saved spawn of every layer by name, kept in `spawns.json` in the
+/// world's data directory.
+#[derive(Resource)]
+pub struct SpawnPoints {
+    path: PathBuf,
+    spawns: HashMap<String, SavedLocation>,
+}
+
+impl SpawnPoints {
+    /// Stores a layer's new spawn and saves it to disk
+    pub fn set(&mut self, layer: &LayerName, spawn: &WorldSpawn) {
+        self.spawns.insert(
+            layer.0.clone(),
+            SavedLocation::new(layer, spawn.position, spawn.look),
+        );
+        self.save();
+    }
+
+    fn save(&self) {
+        storage::save(&self.path, &self.spawns);
+    }
+}
+
+pub fn load_spawn_points(mut commands: Commands, settings: Res<Settings>) {
+    let path = storage::data_dir(&settings).join("spawns.json");
+
+    commands.insert_resource(SpawnPoints {
+        spawns: storage::load(&path),
+        path,
+    });
+}
+
+/// Writes spawns out one last time when the server stops
+pub fn save_spawn_points(spawn_points: Res<SpawnPoints>, mut shutdown: EventReader<ShutdownEvent>) {
+    if shutdown.read().next().is_some() {
+        spawn_points.save();
+    }
+}
+
+/// Gives new layers their saved spawn, or the configured spawn point if they
+/// have never had one set.
+pub fn init_world_spawns(
+    mut commands: Commands,
+    layers: Query<(Entity, &LayerName), Without<WorldSpawn>>,
+    spawn_points: Res<SpawnPoints>,
+    settings: Res<Settings>,
+) {
+    for (entity, name) in &layers {
+        let spawn = match spawn_points.spawns.get(&name.0) {
+            Some(saved) => WorldSpawn {
+                position: saved.position(),
+                look: saved.look(),
+            },
+            None => WorldSpawn {
+                position: settings.spawn_point,
+                look: Look::default(),
+            },
+        };
+
+        commands.entity(entity).insert(spawn);
+    }
+}
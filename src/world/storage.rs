@@ -0,0 +1,157 @@
+use std::{
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use valence::{entity::Look, log::error, math::DVec3, prelude::Entity};
+
+use crate::{setup::settings::Settings, world::spawn::LayerName};
+
+/// A saved position and facing direction on a layer
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SavedLocation {
+    /// The `LayerName` of the layer the location is on
+    pub layer: String,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub yaw: f32,
+    pub pitch: f32,
+}
+
+impl SavedLocation {
+    pub fn new(layer: &LayerName, pos: DVec3, look: Look) -> Self {
+        Self {
+            layer: layer.0.clone(),
+            x: pos.x,
+            y: pos.y,
+            z: pos.z,
+            yaw: look.yaw,
+            pitch: look.pitch,
+        }
+    }
+
+    /// The layer the location is on among `layers`, if it's loaded
+    pub fn find_layer<'l>(
+        &self,
+        mut layers: impl Iterator<Item = (Entity, &'l LayerName)>,
+    ) -> Option<Entity> {
+        layers
+            .find(|(_, layer)| layer.0 == self.layer)
+            .map(|(entity, _)| entity)
+    }
+
+    pub fn position(&self) -> DVec3 {
+        DVec3::new(self.x, self.y, self.z)
+    }
+
+    pub fn look(&self) -> Look {
+        Look {
+            yaw: self.yaw,
+            pitch: self.pitch,
+        }
+    }
+}
+
+/// The directory server data for the current world is kept in
+pub fn data_dir(settings: &Settings) -> PathBuf {
+    settings.data_path.join(settings.world_name())
+}
+
+/// Reads a JSON file, falling back to the default value if it is missing.
+///
+/// A file that can't be read or parsed is moved aside to `*.json.corrupt`
+/// first, so the default saved over it later doesn't destroy what was in it.
+/// If it can't be moved either, the server stops rather than risk that.
+pub fn load<T: DeserializeOwned + Default>(path: &Path) -> T {
+    let error = match fs::read_to_string(path) {
+        Ok(contents) => match serde_json::from_str(&contents) {
+            Ok(value) => return value,
+            Err(e) => e.to_string(),
+        },
+        Err(e) if e.kind() == ErrorKind::NotFound => return T::default(),
+        Err(e) => e.to_string(),
+    };
+
+    let aside = path.with_extension("json.corrupt");
+    if let Err(e) = fs::rename(path, &aside) {
+        panic!(
+            "{} is unreadable ({error}) and couldn't be moved aside: {e}",
+            path.display()
+        );
+    }
+
+    error!(
+        "failed to read {} ({error}), moved it to {} and started fresh",
+        path.display(),
+        aside.display()
+    );
+    T::default()
+}
+
+/// Writes a JSON file, replacing it atomically so a crash mid-write can't
+/// corrupt it
+pub fn save<T: Serialize>(path: &Path, value: &T) {
+    let result = (|| -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let json = serde_json::to_string_pretty(value)?;
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, json)?;
+        fs::rename(&tmp, path)
+    })();
+
+    if let Err(e) = result {
+        error!("failed to save {}: {e}", path.display());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    /// A fresh directory to keep a test's files in
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("storage-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn saved_values_load_back() {
+        let path = test_dir("round_trip").join("values.json");
+        let values = BTreeMap::from([("a".to_owned(), 1), ("b".to_owned(), 2)]);
+
+        save(&path, &values);
+
+        assert_eq!(load::<BTreeMap<String, i32>>(&path), values);
+    }
+
+    #[test]
+    fn missing_files_load_as_default() {
+        let path = test_dir("missing").join("values.json");
+
+        assert_eq!(load::<Vec<i32>>(&path), Vec::new());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn corrupt_files_are_moved_aside() {
+        let path = test_dir("corrupt").join("values.json");
+        fs::write(&path, "[1, 2,").unwrap();
+
+        assert_eq!(load::<Vec<i32>>(&path), Vec::new());
+        assert!(!path.exists());
+        assert_eq!(
+            fs::read_to_string(path.with_extension("json.corrupt")).unwrap(),
+            "[1, 2,"
+        );
+    }
+}